@@ -42,6 +42,18 @@ fn decreasing_put(n: u64) -> Automerge {
     doc
 }
 
+fn decreasing_put_all(n: u64) -> Automerge {
+    let mut doc = Automerge::new();
+    let mut tx = doc.transaction();
+    tx.put_all(
+        ROOT,
+        (0..n).rev().map(|i| (i.to_string(), ScalarValue::from(i))),
+    )
+    .unwrap();
+    tx.commit();
+    doc
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let sizes = [100, 1_000, 10_000];
 
@@ -70,6 +82,13 @@ fn criterion_benchmark(c: &mut Criterion) {
             size,
             |b, &size| b.iter(|| decreasing_put(size)),
         );
+
+        group.throughput(criterion::Throughput::Elements(*size));
+        group.bench_with_input(
+            BenchmarkId::new("decreasing put_all", size),
+            size,
+            |b, &size| b.iter(|| decreasing_put_all(size)),
+        );
     }
     group.finish();
 
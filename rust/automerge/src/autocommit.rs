@@ -1,18 +1,24 @@
+use std::collections::HashMap;
 use std::ops::RangeBounds;
 
+use smol_str::SmolStr;
+
 use crate::automerge::SaveOptions;
 use crate::automerge::{current_state, diff};
 use crate::exid::ExId;
 use crate::hydrate;
-use crate::iter::{Keys, ListRange, MapRange, Values};
-use crate::marks::{ExpandMark, Mark};
+use crate::iter::{Keys, ListRange, MapRange, TopologicalChanges, Values, Words};
+use crate::marks::{ExpandMark, Mark, Span};
 use crate::patches::{PatchLog, TextRepresentation};
 use crate::sync::SyncDoc;
 use crate::transaction::{CommitOptions, Transactable};
-use crate::{sync, ObjType, Parents, Patch, ReadDoc, ScalarValue};
 use crate::{
-    transaction::TransactionInner, ActorId, Automerge, AutomergeError, Change, ChangeHash, Cursor,
-    Prop, Value,
+    sync, ApplyReport, DocumentVisitor, ListDiffEntry, MapDiffEntry, ObjType, OpType, Parents,
+    Patch, ReadDoc, ScalarValue, StateVector,
+};
+use crate::{
+    transaction::TransactionInner, ActorId, Automerge, AutomergeError, Change, ChangeHash,
+    ChangeNode, Cursor, Prop, Value,
 };
 
 /// An automerge document that automatically manages transactions.
@@ -100,6 +106,20 @@ impl AutoCommit {
         })
     }
 
+    /// Load a document from a stream of bytes
+    ///
+    /// See [`Automerge::load_from`]
+    pub fn load_from<R: std::io::Read>(reader: R) -> Result<Self, AutomergeError> {
+        let doc = Automerge::load_from(reader)?;
+        Ok(Self {
+            doc,
+            transaction: None,
+            patch_log: PatchLog::inactive(TextRepresentation::default()),
+            diff_cursor: Vec::new(),
+            save_cursor: Vec::new(),
+        })
+    }
+
     /// Erases the diff cursor created by [`Self::update_diff_cursor`] and no
     /// longer indexes changes to the document.
     pub fn reset_diff_cursor(&mut self) {
@@ -207,6 +227,121 @@ impl AutoCommit {
         patches
     }
 
+    /// Describe how the elements of the list `obj` differ between `before` and `after`, see
+    /// [`Automerge::list_diff`]
+    pub fn list_diff<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        before: &[ChangeHash],
+        after: &[ChangeHash],
+    ) -> Result<Vec<ListDiffEntry>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.list_diff(obj, before, after)
+    }
+
+    /// Describe how the keys of the map `obj` differ between `before` and `after`, see
+    /// [`Automerge::map_diff`]
+    pub fn map_diff<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        before: &[ChangeHash],
+        after: &[ChangeHash],
+    ) -> Result<Vec<MapDiffEntry>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.map_diff(obj, before, after)
+    }
+
+    /// The current state of this document as JSON, see [`Automerge::export_json`]
+    #[cfg(feature = "json")]
+    pub fn export_json(&self) -> serde_json::Value {
+        crate::automerge::json::json_at(self, Value::Object(ObjType::Map), crate::ROOT, None)
+            .expect("the root and objects found in the document should exist")
+    }
+
+    /// Apply a Quill Delta to the text object `obj` as a single change, see
+    /// [`Automerge::text_apply_quill_delta`]
+    ///
+    /// Any pending operations are committed first.
+    #[cfg(feature = "json")]
+    pub fn text_apply_quill_delta(
+        &mut self,
+        obj: &ExId,
+        delta: &str,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_closed();
+        match crate::automerge::quill::apply_quill_delta(self, obj, delta) {
+            Ok(()) => {
+                self.commit();
+                Ok(())
+            }
+            Err(e) => {
+                self.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    /// The text object `obj` as a Quill Delta, see [`Automerge::text_to_quill_delta`]
+    #[cfg(feature = "json")]
+    pub fn text_to_quill_delta(&self, obj: &ExId) -> Result<String, AutomergeError> {
+        self.doc.text_to_quill_delta(obj)
+    }
+
+    /// The current state of this document encoded as CBOR, see [`Automerge::export_cbor`]
+    #[cfg(feature = "cbor")]
+    pub fn export_cbor(&self) -> Vec<u8> {
+        self.doc.export_cbor()
+    }
+
+    /// The current state of this document encoded as MessagePack, see [`Automerge::to_msgpack`]
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, AutomergeError> {
+        self.doc.to_msgpack()
+    }
+
+    /// The current state of a list as a JSON array, see [`Automerge::list_to_json`]
+    #[cfg(feature = "json")]
+    pub fn list_to_json(&self, obj: &ExId) -> Result<serde_json::Value, AutomergeError> {
+        self.doc.list_to_json(obj)
+    }
+
+    /// The current state of this document as typed JSON, see [`Automerge::to_json_typed`]
+    #[cfg(feature = "json")]
+    pub fn to_json_typed(&self) -> serde_json::Value {
+        self.doc.to_json_typed()
+    }
+
+    /// Apply an RFC 6902 JSON Patch as a single change, see [`Automerge::apply_json_patch`]
+    ///
+    /// Any pending operations are committed first.
+    #[cfg(feature = "json-patch")]
+    pub fn apply_json_patch(&mut self, ops: &[crate::JsonPatchOp]) -> Result<(), AutomergeError> {
+        self.ensure_transaction_closed();
+        match crate::automerge::json_patch::apply_json_patch(self, ops) {
+            Ok(()) => {
+                self.commit();
+                Ok(())
+            }
+            Err(e) => {
+                self.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    /// Describe the difference between two states of the document as an RFC 6902 JSON Patch
+    ///
+    /// See [`Automerge::diff_as_json_patch`]
+    #[cfg(feature = "json-patch")]
+    pub fn diff_as_json_patch(
+        &mut self,
+        before: &[ChangeHash],
+        after: &[ChangeHash],
+    ) -> Result<Vec<crate::JsonPatchOp>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.diff_as_json_patch(before, after)
+    }
+
     pub fn fork(&mut self) -> Self {
         self.ensure_transaction_closed();
         Self {
@@ -252,6 +387,211 @@ impl AutoCommit {
         self.doc.get_actor()
     }
 
+    /// Replace the contents of `buf` with the text of `obj`, see [`Automerge::text_into`]
+    pub fn text_into<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        buf: &mut String,
+    ) -> Result<(), AutomergeError> {
+        self.doc.text_into(obj, buf)
+    }
+
+    /// The words of a text object, see [`Automerge::text_words`]
+    pub fn text_words<'a, O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        buf: &'a mut String,
+    ) -> Result<Words<'a>, AutomergeError> {
+        self.doc.text_words(obj, buf)
+    }
+
+    /// The value most recently deleted from a map key, see [`Automerge::get_deleted`]
+    pub fn get_deleted<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<(Value<'static>, ExId, ChangeHash)>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.get_deleted(obj, prop)
+    }
+
+    /// Restore the value most recently deleted from a map key, see [`Automerge::undelete`]
+    ///
+    /// The restored value is added to the pending transaction.
+    pub fn undelete<P: Into<Prop>>(
+        &mut self,
+        obj: &ExId,
+        prop: P,
+    ) -> Result<Option<ExId>, AutomergeError> {
+        let prop = prop.into();
+        self.ensure_transaction_closed();
+        let snapshot = match self.doc.deleted_snapshot(obj, prop.clone())? {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+        snapshot.put_value(self, obj, prop).map(Some)
+    }
+
+    /// Set `prop` of `obj` to `value` unless it already has a value, see
+    /// [`Automerge::put_if_absent`]
+    ///
+    /// The value is written to the pending transaction.
+    pub fn put_if_absent<O: AsRef<ExId>, P: Into<Prop>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        value: V,
+    ) -> Result<bool, AutomergeError> {
+        crate::automerge::put_if_absent::put_if_absent(
+            self,
+            obj.as_ref(),
+            prop.into(),
+            value.into(),
+        )
+    }
+
+    /// The map at `prop` of `obj`, creating it if `prop` has no value, see
+    /// [`Automerge::get_or_create_map`]
+    ///
+    /// The map is created in the pending transaction.
+    pub fn get_or_create_map<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+    ) -> Result<ExId, AutomergeError> {
+        crate::automerge::get_or_create::get_or_create_map(self, obj.as_ref(), prop.into())
+            .map(|(id, _)| id)
+    }
+
+    /// Move an item of a list to another index, see [`Automerge::move_list_item`]
+    ///
+    /// The delete and insert are added to the pending transaction.
+    pub fn move_list_item<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        from: usize,
+        to: usize,
+    ) -> Result<(), AutomergeError> {
+        crate::automerge::move_item::move_list_item(self, obj.as_ref(), from, to)
+    }
+
+    /// Swap the values of two properties, see [`Automerge::swap`]
+    ///
+    /// The writes are added to the pending transaction.
+    pub fn swap<O: AsRef<ExId>, P1: Into<Prop>, P2: Into<Prop>>(
+        &mut self,
+        obj: O,
+        a: P1,
+        b: P2,
+    ) -> Result<(), AutomergeError> {
+        crate::automerge::swap::swap(self, obj.as_ref(), a.into(), b.into())
+    }
+
+    /// Increment the counter at `prop`, see [`Automerge::increment_by`]
+    ///
+    /// The increment is added to the pending transaction like [`Transactable::increment`].
+    pub fn increment_by<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        by: i64,
+    ) -> Result<(), AutomergeError> {
+        let prop = prop.into();
+        crate::automerge::increment::check_counter(self, obj.as_ref(), &prop)?;
+        self.increment(obj, prop, by)
+    }
+
+    /// The text of `obj` split into runs sharing the same marks, see [`Automerge::text_spans`]
+    pub fn text_spans<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<Span>, AutomergeError> {
+        self.doc.text_spans(obj)
+    }
+
+    /// The marks active at `char_index` of `obj`, see [`Automerge::get_marks_at_index`]
+    pub fn get_marks_at_index<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        char_index: usize,
+    ) -> Result<HashMap<SmolStr, ScalarValue>, AutomergeError> {
+        self.doc.get_marks_at_index(obj, char_index)
+    }
+
+    /// A cursor for the offset `offset` in `obj`, see [`Automerge::text_to_cursor`]
+    pub fn text_to_cursor<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        offset: usize,
+    ) -> Result<Cursor, AutomergeError> {
+        self.doc.text_to_cursor(obj, offset)
+    }
+
+    /// The id of every object of type `typ`, see [`Automerge::objects_of_type`]
+    pub fn objects_of_type(&self, typ: ObjType) -> impl Iterator<Item = ExId> + '_ {
+        self.doc.objects_of_type(typ)
+    }
+
+    /// The path from the root of the document to `obj`, see [`Automerge::path_to_object`]
+    pub fn path_to_object(&self, obj: &ExId) -> Result<Vec<(ExId, Prop)>, AutomergeError> {
+        self.doc.path_to_object(obj)
+    }
+
+    /// The number of changes from each actor, see [`Automerge::compute_state_vector`]
+    ///
+    /// Any pending operations are committed first.
+    pub fn compute_state_vector(&mut self) -> StateVector {
+        self.ensure_transaction_closed();
+        self.doc.compute_state_vector()
+    }
+
+    /// The changes which are not described by `sv`, see [`Automerge::changes_for_state_vector`]
+    ///
+    /// Any pending operations are committed first.
+    pub fn changes_for_state_vector(&mut self, sv: &StateVector) -> Vec<&Change> {
+        self.ensure_transaction_closed();
+        self.doc.changes_for_state_vector(sv)
+    }
+
+    /// Visit every object and value in this document with `visitor`, see [`Automerge::walk`]
+    pub fn walk<V: DocumentVisitor>(&self, visitor: V) {
+        self.doc.walk(visitor)
+    }
+
+    /// The lowest Lamport counter of an op by `actor`, see [`Automerge::actor_first_seen_seq`]
+    ///
+    /// Any pending operations are committed first.
+    pub fn actor_first_seen_seq(&mut self, actor: &ActorId) -> Option<u64> {
+        self.ensure_transaction_closed();
+        self.doc.actor_first_seen_seq(actor)
+    }
+
+    /// Mark `start..end` of `obj` without letting the mark grow, see
+    /// [`Automerge::mark_range_exclusive`]
+    ///
+    /// The mark is added to the pending transaction.
+    pub fn mark_range_exclusive(
+        &mut self,
+        obj: &ExId,
+        start: usize,
+        end: usize,
+        name: &SmolStr,
+        value: ScalarValue,
+    ) -> Result<(), AutomergeError> {
+        let mark = Mark::new(name.to_string(), value, start, end);
+        self.mark(obj, mark, ExpandMark::None)
+    }
+
+    /// Call `observer` with every [`Patch`] to this document, see [`Automerge::observe_changes`]
+    ///
+    /// Local changes are observed when the pending transaction is committed, either explicitly
+    /// or by a method which commits it first.
+    pub fn observe_changes<F: Fn(&Patch) + Send + Sync + 'static>(&mut self, observer: F) {
+        self.doc.observe_changes(observer)
+    }
+
+    /// The actor which authors pending and future changes, see [`Automerge::transaction_actor`]
+    pub fn transaction_actor(&self) -> &ActorId {
+        self.doc.transaction_actor()
+    }
+
     fn ensure_transaction_open(&mut self) {
         if self.transaction.is_none() {
             let args = self.doc.transaction_args();
@@ -289,6 +629,39 @@ impl AutoCommit {
             .apply_changes_log_patches(changes, &mut self.patch_log)
     }
 
+    /// The changes which `changes` depend on and which are not in the history of `heads`, see
+    /// [`Automerge::missing_deps_for`]
+    pub fn missing_deps_for(
+        &mut self,
+        changes: &[Change],
+        heads: &[ChangeHash],
+    ) -> Vec<ChangeHash> {
+        self.ensure_transaction_closed();
+        self.doc.missing_deps_for(changes, heads)
+    }
+
+    /// Apply changes and report how many were new, see [`Automerge::apply_changes_report`]
+    pub fn apply_changes_report(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+    ) -> Result<ApplyReport, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc
+            .apply_changes_report_log_patches(changes, &mut self.patch_log)
+    }
+
+    /// Apply changes and return the resulting patches, see [`Automerge::apply_changes_and_diff`]
+    pub fn apply_changes_and_diff(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+    ) -> Result<Vec<Patch>, AutomergeError> {
+        self.ensure_transaction_closed();
+        let before = self.doc.get_heads();
+        self.apply_changes(changes)?;
+        let after = self.doc.get_heads();
+        Ok(self.doc.diff(&before, &after, self.patch_log.text_rep()))
+    }
+
     /// Takes all the changes in `other` which are not in `self` and applies them
     pub fn merge(&mut self, other: &mut AutoCommit) -> Result<Vec<ChangeHash>, AutomergeError> {
         self.ensure_transaction_closed();
@@ -311,6 +684,24 @@ impl AutoCommit {
         bytes
     }
 
+    /// Save the entirety of this document to `writer`
+    ///
+    /// See [`Automerge::save_to`]
+    pub fn save_to<W: std::io::Write>(&mut self, writer: W) -> Result<(), AutomergeError> {
+        self.save_to_with_options(writer, SaveOptions::default())
+    }
+
+    pub fn save_to_with_options<W: std::io::Write>(
+        &mut self,
+        writer: W,
+        options: SaveOptions,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.save_to_with_options(writer, options)?;
+        self.save_cursor = self.get_heads();
+        Ok(())
+    }
+
     /// Save the document and attempt to load it before returning - slow!
     pub fn save_and_verify(&mut self) -> Result<Vec<u8>, AutomergeError> {
         let bytes = self.save();
@@ -362,6 +753,19 @@ impl AutoCommit {
         self.doc.get_change_by_hash(hash)
     }
 
+    /// Iterate over every change in the document such that each change comes after all of its
+    /// dependencies, see [`Automerge::iter_changes_topological`]
+    pub fn iter_changes_topological(&mut self) -> TopologicalChanges<'_> {
+        self.ensure_transaction_closed();
+        self.doc.iter_changes_topological()
+    }
+
+    /// The dependency graph of the changes in this document, see [`Automerge::change_graph`]
+    pub fn change_graph(&mut self) -> Vec<ChangeNode> {
+        self.ensure_transaction_closed();
+        self.doc.change_graph()
+    }
+
     /// Get changes in `other` that are not in `self
     pub fn get_changes_added<'a>(&mut self, other: &'a mut Self) -> Vec<&'a Change> {
         self.ensure_transaction_closed();
@@ -396,7 +800,7 @@ impl AutoCommit {
         self.doc.visualise_optree(objects)
     }
 
-    /// Get the current heads of the document.
+    /// Get the current heads of the document, sorted as described in [`Automerge::get_heads`].
     ///
     /// This closes the transaction first, if one is in progress.
     pub fn get_heads(&mut self) -> Vec<ChangeHash> {
@@ -404,6 +808,18 @@ impl AutoCommit {
         self.doc.get_heads()
     }
 
+    /// Whether `hash` is one of the heads of this document, see [`Automerge::has_head`]
+    pub fn has_head(&mut self, hash: &ChangeHash) -> bool {
+        self.ensure_transaction_closed();
+        self.doc.has_head(hash)
+    }
+
+    /// The heads of this document as a string, see [`Automerge::get_heads_as_string`]
+    pub fn get_heads_as_string(&mut self) -> String {
+        self.ensure_transaction_closed();
+        self.doc.get_heads_as_string()
+    }
+
     pub fn set_text_rep(&mut self, text_rep: TextRepresentation) {
         self.patch_log.set_text_rep(text_rep)
     }
@@ -417,6 +833,15 @@ impl AutoCommit {
         self
     }
 
+    /// The number of operations which have been made since the last commit
+    ///
+    /// These operations will be turned into a change by the next call to [`Self::commit`] (or any
+    /// method which implicitly commits, such as [`Self::save`]). Returns 0 if there is no open
+    /// transaction.
+    pub fn pending_change_count(&self) -> usize {
+        self.pending_ops()
+    }
+
     /// Commit any uncommitted changes
     ///
     /// Returns `None` if there were no operations to commit
@@ -453,7 +878,17 @@ impl AutoCommit {
     pub fn rollback(&mut self) -> usize {
         self.transaction
             .take()
-            .map(|(_, tx)| tx.rollback(&mut self.doc))
+            .map(|(_, tx)| tx.rollback(&mut self.doc, None))
+            .unwrap_or(0)
+    }
+
+    /// Like [`Self::rollback`] but call `observer` for each discarded operation
+    ///
+    /// See [`crate::transaction::Transaction::rollback_with`]
+    pub fn rollback_with<F: FnMut(ExId, Prop, OpType)>(&mut self, mut observer: F) -> usize {
+        self.transaction
+            .take()
+            .map(|(_, tx)| tx.rollback(&mut self.doc, Some(&mut observer)))
             .unwrap_or(0)
     }
 
@@ -481,9 +916,58 @@ impl AutoCommit {
         SyncWrapper { inner: self }
     }
 
+    /// Apply a received sync message and return the [`Patch`]es it produced
+    ///
+    /// See [`Automerge::receive_sync_message_with_patches`]. The changes are also logged to this
+    /// document's own patch log, as with [`SyncDoc::receive_sync_message`].
+    pub fn receive_sync_message_with_patches(
+        &mut self,
+        sync_state: &mut sync::State,
+        message: sync::Message,
+    ) -> Result<Vec<Patch>, AutomergeError> {
+        let mut patch_log = PatchLog::active(self.patch_log.text_rep());
+        self.sync()
+            .receive_sync_message_log_patches(sync_state, message, &mut patch_log)?;
+        Ok(self.doc.make_patches(&mut patch_log))
+    }
+
+    /// Save a compacted copy of this document, see [`Automerge::encode_compact`]
+    pub fn encode_compact(&mut self) -> Result<Vec<u8>, AutomergeError> {
+        self.ensure_transaction_closed();
+        self.doc.encode_compact()
+    }
+
+    /// Sync this document with `other` until they both have the same changes, see
+    /// [`Automerge::sync_round_trip`]
+    pub fn sync_round_trip(
+        &mut self,
+        other: &mut AutoCommit,
+    ) -> Result<(usize, usize), AutomergeError> {
+        sync::sync_round_trip(&mut self.sync(), &mut other.sync())
+    }
+
     pub fn hydrate(&self, heads: Option<&[ChangeHash]>) -> hydrate::Value {
         self.doc.hydrate(heads)
     }
+
+    /// Get the number of visible keys in the map `obj`, see [`Automerge::keys_len`]
+    pub fn keys_len<O: AsRef<ExId>>(&self, obj: O) -> Result<usize, AutomergeError> {
+        self.doc.keys_len(obj)
+    }
+
+    /// The number of operations in the op tree of this document, see [`Automerge::op_count`]
+    pub fn op_count(&self) -> usize {
+        self.doc.op_count()
+    }
+
+    /// Whether there is a visible value at `prop` in `obj`, see [`Automerge::contains_key`]
+    pub fn contains_key<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<bool, AutomergeError> {
+        self.doc.contains_key(obj, prop)
+    }
 }
 
 impl ReadDoc for AutoCommit {
@@ -637,6 +1121,10 @@ impl ReadDoc for AutoCommit {
         self.doc.get_all_at(obj, prop, heads)
     }
 
+    fn count_conflicts(&self) -> usize {
+        self.doc.count_conflicts()
+    }
+
     fn get_missing_deps(&self, heads: &[ChangeHash]) -> Vec<ChangeHash> {
         self.doc.get_missing_deps(heads)
     }
@@ -665,6 +1153,16 @@ impl Transactable for AutoCommit {
         tx.put(&mut self.doc, patch_log, obj.as_ref(), prop, value)
     }
 
+    fn put_all<O: AsRef<ExId>, I: IntoIterator<Item = (String, ScalarValue)>>(
+        &mut self,
+        obj: O,
+        entries: I,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.put_all(&mut self.doc, patch_log, obj.as_ref(), entries)
+    }
+
     fn put_object<O: AsRef<ExId>, P: Into<Prop>>(
         &mut self,
         obj: O,
@@ -733,6 +1231,17 @@ impl Transactable for AutoCommit {
         tx.splice(&mut self.doc, patch_log, obj.as_ref(), pos, del, vals)
     }
 
+    fn list_insert_many<O: AsRef<ExId>, V: IntoIterator<Item = ScalarValue>>(
+        &mut self,
+        obj: O,
+        index: usize,
+        vals: V,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.list_insert_many(&mut self.doc, patch_log, obj.as_ref(), index, vals)
+    }
+
     fn splice_text<O: AsRef<ExId>>(
         &mut self,
         obj: O,
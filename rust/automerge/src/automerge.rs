@@ -2,6 +2,7 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::Debug;
 use std::num::NonZeroU64;
 use std::ops::RangeBounds;
+use std::sync::Arc;
 
 use itertools::Itertools;
 
@@ -9,25 +10,71 @@ use crate::change_graph::ChangeGraph;
 use crate::columnar::Key as EncodedKey;
 use crate::exid::ExId;
 use crate::hydrate;
-use crate::iter::{Keys, ListRange, MapRange, Values};
+use crate::iter::{Keys, ListRange, MapRange, TopologicalChanges, Values, Words};
 use crate::marks::{Mark, MarkStateMachine};
 use crate::op_set::OpSet;
 use crate::parents::Parents;
 use crate::patches::{Patch, PatchLog, TextRepresentation};
-use crate::storage::{self, load, CompressConfig, VerificationMode};
-use crate::transaction::{self, CommitOptions, Failure, Success, Transaction, TransactionArgs};
+use crate::storage::load::Budget;
+use crate::storage::{self, load, CompressConfig, LoadLimits, VerificationMode};
+use crate::sync;
+use crate::transaction::{
+    self, CommitOptions, Failure, Success, TextOp, Transaction, TransactionArgs,
+};
 use crate::types::{
     ActorId, ChangeHash, Clock, ElemId, Export, Exportable, Key, MarkData, ObjId, ObjMeta, Op,
     OpId, OpType, Value,
 };
-use crate::{AutomergeError, Change, Cursor, ObjType, Prop, ReadDoc};
+use crate::{
+    AutomergeError, Change, ChangeNode, Cursor, ObjType, ParseChangeHashError, Prop, ReadDoc,
+};
 
+#[cfg(feature = "cbor")]
+mod cbor;
+mod compact;
 pub(crate) mod current_state;
+mod deleted;
 pub(crate) mod diff;
+mod diff_cursor;
+pub(crate) mod get_or_create;
+pub(crate) mod increment;
+mod insert_many;
+#[cfg(feature = "json")]
+pub(crate) mod json;
+#[cfg(feature = "json-patch")]
+pub(crate) mod json_patch;
+mod list_diff;
+mod map_diff;
+mod mark_range;
+mod marks_at_index;
+pub(crate) mod move_item;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod objects;
+mod observers;
+pub(crate) mod put_if_absent;
+#[cfg(feature = "json")]
+pub(crate) mod quill;
+#[cfg(feature = "serde-document")]
+mod serde_impl;
+mod splice_at_cursor;
+mod state_vector;
+pub(crate) mod swap;
+mod text_cursor;
+mod text_spans;
+mod walk;
 
 #[cfg(test)]
 mod tests;
 
+pub use diff_cursor::DiffCursor;
+#[cfg(feature = "json-patch")]
+pub use json_patch::JsonPatchOp;
+pub use list_diff::ListDiffEntry;
+pub use map_diff::MapDiffEntry;
+pub use state_vector::StateVector;
+pub use walk::DocumentVisitor;
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Actor {
     Unused(ActorId),
@@ -91,6 +138,10 @@ pub struct Automerge {
     actor: Actor,
     /// The maximum operation counter this document has seen.
     max_op: u64,
+    /// The sync states of the peers this document is synchronizing with.
+    sync_peers: sync::Peers,
+    /// Callbacks which are called with the patches for each change to this document.
+    observers: observers::Observers,
 }
 
 impl Automerge {
@@ -106,6 +157,8 @@ impl Automerge {
             deps: Default::default(),
             actor: Actor::Unused(ActorId::random()),
             max_op: 0,
+            sync_peers: Default::default(),
+            observers: Default::default(),
         }
     }
 
@@ -117,11 +170,28 @@ impl Automerge {
         &self.ops
     }
 
+    pub(crate) fn sync_peers(&self) -> &sync::Peers {
+        &self.sync_peers
+    }
+
+    pub(crate) fn sync_peers_mut(&mut self) -> &mut sync::Peers {
+        &mut self.sync_peers
+    }
+
     /// Whether this document has any operations
     pub fn is_empty(&self) -> bool {
         self.history.is_empty() && self.queue.is_empty()
     }
 
+    /// The number of operations in the op tree of this document
+    ///
+    /// This includes operations which are no longer visible because they have been deleted or
+    /// overwritten, so it is a measure of the size of the document rather than of its current
+    /// state. Delete operations themselves are not stored in the op tree and are not counted.
+    pub fn op_count(&self) -> usize {
+        self.ops.len()
+    }
+
     pub(crate) fn actor_id(&self) -> ActorId {
         match &self.actor {
             Actor::Unused(id) => id.clone(),
@@ -169,6 +239,15 @@ impl Automerge {
         }
     }
 
+    /// The actor which will author the next change committed to this document
+    ///
+    /// This is always the same as [`Self::get_actor`], it exists to make it clear that this is
+    /// the actor new changes are written as. Changes received from other documents keep their
+    /// own actors.
+    pub fn transaction_actor(&self) -> &ActorId {
+        self.get_actor()
+    }
+
     pub(crate) fn get_actor_index(&mut self) -> usize {
         match &mut self.actor {
             Actor::Unused(actor) => {
@@ -345,9 +424,47 @@ impl Automerge {
     pub fn fork(&self) -> Self {
         let mut f = self.clone();
         f.set_actor(ActorId::random());
+        f.sync_peers = Default::default();
+        f.observers = Default::default();
         f
     }
 
+    /// Replace the contents of `buf` with the text of `obj`
+    ///
+    /// This produces exactly the same string as [`ReadDoc::text`] but reuses the capacity of
+    /// `buf` rather than allocating a new `String`, which helps when the text is read
+    /// repeatedly, for example on every frame of a UI.
+    pub fn text_into<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        buf: &mut String,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(obj.as_ref())?;
+        self.ops.text_into(&obj.id, None, buf);
+        Ok(())
+    }
+
+    /// The words of the text object `obj`, with the character index each word starts at
+    ///
+    /// The text is written into `buf` as by [`Self::text_into`] and each word is a slice of it.
+    /// See [`Words`] for how the text is split into words.
+    pub fn text_words<'a, O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        buf: &'a mut String,
+    ) -> Result<Words<'a>, AutomergeError> {
+        self.text_into(obj, buf)?;
+        Ok(Words::new(buf))
+    }
+
+    /// Wrap this document in an [`Arc`] so it can be cheaply cloned and shared for reading
+    ///
+    /// `Arc<Automerge>` implements [`ReadDoc`], so the shared document can be read without
+    /// dereferencing it first.
+    pub fn into_arc(self) -> Arc<Automerge> {
+        Arc::new(self)
+    }
+
     /// Fork this document at the given heads
     ///
     /// This will create a new actor ID for the forked document
@@ -464,14 +581,78 @@ impl Automerge {
         on_error: OnPartialLoad,
         mode: VerificationMode,
         patch_log: &mut PatchLog,
+    ) -> Result<Self, AutomergeError> {
+        Self::load_with_budget(data, on_error, mode, &mut Budget::unlimited(), patch_log)
+    }
+
+    /// Load a document, failing part way through if the data exceeds any of `limits`
+    ///
+    /// This is intended for loading untrusted data, a malformed document which claims to contain
+    /// a huge number of ops or which decompresses to a huge size returns
+    /// [`AutomergeError::LimitExceeded`] rather than exhausting memory.
+    pub fn load_with_limits(data: &[u8], limits: LoadLimits) -> Result<Self, AutomergeError> {
+        Self::load_with_budget(
+            data,
+            OnPartialLoad::Error,
+            VerificationMode::Check,
+            &mut Budget::new(limits),
+            &mut PatchLog::inactive(TextRepresentation::default()),
+        )
+    }
+
+    /// Load a document from a stream of bytes
+    ///
+    /// This accepts the same data as [`Self::load`] but reads it from `reader` one chunk at a time,
+    /// so the saved document never needs to be held in memory all at once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::Load`] if reading from `reader` fails, if the stream ends part way
+    /// through a chunk or if any chunk is invalid.
+    pub fn load_from<R: std::io::Read>(mut reader: R) -> Result<Self, AutomergeError> {
+        let first = match load::read_chunk(&mut reader)? {
+            Some(chunk) => chunk,
+            None => return Ok(Self::new()),
+        };
+        let first_chunk_was_doc = first[8] == u8::from(storage::ChunkType::Document);
+        let mut am = if first_chunk_was_doc {
+            Self::load(&first)?
+        } else {
+            let mut am = Self::new();
+            am.apply_stream_chunk(&first)?;
+            am
+        };
+        drop(first);
+        while let Some(chunk) = load::read_chunk(&mut reader)? {
+            am.apply_stream_chunk(&chunk)?;
+        }
+        // Only allow missing deps if the first chunk was a document chunk, as in `load_with`
+        if !am.queue.is_empty() && !first_chunk_was_doc {
+            return Err(AutomergeError::MissingDeps);
+        }
+        Ok(am)
+    }
+
+    fn apply_stream_chunk(&mut self, chunk: &[u8]) -> Result<(), AutomergeError> {
+        match load::load_changes(storage::parse::Input::new(chunk), &mut Budget::unlimited()) {
+            load::LoadedChanges::Complete(changes) => self.apply_changes(changes),
+            load::LoadedChanges::Partial { error, .. } => Err(error.into()),
+        }
+    }
+
+    fn load_with_budget(
+        data: &[u8],
+        on_error: OnPartialLoad,
+        mode: VerificationMode,
+        budget: &mut Budget,
+        patch_log: &mut PatchLog,
     ) -> Result<Self, AutomergeError> {
         if data.is_empty() {
             tracing::trace!("no data, initializing empty document");
             return Ok(Self::new());
         }
         tracing::trace!("loading first chunk");
-        let (remaining, first_chunk) = storage::Chunk::parse(storage::parse::Input::new(data))
-            .map_err(|e| load::Error::Parse(Box::new(e)))?;
+        let (remaining, first_chunk) = load::parse_chunk(storage::parse::Input::new(data), budget)?;
         if !first_chunk.checksum_valid() {
             return Err(load::Error::BadChecksum.into());
         }
@@ -487,8 +668,8 @@ impl Automerge {
                     result: op_set,
                     changes,
                     heads,
-                } = storage::load::reconstruct_document(&d, mode, OpSet::builder())
-                    .map_err(|e| load::Error::InflateDocument(Box::new(e)))?;
+                } = storage::load::reconstruct_document(&d, mode, OpSet::builder(), budget)
+                    .map_err(load::Error::from)?;
                 let mut hashes_by_index = HashMap::new();
                 let mut actor_to_history: HashMap<usize, Vec<usize>> = HashMap::new();
                 let mut change_graph = ChangeGraph::new();
@@ -511,30 +692,37 @@ impl Automerge {
                     deps: heads.into_iter().collect(),
                     actor: Actor::Unused(ActorId::random()),
                     max_op,
+                    sync_peers: Default::default(),
+                    observers: Default::default(),
                 }
             }
             storage::Chunk::Change(stored_change) => {
                 tracing::trace!("first chunk is change chunk");
                 change = Some(
-                    Change::new_from_unverified(stored_change.into_owned(), None)
-                        .map_err(|e| load::Error::InvalidChangeColumns(Box::new(e)))?,
+                    Change::new_from_unverified_with_budget(
+                        stored_change.into_owned(),
+                        None,
+                        budget,
+                    )
+                    .map_err(load::Error::from)?,
                 );
                 Self::new()
             }
             storage::Chunk::CompressedChange(stored_change, compressed) => {
                 tracing::trace!("first chunk is compressed change");
                 change = Some(
-                    Change::new_from_unverified(
+                    Change::new_from_unverified_with_budget(
                         stored_change.into_owned(),
                         Some(compressed.into_owned()),
+                        budget,
                     )
-                    .map_err(|e| load::Error::InvalidChangeColumns(Box::new(e)))?,
+                    .map_err(load::Error::from)?,
                 );
                 Self::new()
             }
         };
         tracing::trace!("loading change chunks");
-        match load::load_changes(remaining.reset()) {
+        match load::load_changes(remaining.reset(), budget) {
             load::LoadedChanges::Complete(c) => {
                 am.apply_changes(change.into_iter().chain(c))?;
                 // Only allow missing deps if the first chunk was a document chunk
@@ -544,7 +732,11 @@ impl Automerge {
                 }
             }
             load::LoadedChanges::Partial { error, .. } => {
-                if on_error == OnPartialLoad::Error {
+                // Exceeding a limit is never a partial load, the limits are there to stop us
+                // loading the rest of the data
+                if on_error == OnPartialLoad::Error
+                    || matches!(error, load::Error::LimitExceeded(_))
+                {
                     return Err(error.into());
                 }
             }
@@ -600,19 +792,24 @@ impl Automerge {
                 &mut PatchLog::inactive(TextRepresentation::default()),
             )?;
             doc = doc.with_actor(self.actor_id());
+            doc.sync_peers = std::mem::take(&mut self.sync_peers);
+            doc.observers = std::mem::take(&mut self.observers);
             if patch_log.is_active() {
                 current_state::log_current_state_patches(&doc, patch_log);
             }
+            let before = doc.observed_heads().map(|_| Vec::new());
             *self = doc;
+            self.notify_observers(before);
             return Ok(self.ops.len());
         }
-        let changes = match load::load_changes(storage::parse::Input::new(data)) {
-            load::LoadedChanges::Complete(c) => c,
-            load::LoadedChanges::Partial { error, loaded, .. } => {
-                tracing::warn!(successful_chunks=loaded.len(), err=?error, "partial load");
-                loaded
-            }
-        };
+        let changes =
+            match load::load_changes(storage::parse::Input::new(data), &mut Budget::unlimited()) {
+                load::LoadedChanges::Complete(c) => c,
+                load::LoadedChanges::Partial { error, loaded, .. } => {
+                    tracing::warn!(successful_chunks=loaded.len(), err=?error, "partial load");
+                    loaded
+                }
+            };
         let start = self.ops.len();
         self.apply_changes_log_patches(changes, patch_log)?;
         let delta = self.ops.len() - start;
@@ -643,6 +840,106 @@ impl Automerge {
         )
     }
 
+    /// Like [`Self::apply_changes`] but return the [`Patch`]es describing how the current state
+    /// of the document changed
+    ///
+    /// The patches are the [`Self::diff`] between the heads before and after applying the
+    /// changes, so changes which were already in the document, or which are still waiting for
+    /// their dependencies, produce no patches.
+    pub fn apply_changes_and_diff(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+    ) -> Result<Vec<Patch>, AutomergeError> {
+        let before = self.get_heads();
+        self.apply_changes(changes)?;
+        let after = self.get_heads();
+        Ok(self.diff(&before, &after, TextRepresentation::default()))
+    }
+
+    /// The hashes of changes which `changes` depend on, directly or indirectly, and which are
+    /// neither in `changes` nor in the history of `heads`
+    ///
+    /// Dependencies are followed through `changes` and through changes which have been received
+    /// but are waiting for their own dependencies, so the result is exactly the set of changes
+    /// which must be fetched before `changes` can be applied on top of `heads`. Pass
+    /// [`Self::get_heads`] to find what this document is missing. Heads which are not in this
+    /// document cover no changes. The result is sorted.
+    pub fn missing_deps_for(&self, changes: &[Change], heads: &[ChangeHash]) -> Vec<ChangeHash> {
+        let available = changes
+            .iter()
+            .chain(self.queue.iter())
+            .map(|c| (c.hash(), c))
+            .collect::<HashMap<_, _>>();
+        let mut missing = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut to_visit = changes
+            .iter()
+            .flat_map(|c| c.deps())
+            .copied()
+            .collect::<Vec<_>>();
+        while let Some(hash) = to_visit.pop() {
+            if !visited.insert(hash) {
+                continue;
+            }
+            match available.get(&hash) {
+                Some(change) => to_visit.extend(change.deps()),
+                None => {
+                    missing.insert(hash);
+                }
+            }
+        }
+        self.change_graph.remove_ancestors(&mut missing, heads);
+        missing.into_iter().collect()
+    }
+
+    /// Like [`Self::apply_changes`] but report how many of the changes were new
+    ///
+    /// Changes whose dependencies are missing are buffered until the dependencies arrive, as
+    /// with [`Self::apply_changes`]. They count as deferred in this report and as applied in the
+    /// report for the batch which supplies the last of their dependencies.
+    pub fn apply_changes_report(
+        &mut self,
+        changes: impl IntoIterator<Item = Change>,
+    ) -> Result<ApplyReport, AutomergeError> {
+        self.apply_changes_report_log_patches(
+            changes,
+            &mut PatchLog::inactive(TextRepresentation::default()),
+        )
+    }
+
+    /// Like [`Self::apply_changes_report`] but log the resulting changes to the current state of
+    /// the document to `patch_log`
+    pub fn apply_changes_report_log_patches<I: IntoIterator<Item = Change>>(
+        &mut self,
+        changes: I,
+        patch_log: &mut PatchLog,
+    ) -> Result<ApplyReport, AutomergeError> {
+        let applied_before = self.history.len();
+        let mut seen = HashSet::new();
+        let mut duplicate = 0;
+        let mut new_changes = Vec::new();
+        for change in changes {
+            let hash = change.hash();
+            if self.history_index.contains_key(&hash)
+                || self.queue.iter().any(|c| c.hash() == hash)
+                || !seen.insert(hash)
+            {
+                duplicate += 1;
+            } else {
+                new_changes.push(change);
+            }
+        }
+        self.apply_changes_log_patches(new_changes, patch_log)?;
+        Ok(ApplyReport {
+            applied: self.history.len() - applied_before,
+            duplicate,
+            deferred: seen
+                .iter()
+                .filter(|hash| !self.history_index.contains_key(hash))
+                .count(),
+        })
+    }
+
     /// Like [`Self::apply_changes`] but log the resulting changes to the current state of the
     /// document to `patch_log`
     pub fn apply_changes_log_patches<I: IntoIterator<Item = Change>>(
@@ -654,6 +951,7 @@ impl Automerge {
         // the final state after all the changes have been applied. We can only do this for an
         // empty document right now, once we have logic to produce the diffs between arbitrary
         // states of the OpSet we can make this cleaner.
+        let before = self.observed_heads();
         for c in changes {
             if !self.history_index.contains_key(&c.hash()) {
                 if self.duplicate_seq(&c) {
@@ -674,6 +972,7 @@ impl Automerge {
                 self.apply_change(c, patch_log)?;
             }
         }
+        self.notify_observers(before);
         Ok(())
     }
 
@@ -792,21 +1091,7 @@ impl Automerge {
 
     /// Save the entirety of this document in a compact form.
     pub fn save_with_options(&self, options: SaveOptions) -> Vec<u8> {
-        let heads = self.get_heads();
-        let c = self.history.iter();
-        let compress = if options.deflate {
-            None
-        } else {
-            Some(CompressConfig::None)
-        };
-        let mut bytes = crate::storage::save::save_document(
-            c,
-            self.ops.iter().map(|(objid, _, op)| (objid, op)),
-            &self.ops.m.actors,
-            &self.ops.m.props,
-            &heads,
-            compress,
-        );
+        let mut bytes = self.save_document(&options);
         if options.retain_orphans {
             for orphaned in self.queue.iter() {
                 bytes.extend(orphaned.raw_bytes());
@@ -820,6 +1105,69 @@ impl Automerge {
         self.save_with_options(SaveOptions::default())
     }
 
+    /// Save the entirety of this document to `writer`
+    ///
+    /// The output is identical to [`Self::save`], but the document chunk is written to `writer`
+    /// in parts as it is encoded rather than first being assembled into a single buffer, followed
+    /// by each retained orphaned change in turn. Only the encoded columns of the document are
+    /// held in memory, so the peak memory used is roughly halved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::Write`] if writing to `writer` fails.
+    pub fn save_to<W: std::io::Write>(&self, writer: W) -> Result<(), AutomergeError> {
+        self.save_to_with_options(writer, SaveOptions::default())
+    }
+
+    /// Like [`Self::save_to`] but with the given [`SaveOptions`]
+    pub fn save_to_with_options<W: std::io::Write>(
+        &self,
+        mut writer: W,
+        options: SaveOptions,
+    ) -> Result<(), AutomergeError> {
+        let heads = self.get_heads();
+        crate::storage::save::write_document(
+            self.history.iter(),
+            self.ops.iter().map(|(objid, _, op)| (objid, op)),
+            &self.ops.m.actors,
+            &self.ops.m.props,
+            &heads,
+            Self::compress_config(&options),
+            &mut writer,
+        )
+        .map_err(AutomergeError::Write)?;
+        if options.retain_orphans {
+            for orphaned in self.queue.iter() {
+                writer
+                    .write_all(orphaned.raw_bytes())
+                    .map_err(AutomergeError::Write)?;
+            }
+        }
+        writer.flush().map_err(AutomergeError::Write)
+    }
+
+    /// Encode the document chunk, without any orphaned changes
+    fn save_document(&self, options: &SaveOptions) -> Vec<u8> {
+        let heads = self.get_heads();
+        let c = self.history.iter();
+        crate::storage::save::save_document(
+            c,
+            self.ops.iter().map(|(objid, _, op)| (objid, op)),
+            &self.ops.m.actors,
+            &self.ops.m.props,
+            &heads,
+            Self::compress_config(options),
+        )
+    }
+
+    fn compress_config(options: &SaveOptions) -> Option<CompressConfig> {
+        if options.deflate {
+            None
+        } else {
+            Some(CompressConfig::None)
+        }
+    }
+
     /// Save the document and attempt to load it before returning - slow!
     pub fn save_and_verify(&self) -> Result<Vec<u8>, AutomergeError> {
         let bytes = self.save();
@@ -838,14 +1186,17 @@ impl Automerge {
     /// Save the changes since the given heads
     ///
     /// The output of this will not be a compressed document format, but a series of individual
-    /// changes. This is useful if you know you have only made a small change since the last `save`
-    /// and you want to immediately send it somewhere (e.g. you've inserted a single character in a
-    /// text object).
+    /// changes, each of which is DEFLATE compressed if it is large enough to benefit. This is
+    /// useful if you know you have only made a small change since the last `save` and you want to
+    /// immediately send it somewhere (e.g. you've inserted a single character in a text object).
+    ///
+    /// The output can be loaded with [`Self::load`] or [`Self::load_incremental`]. If `heads` are
+    /// the current heads of the document the output is empty.
     pub fn save_after(&self, heads: &[ChangeHash]) -> Vec<u8> {
         let changes = self.get_changes(heads);
         let mut bytes = vec![];
         for c in changes {
-            bytes.extend(c.raw_bytes());
+            bytes.extend(c.compressed_bytes().as_ref());
         }
         bytes
     }
@@ -906,6 +1257,49 @@ impl Automerge {
             .find(|c| c.actor_id() == self.get_actor());
     }
 
+    /// The lowest Lamport counter of an op by `actor`, or `None` if `actor` has no changes in
+    /// this document
+    ///
+    /// This is the start op of the first change by `actor`. Every op has a higher counter than
+    /// the ops it was made after, so sorting actors by this orders them by when they first
+    /// appeared in the history of the document. Actors whose first changes were made concurrently
+    /// can have the same counter, so sort by `(seq, actor)` to get the same order in every
+    /// document with the changes of those actors.
+    pub fn actor_first_seen_seq(&self, actor: &ActorId) -> Option<u64> {
+        let actor = self.ops.m.actors.lookup(actor)?;
+        let first = *self.states.get(&actor)?.first()?;
+        Some(self.history[first].start_op().get())
+    }
+
+    /// Count the props in every object reachable from the root which have more than one value
+    /// visible at `clock`
+    ///
+    /// Objects are reached through every visible value, including those which lost a conflict,
+    /// as in the default [`ReadDoc::count_conflicts`].
+    pub(crate) fn count_conflicts_for(&self, clock: Option<&Clock>) -> usize {
+        let mut conflicts = 0;
+        let mut objs = vec![ObjId::root()];
+        while let Some(obj) = objs.pop() {
+            let mut current: Option<(Key, usize)> = None;
+            for op in self.ops.iter_ops(&obj).filter(|op| op.visible_at(clock)) {
+                if op.action.is_make() {
+                    objs.push(op.id.into());
+                }
+                let key = op.elemid_or_key();
+                match &mut current {
+                    Some((k, count)) if *k == key => {
+                        *count += 1;
+                        if *count == 2 {
+                            conflicts += 1;
+                        }
+                    }
+                    _ => current = Some((key, 1)),
+                }
+            }
+        }
+        conflicts
+    }
+
     pub(crate) fn clock_at(&self, heads: &[ChangeHash]) -> Clock {
         self.change_graph.clock_for_heads(heads)
     }
@@ -1106,17 +1500,82 @@ impl Automerge {
         patch_log.make_patches(self)
     }
 
+    /// The minimal [`TextOp`]s which turn `before` into `after`
+    ///
+    /// This is the Myers diff used by [`crate::transaction::Transactable::text_set`]. Lengths are
+    /// in characters, so when text is measured in UTF-16 code units they must be converted
+    /// before being used as indexes into a text object. When more than about a thousand
+    /// characters would have to be inserted or deleted the diff gives up on minimality and
+    /// replaces everything between the common prefix and suffix.
+    pub fn text_diff_myers(before: &str, after: &str) -> Vec<TextOp> {
+        transaction::text_diff::text_ops(before, after)
+    }
+
     /// Get the heads of this document.
+    ///
+    /// The heads are sorted by the [`Ord`] implementation of [`ChangeHash`], so two documents
+    /// which contain the same changes return equal vectors regardless of the order in which the
+    /// changes were applied.
     pub fn get_heads(&self) -> Vec<ChangeHash> {
         let mut deps: Vec<_> = self.deps.iter().copied().collect();
         deps.sort_unstable();
         deps
     }
 
+    /// Whether `hash` is one of the heads of this document
+    ///
+    /// This is cheaper than searching the result of [`Self::get_heads`] as it doesn't allocate.
+    pub fn has_head(&self, hash: &ChangeHash) -> bool {
+        self.deps.contains(hash)
+    }
+
+    /// The heads of this document as a comma separated list of hex encoded hashes
+    ///
+    /// The heads are sorted, so two documents with the same heads produce the same string. This
+    /// is useful wherever a compact version identifier is needed, such as in logs or as an HTTP
+    /// `ETag`. Use [`Self::parse_heads_string`] to turn the string back into heads.
+    pub fn get_heads_as_string(&self) -> String {
+        self.get_heads()
+            .iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parse heads encoded by [`Self::get_heads_as_string`]
+    ///
+    /// Whitespace around each hash is ignored and an empty string parses to no heads.
+    pub fn parse_heads_string(s: &str) -> Result<Vec<ChangeHash>, ParseChangeHashError> {
+        if s.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        s.split(',').map(|h| h.trim().parse()).collect()
+    }
+
     pub fn get_changes(&self, have_deps: &[ChangeHash]) -> Vec<&Change> {
         self.get_changes_clock(have_deps)
     }
 
+    /// Iterate over every change in the document such that each change comes after all of its
+    /// dependencies
+    ///
+    /// Changes are produced lazily. Where more than one change is ready to be produced the one with
+    /// the lowest hash is produced first, so documents containing the same changes always iterate
+    /// them in the same order.
+    pub fn iter_changes_topological(&self) -> TopologicalChanges<'_> {
+        TopologicalChanges::new(&self.history, &self.history_index)
+    }
+
+    /// The dependency graph of the changes in this document
+    ///
+    /// Every change comes after all of its dependencies, in the same order as
+    /// [`Self::iter_changes_topological`], so the order is stable for a given set of changes.
+    pub fn change_graph(&self) -> Vec<ChangeNode> {
+        self.iter_changes_topological()
+            .map(ChangeNode::from)
+            .collect()
+    }
+
     /// Get changes in `other` that are not in `self
     pub fn get_changes_added<'a>(&self, other: &'a Self) -> Vec<&'a Change> {
         // Depth-first traversal from the heads through the dependency graph,
@@ -1148,8 +1607,17 @@ impl Automerge {
         obj: O,
         heads: Option<&[ChangeHash]>,
     ) -> Result<Vec<Mark<'_>>, AutomergeError> {
-        let obj = self.exid_to_obj(obj.as_ref())?;
         let clock = heads.map(|heads| self.clock_at(heads));
+        self.marks_at_clock(obj.as_ref(), clock.as_ref())
+    }
+
+    /// The marks of `obj` as of `clock`, or its current marks if `clock` is `None`
+    pub(crate) fn marks_at_clock(
+        &self,
+        obj: &ExId,
+        clock: Option<&Clock>,
+    ) -> Result<Vec<Mark<'_>>, AutomergeError> {
+        let obj = self.exid_to_obj(obj)?;
         let ops_by_key = self.ops().iter_ops(&obj.id).group_by(|o| o.elemid_or_key());
         let mut index = 0;
         let mut marks = MarkStateMachine::default();
@@ -1158,7 +1626,7 @@ impl Automerge {
             .into_iter()
             .filter_map(|(_key, key_ops)| {
                 key_ops
-                    .filter(|o| o.visible_or_mark(clock.as_ref()))
+                    .filter(|o| o.visible_or_mark(clock))
                     .last()
                     .and_then(|o| match &o.action {
                         OpType::Make(_) | OpType::Put(_) => {
@@ -1177,6 +1645,51 @@ impl Automerge {
         let clock = heads.map(|heads| self.clock_at(heads));
         self.hydrate_map(&ObjId::root(), clock.as_ref())
     }
+
+    /// Get the number of visible keys in the map `obj`
+    ///
+    /// Unlike counting the output of [`ReadDoc::keys`] this does not walk the ops in the map, the
+    /// count is read from the index maintained alongside the op tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a map or a table
+    pub fn keys_len<O: AsRef<ExId>>(&self, obj: O) -> Result<usize, AutomergeError> {
+        let obj = self.exid_to_obj(obj.as_ref())?;
+        match obj.typ {
+            ObjType::Map | ObjType::Table => Ok(self.ops.length(&obj.id, obj.encoding, None)),
+            typ => Err(AutomergeError::InvalidOp(typ)),
+        }
+    }
+
+    /// Whether there is a visible value at `prop` in `obj`
+    ///
+    /// This is consistent with [`ReadDoc::get`], a key which has been deleted is not contained in
+    /// the object even though there are ops for it in the document history. Unlike `get` this does
+    /// not export the value.
+    pub fn contains_key<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<bool, AutomergeError> {
+        let obj = self.exid_to_obj(obj.as_ref())?;
+        Ok(!self
+            .ops
+            .seek_ops_by_prop(&obj.id, prop.into(), obj.encoding, None)
+            .ops
+            .is_empty())
+    }
+
+    /// The path from the root of the document to `obj`
+    ///
+    /// Each step is the parent object and the [`Prop`] at which the next object on the path is
+    /// found in it, the last step being the property holding `obj`, so the path of the root is
+    /// empty. Indices in sequences are the current index of the element. This is
+    /// [`Parents::path`], the path includes deleted or conflicted steps, see
+    /// [`Parents::visible_path`] for a path which is only returned if `obj` is visible.
+    pub fn path_to_object(&self, obj: &ExId) -> Result<Vec<(ExId, Prop)>, AutomergeError> {
+        Ok(self.parents(obj)?.path())
+    }
 }
 
 impl ReadDoc for Automerge {
@@ -1443,6 +1956,10 @@ impl ReadDoc for Automerge {
         Ok(values)
     }
 
+    fn count_conflicts(&self) -> usize {
+        self.count_conflicts_for(None)
+    }
+
     fn get_missing_deps(&self, heads: &[ChangeHash]) -> Vec<ChangeHash> {
         let in_queue: HashSet<_> = self.queue.iter().map(|change| change.hash()).collect();
         let mut missing = HashSet::new();
@@ -1475,12 +1992,188 @@ impl ReadDoc for Automerge {
     }
 }
 
+impl ReadDoc for Arc<Automerge> {
+    fn parents<O: AsRef<ExId>>(&self, obj: O) -> Result<Parents<'_>, AutomergeError> {
+        (**self).parents(obj)
+    }
+
+    fn parents_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<Parents<'_>, AutomergeError> {
+        (**self).parents_at(obj, heads)
+    }
+
+    fn keys<O: AsRef<ExId>>(&self, obj: O) -> Keys<'_> {
+        (**self).keys(obj)
+    }
+
+    fn keys_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> Keys<'_> {
+        (**self).keys_at(obj, heads)
+    }
+
+    fn map_range<'a, O: AsRef<ExId>, R: RangeBounds<String> + 'a>(
+        &'a self,
+        obj: O,
+        range: R,
+    ) -> MapRange<'a, R> {
+        (**self).map_range(obj, range)
+    }
+
+    fn map_range_at<'a, O: AsRef<ExId>, R: RangeBounds<String> + 'a>(
+        &'a self,
+        obj: O,
+        range: R,
+        heads: &[ChangeHash],
+    ) -> MapRange<'a, R> {
+        (**self).map_range_at(obj, range, heads)
+    }
+
+    fn list_range<O: AsRef<ExId>, R: RangeBounds<usize>>(
+        &self,
+        obj: O,
+        range: R,
+    ) -> ListRange<'_, R> {
+        (**self).list_range(obj, range)
+    }
+
+    fn list_range_at<O: AsRef<ExId>, R: RangeBounds<usize>>(
+        &self,
+        obj: O,
+        range: R,
+        heads: &[ChangeHash],
+    ) -> ListRange<'_, R> {
+        (**self).list_range_at(obj, range, heads)
+    }
+
+    fn values<O: AsRef<ExId>>(&self, obj: O) -> Values<'_> {
+        (**self).values(obj)
+    }
+
+    fn values_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> Values<'_> {
+        (**self).values_at(obj, heads)
+    }
+
+    fn length<O: AsRef<ExId>>(&self, obj: O) -> usize {
+        (**self).length(obj)
+    }
+
+    fn length_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> usize {
+        (**self).length_at(obj, heads)
+    }
+
+    fn object_type<O: AsRef<ExId>>(&self, obj: O) -> Result<ObjType, AutomergeError> {
+        (**self).object_type(obj)
+    }
+
+    fn marks<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<Mark<'_>>, AutomergeError> {
+        (**self).marks(obj)
+    }
+
+    fn marks_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<Mark<'_>>, AutomergeError> {
+        (**self).marks_at(obj, heads)
+    }
+
+    fn text<O: AsRef<ExId>>(&self, obj: O) -> Result<String, AutomergeError> {
+        (**self).text(obj)
+    }
+
+    fn text_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<String, AutomergeError> {
+        (**self).text_at(obj, heads)
+    }
+
+    fn get_cursor<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        position: usize,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Cursor, AutomergeError> {
+        (**self).get_cursor(obj, position, at)
+    }
+
+    fn get_cursor_position<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        address: &Cursor,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<usize, AutomergeError> {
+        (**self).get_cursor_position(obj, address, at)
+    }
+
+    fn get<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        (**self).get(obj, prop)
+    }
+
+    fn get_at<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+        heads: &[ChangeHash],
+    ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
+        (**self).get_at(obj, prop, heads)
+    }
+
+    fn get_all<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Vec<(Value<'_>, ExId)>, AutomergeError> {
+        (**self).get_all(obj, prop)
+    }
+
+    fn get_all_at<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<(Value<'_>, ExId)>, AutomergeError> {
+        (**self).get_all_at(obj, prop, heads)
+    }
+
+    fn count_conflicts(&self) -> usize {
+        (**self).count_conflicts()
+    }
+
+    fn get_missing_deps(&self, heads: &[ChangeHash]) -> Vec<ChangeHash> {
+        (**self).get_missing_deps(heads)
+    }
+
+    fn get_change_by_hash(&self, hash: &ChangeHash) -> Option<&Change> {
+        (**self).get_change_by_hash(hash)
+    }
+}
+
 impl Default for Automerge {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// What happened to the changes passed to [`Automerge::apply_changes_report`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    /// The number of changes which were applied to the document, including any changes received
+    /// earlier whose dependencies were supplied by this batch
+    pub applied: usize,
+    /// The number of changes which the document already had, or which were repeated in the batch
+    pub duplicate: usize,
+    /// The number of changes in the batch which are waiting for their dependencies
+    pub deferred: usize,
+}
+
 /// Options to pass to `[Automerge::save_with_options]` and [`crate::AutoCommit::save_with_options`]
 #[derive(Debug)]
 pub struct SaveOptions {
@@ -0,0 +1,131 @@
+use ciborium::value::Value as Cbor;
+
+use crate::exid::ExId;
+use crate::{Automerge, AutomergeError, ObjType, ReadDoc, ScalarValue, Value, ROOT};
+
+/// The CBOR tag for a date and time as seconds since the epoch, RFC 8949 section 3.4.2
+const EPOCH_DATE_TIME: u64 = 1;
+
+impl Automerge {
+    /// The current state of this document encoded as CBOR
+    ///
+    /// This has the same structure as [`Self::export_json`]: maps and tables become CBOR maps,
+    /// lists become arrays, text becomes a text string and counters become their current value.
+    /// Bytes become a CBOR byte string and timestamps an epoch based date/time (tag 1), which is
+    /// an integer number of seconds if the timestamp is a whole number of seconds and a float
+    /// otherwise. Where a property has conflicting values the value returned by
+    /// [`ReadDoc::get`] is used.
+    pub fn export_cbor(&self) -> Vec<u8> {
+        let value = cbor_at(self, Value::Object(ObjType::Map), ROOT)
+            .expect("the root and objects found in the document should exist");
+        let mut out = Vec::new();
+        ciborium::ser::into_writer(&value, &mut out).expect("writing to a Vec cannot fail");
+        out
+    }
+}
+
+/// The CBOR representation of `value`, which lives at `obj`
+fn cbor_at<R: ReadDoc>(doc: &R, value: Value<'_>, obj: ExId) -> Result<Cbor, AutomergeError> {
+    match value {
+        Value::Object(ObjType::Map | ObjType::Table) => doc
+            .map_range(&obj, ..)
+            .map(|item| {
+                Ok((
+                    Cbor::Text(item.key.to_string()),
+                    cbor_at(doc, item.value, item.id)?,
+                ))
+            })
+            .collect::<Result<_, _>>()
+            .map(Cbor::Map),
+        Value::Object(ObjType::List) => doc
+            .list_range(&obj, ..)
+            .map(|item| cbor_at(doc, item.value, item.id))
+            .collect::<Result<_, _>>()
+            .map(Cbor::Array),
+        Value::Object(ObjType::Text) => Ok(Cbor::Text(doc.text(&obj)?)),
+        Value::Scalar(s) => Ok(scalar_to_cbor(&s)),
+    }
+}
+
+fn scalar_to_cbor(value: &ScalarValue) -> Cbor {
+    match value {
+        ScalarValue::Bytes(b) => Cbor::Bytes(b.clone()),
+        ScalarValue::Str(s) => Cbor::Text(s.to_string()),
+        ScalarValue::Int(i) => Cbor::Integer((*i).into()),
+        ScalarValue::Uint(u) => Cbor::Integer((*u).into()),
+        ScalarValue::F64(f) => Cbor::Float(*f),
+        ScalarValue::Counter(c) => Cbor::Integer(i64::from(c).into()),
+        ScalarValue::Timestamp(millis) => {
+            let seconds = if millis % 1000 == 0 {
+                Cbor::Integer((millis / 1000).into())
+            } else {
+                Cbor::Float(*millis as f64 / 1000.0)
+            };
+            Cbor::Tag(EPOCH_DATE_TIME, Box::new(seconds))
+        }
+        ScalarValue::Boolean(b) => Cbor::Bool(*b),
+        ScalarValue::Unknown { type_code, bytes } => Cbor::Map(vec![
+            (
+                Cbor::Text("type_code".into()),
+                Cbor::Integer((*type_code).into()),
+            ),
+            (Cbor::Text("bytes".into()), Cbor::Bytes(bytes.clone())),
+        ]),
+        ScalarValue::Null => Cbor::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ciborium::value::Value as Cbor;
+
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, ObjType, ScalarValue, ROOT};
+
+    fn text(s: &str) -> Cbor {
+        Cbor::Text(s.to_string())
+    }
+
+    #[test]
+    fn export_cbor_mirrors_export_json() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "bytes", vec![1u8, 2, 3]).unwrap();
+        doc.put(ROOT, "at", ScalarValue::Timestamp(1_500)).unwrap();
+        doc.put(ROOT, "whole", ScalarValue::Timestamp(-2_000))
+            .unwrap();
+        doc.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+        doc.increment(ROOT, "count", 4).unwrap();
+        doc.put(ROOT, "big", u64::MAX).unwrap();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, -1).unwrap();
+        doc.insert(&list, 1, 0.5).unwrap();
+        doc.insert(&list, 2, ScalarValue::Null).unwrap();
+        let note = doc.put_object(ROOT, "note", ObjType::Text).unwrap();
+        doc.splice_text(&note, 0, 0, "hi").unwrap();
+
+        let encoded = doc.export_cbor();
+        let decoded: Cbor = ciborium::de::from_reader(&encoded[..]).unwrap();
+        assert_eq!(
+            decoded,
+            Cbor::Map(vec![
+                (text("at"), Cbor::Tag(1, Box::new(Cbor::Float(1.5)))),
+                (text("big"), Cbor::Integer(u64::MAX.into())),
+                (text("bytes"), Cbor::Bytes(vec![1, 2, 3])),
+                (text("count"), Cbor::Integer(5.into())),
+                (
+                    text("list"),
+                    Cbor::Array(vec![
+                        Cbor::Integer((-1).into()),
+                        Cbor::Float(0.5),
+                        Cbor::Null
+                    ])
+                ),
+                (text("note"), text("hi")),
+                (
+                    text("whole"),
+                    Cbor::Tag(1, Box::new(Cbor::Integer((-2).into())))
+                ),
+            ])
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::exid::ExId;
+use crate::marks::ExpandMark;
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, ObjType, Prop, ReadDoc, ScalarValue, Value};
+
+/// Where to write a value in the compacted document
+enum Slot {
+    Put(Prop),
+    Insert(usize),
+}
+
+/// A property of an object in the compacted document whose value in the source document is a
+/// conflict
+///
+/// Concurrent values can't be written in one change so these are written once the rest of the
+/// object has been copied, each value in its own change forked from the same base.
+struct Conflict<'a> {
+    obj: ExId,
+    prop: Prop,
+    values: Vec<(Value<'a>, ExId)>,
+}
+
+impl Automerge {
+    /// Create a new document with the same current state as this one but containing only the
+    /// operations needed to reconstruct that state
+    ///
+    /// Deleted and overwritten values are discarded and the result has a new history, so the
+    /// compacted document has different change hashes and object IDs to this one and must not be
+    /// merged with it. Conflicting values are preserved, each concurrent value is written by a
+    /// separate change so that [`ReadDoc::get_all`] returns the same values in the same order and
+    /// [`ReadDoc::get`] returns the same winner. Marks are copied with the default
+    /// [`ExpandMark`] behaviour.
+    ///
+    /// The compacted document uses a new random actor ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from reading this document or writing its values to the compacted
+    /// document, in which case no compacted document is returned
+    pub fn compact(&self) -> Result<Automerge, AutomergeError> {
+        let mut compacted = Automerge::new();
+        let mut conflicts = Vec::new();
+        let mut tx = compacted.transaction();
+        copy_contents(self, &ExId::Root, &mut tx, &ExId::Root, &mut conflicts)?;
+        tx.commit();
+        write_conflicts(self, &mut compacted, conflicts)?;
+        Ok(compacted)
+    }
+
+    /// Save [`Self::compact`] of this document
+    ///
+    /// This is the smallest encoding of the current state of the document, for archiving where the
+    /// history is not needed. The result can be loaded with [`Self::load`] but, like the compacted
+    /// document, it has a different history to this one and must not be merged with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Self::compact`]
+    pub fn encode_compact(&self) -> Result<Vec<u8>, AutomergeError> {
+        Ok(self.compact()?.save())
+    }
+}
+
+/// Copy the contents of `src_obj` into the empty object `dst_obj`, deferring conflicted values
+fn copy_contents<'a, T: Transactable>(
+    src: &'a Automerge,
+    src_obj: &ExId,
+    tx: &mut T,
+    dst_obj: &ExId,
+    conflicts: &mut Vec<Conflict<'a>>,
+) -> Result<(), AutomergeError> {
+    let typ = src.object_type(src_obj)?;
+    match typ {
+        ObjType::Map | ObjType::Table => {
+            for key in src.keys(src_obj) {
+                let mut values = src.get_all(src_obj, key.as_str())?;
+                if values.len() > 1 {
+                    conflicts.push(Conflict {
+                        obj: dst_obj.clone(),
+                        prop: key.into(),
+                        values,
+                    });
+                } else if let Some((value, id)) = values.pop() {
+                    write_value(
+                        src,
+                        value,
+                        &id,
+                        tx,
+                        dst_obj,
+                        Slot::Put(key.into()),
+                        conflicts,
+                    )?;
+                }
+            }
+        }
+        ObjType::List => {
+            for index in 0..src.length(src_obj) {
+                let mut values = src.get_all(src_obj, index)?;
+                if values.len() > 1 {
+                    // The concurrent puts need an element to overwrite
+                    tx.insert(dst_obj, index, ScalarValue::Null)?;
+                    conflicts.push(Conflict {
+                        obj: dst_obj.clone(),
+                        prop: index.into(),
+                        values,
+                    });
+                } else if let Some((value, id)) = values.pop() {
+                    write_value(src, value, &id, tx, dst_obj, Slot::Insert(index), conflicts)?;
+                }
+            }
+        }
+        ObjType::Text => tx.splice_text(dst_obj, 0, 0, &src.text(src_obj)?)?,
+    }
+    if matches!(typ, ObjType::List | ObjType::Text) {
+        for mark in src.marks(src_obj)? {
+            tx.mark(dst_obj, mark, ExpandMark::default())?;
+        }
+    }
+    Ok(())
+}
+
+fn write_value<'a, T: Transactable>(
+    src: &'a Automerge,
+    value: Value<'a>,
+    src_id: &ExId,
+    tx: &mut T,
+    dst_obj: &ExId,
+    slot: Slot,
+    conflicts: &mut Vec<Conflict<'a>>,
+) -> Result<(), AutomergeError> {
+    match (value, slot) {
+        (Value::Object(typ), Slot::Put(prop)) => {
+            let new_obj = tx.put_object(dst_obj, prop, typ)?;
+            copy_contents(src, src_id, tx, &new_obj, conflicts)
+        }
+        (Value::Object(typ), Slot::Insert(index)) => {
+            let new_obj = tx.insert_object(dst_obj, index, typ)?;
+            copy_contents(src, src_id, tx, &new_obj, conflicts)
+        }
+        (Value::Scalar(s), Slot::Put(prop)) => tx.put(dst_obj, prop, s.into_owned().detached()),
+        (Value::Scalar(s), Slot::Insert(index)) => {
+            tx.insert(dst_obj, index, s.into_owned().detached())
+        }
+    }
+}
+
+/// Write each conflicted value from a separate fork of `doc` and merge the forks back in
+///
+/// Every fork starts from the same state so the puts are concurrent. The forks are given
+/// increasing op counters so that the values sort in the same order as in the source document.
+fn write_conflicts<'a>(
+    src: &'a Automerge,
+    doc: &mut Automerge,
+    conflicts: Vec<Conflict<'a>>,
+) -> Result<(), AutomergeError> {
+    for conflict in conflicts {
+        let base = doc.fork();
+        let mut max_op = doc.max_op;
+        for (value, id) in conflict.values {
+            let mut fork = base.fork();
+            fork.max_op = max_op;
+            let mut nested = Vec::new();
+            let mut tx = fork.transaction();
+            let slot = Slot::Put(conflict.prop.clone());
+            write_value(src, value, &id, &mut tx, &conflict.obj, slot, &mut nested)?;
+            tx.commit();
+            write_conflicts(src, &mut fork, nested)?;
+            max_op = fork.max_op;
+            doc.merge(&mut fork)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,299 @@
+use std::cmp::Ordering;
+
+use crate::clock::{Clock, ClockData};
+use crate::exid::ExId;
+use crate::marks::Mark;
+use crate::transaction::Snapshot;
+use crate::types::{Op, OpId, OpType};
+use crate::{Automerge, AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, Value};
+
+impl Automerge {
+    /// The value most recently deleted from the key `prop` of the map `obj`
+    ///
+    /// Returns the deleted value, its id and the hash of the change which deleted it. A value
+    /// which was overwritten counts as deleted by the overwrite, so if a key was set twice and
+    /// then deleted this returns the second value. Returns `None` if the key was never set or
+    /// currently has a value. Counters are returned with every increment applied to them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a map or table, or `prop` is an
+    /// index, since deleted list elements have no index.
+    pub fn get_deleted<O: AsRef<ExId>, P: Into<Prop>>(
+        &self,
+        obj: O,
+        prop: P,
+    ) -> Result<Option<(Value<'static>, ExId, ChangeHash)>, AutomergeError> {
+        Ok(self
+            .last_deleted(obj.as_ref(), prop.into())?
+            .and_then(|(op, deleted_by)| {
+                let hash = self.change_for_op(deleted_by)?;
+                Some((op.value().into_owned(), self.id_to_exid(op.id), hash))
+            }))
+    }
+
+    /// Restore the value most recently deleted from the key `prop` of the map `obj`
+    ///
+    /// The value returned by [`Self::get_deleted`] is put back in a new change. A deleted object
+    /// is recreated with the contents it had just before it was deleted, since the deleted object
+    /// itself can't be made visible again. Returns the id of the restored value, or `None` if
+    /// there was nothing to restore.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a map or table, or `prop` is an
+    /// index.
+    pub fn undelete<P: Into<Prop>>(
+        &mut self,
+        obj: &ExId,
+        prop: P,
+    ) -> Result<Option<ExId>, AutomergeError> {
+        let prop = prop.into();
+        let snapshot = match self.deleted_snapshot(obj, prop.clone())? {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+        let mut tx = self.transaction();
+        match snapshot.put_value(&mut tx, obj, prop) {
+            Ok(id) => {
+                tx.commit();
+                Ok(Some(id))
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    /// The value most recently deleted from `prop`, as it was just before it was deleted
+    pub(crate) fn deleted_snapshot(
+        &self,
+        obj: &ExId,
+        prop: Prop,
+    ) -> Result<Option<Snapshot>, AutomergeError> {
+        let (op, deleted_by) = match self.last_deleted(obj, prop)? {
+            Some(deleted) => deleted,
+            None => return Ok(None),
+        };
+        let change = match self.change_for_op(deleted_by) {
+            Some(hash) => self.get_change_by_hash(&hash),
+            None => None,
+        };
+        let change = match change {
+            Some(change) => change,
+            None => return Ok(None),
+        };
+        // everything the deleting change depends on, and the ops it made before the delete
+        let mut clock = self.clock_at(change.deps());
+        clock.include(
+            deleted_by.actor(),
+            ClockData {
+                max_op: deleted_by.counter() - 1,
+                seq: change.seq(),
+            },
+        );
+        self.snapshot_op(op, &clock).map(Some)
+    }
+
+    fn snapshot_op(&self, op: &Op, clock: &Clock) -> Result<Snapshot, AutomergeError> {
+        let typ = match op.action {
+            OpType::Make(typ) => typ,
+            _ => match op.value_at(Some(clock)) {
+                Value::Scalar(s) => return Ok(Snapshot::Scalar(s.into_owned().detached())),
+                Value::Object(_) => unreachable!("only make ops create objects"),
+            },
+        };
+        let obj = op.id.into();
+        Ok(match typ {
+            ObjType::Text => {
+                let marks = self.marks_at_clock(&self.id_to_exid(op.id), Some(clock))?;
+                Snapshot::Text(
+                    self.ops().text(&obj, Some(clock.clone())),
+                    marks.into_iter().map(Mark::into_owned).collect(),
+                )
+            }
+            ObjType::Map | ObjType::Table => Snapshot::Map(
+                typ,
+                self.ops()
+                    .top_ops(&obj, Some(clock.clone()))
+                    .map(|top| {
+                        let key = self.ops().to_string(top.op.elemid_or_key());
+                        Ok((key, self.snapshot_op(top.op, clock)?))
+                    })
+                    .collect::<Result<_, AutomergeError>>()?,
+            ),
+            ObjType::List => Snapshot::List(
+                self.ops()
+                    .top_ops(&obj, Some(clock.clone()))
+                    .map(|top| self.snapshot_op(top.op, clock))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+
+    /// The op most recently deleted from the map key `prop` and the op which deleted it
+    fn last_deleted(&self, obj: &ExId, prop: Prop) -> Result<Option<(&Op, OpId)>, AutomergeError> {
+        let obj = self.exid_to_obj(obj)?;
+        let key = match (obj.typ, prop) {
+            (ObjType::Map | ObjType::Table, Prop::Map(key)) => key,
+            (typ, _) => return Err(AutomergeError::InvalidOp(typ)),
+        };
+        let mut latest: Option<(OpId, _)> = None;
+        for op in self.ops().ops_for_map_key(&obj.id, key) {
+            if op.is_inc() || op.is_delete() {
+                continue;
+            }
+            if op.visible() {
+                return Ok(None);
+            }
+            let deleted_by = op
+                .succ_iter()
+                .max_by(|a, b| self.ops().m.lamport_cmp(**a, **b))
+                .copied();
+            if let Some(deleted_by) = deleted_by {
+                let is_later = latest.as_ref().map_or(true, |(latest, _)| {
+                    self.ops().m.lamport_cmp(deleted_by, *latest) == Ordering::Greater
+                });
+                if is_later {
+                    latest = Some((deleted_by, op));
+                }
+            }
+        }
+        Ok(latest.map(|(deleted_by, op)| (op, deleted_by)))
+    }
+
+    /// The hash of the change containing the op `id`
+    fn change_for_op(&self, id: OpId) -> Option<ChangeHash> {
+        let changes = self.states.get(&id.actor())?;
+        let after = changes.partition_point(|&i| self.history[i].start_op().get() <= id.counter());
+        let index = changes.get(after.checked_sub(1)?)?;
+        Some(self.history[*index].hash())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, AutomergeError, ObjType, ReadDoc, ScalarValue, Value, ROOT};
+
+    #[test]
+    fn get_deleted_returns_the_last_value_and_deleting_change() {
+        let mut doc = AutoCommit::new();
+        assert_eq!(doc.document().get_deleted(ROOT, "key").unwrap(), None);
+        doc.put(ROOT, "key", "first").unwrap();
+        doc.commit();
+        doc.put(ROOT, "key", "second").unwrap();
+        let second = doc.get(ROOT, "key").unwrap().unwrap().1;
+        doc.commit();
+        assert_eq!(doc.document().get_deleted(ROOT, "key").unwrap(), None);
+        doc.delete(ROOT, "key").unwrap();
+        doc.commit();
+        let deleted_in = doc.get_heads()[0];
+        assert_eq!(
+            doc.document().get_deleted(ROOT, "key").unwrap(),
+            Some((Value::from("second"), second, deleted_in))
+        );
+
+        doc.put(ROOT, "counter", ScalarValue::counter(1)).unwrap();
+        doc.increment(ROOT, "counter", 2).unwrap();
+        doc.delete(ROOT, "counter").unwrap();
+        doc.commit();
+        let (value, _, hash) = doc
+            .document()
+            .get_deleted(ROOT, "counter")
+            .unwrap()
+            .unwrap();
+        assert_eq!(value, Value::counter(3));
+        assert_eq!(hash, doc.get_heads()[0]);
+
+        let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+        doc.put(&map, "inner", 1).unwrap();
+        doc.delete(ROOT, "map").unwrap();
+        let (value, id, _) = doc.document().get_deleted(ROOT, "map").unwrap().unwrap();
+        assert_eq!((value, id), (Value::Object(ObjType::Map), map));
+    }
+
+    #[test]
+    fn get_deleted_finds_concurrent_deletes() {
+        let mut doc1 = AutoCommit::new();
+        doc1.put(ROOT, "key", 1).unwrap();
+        let mut doc2 = doc1.fork();
+        doc1.delete(ROOT, "key").unwrap();
+        doc2.put(ROOT, "key", 2).unwrap();
+        doc2.delete(ROOT, "key").unwrap();
+        doc1.merge(&mut doc2).unwrap();
+        let (value, _, _) = doc1.document().get_deleted(ROOT, "key").unwrap().unwrap();
+        assert_eq!(value, Value::from(2));
+    }
+
+    #[test]
+    fn undelete_restores_scalars_and_objects() {
+        let mut doc = AutoCommit::new();
+        assert_eq!(doc.undelete(&ROOT, "key").unwrap(), None);
+        doc.put(ROOT, "key", "value").unwrap();
+        doc.delete(ROOT, "key").unwrap();
+        let restored = doc.undelete(&ROOT, "key").unwrap().unwrap();
+        assert_eq!(
+            doc.get(ROOT, "key").unwrap(),
+            Some((Value::from("value"), restored))
+        );
+        assert_eq!(doc.undelete(&ROOT, "key").unwrap(), None);
+
+        doc.put(ROOT, "counter", ScalarValue::counter(1)).unwrap();
+        doc.increment(ROOT, "counter", 2).unwrap();
+        doc.delete(ROOT, "counter").unwrap();
+        doc.undelete(&ROOT, "counter").unwrap();
+        doc.increment(ROOT, "counter", 1).unwrap();
+        assert_eq!(
+            doc.get(ROOT, "counter").unwrap().unwrap().0,
+            Value::counter(4)
+        );
+
+        let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+        doc.put(&map, "inner", 1).unwrap();
+        let list = doc.put_object(&map, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, "a").unwrap();
+        let text = doc.insert_object(&list, 1, ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+        let before = doc.hydrate(None);
+        doc.delete(ROOT, "map").unwrap();
+        doc.commit();
+        // edits made after the delete are not restored
+        doc.put(&map, "inner", 2).unwrap();
+
+        let mut plain = doc.document().clone();
+        let restored = plain.undelete(&ROOT, "map").unwrap().unwrap();
+        assert_ne!(restored, map);
+        assert_eq!(plain.hydrate(None), before);
+        let restored = doc.undelete(&ROOT, "map").unwrap().unwrap();
+        assert_eq!(doc.get(ROOT, "map").unwrap().unwrap().1, restored);
+        assert_eq!(doc.hydrate(None), before);
+    }
+
+    #[test]
+    fn get_deleted_rejects_sequences() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        assert!(matches!(
+            doc.document().get_deleted(&list, 0),
+            Err(AutomergeError::InvalidOp(ObjType::List))
+        ));
+    }
+
+    #[test]
+    fn undeleted_text_has_the_marks_it_was_deleted_with() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        let bold = Mark::new("bold".into(), true, 0, 5);
+        doc.mark(&text, bold.clone(), ExpandMark::After).unwrap();
+        doc.delete(ROOT, "text").unwrap();
+        doc.commit();
+
+        let restored = doc.undelete(&ROOT, "text").unwrap().unwrap();
+        assert_eq!(doc.text(&restored).unwrap(), "hello world");
+        assert_eq!(doc.marks(&restored).unwrap(), vec![bold]);
+    }
+}
@@ -597,6 +597,11 @@ impl<'a, 'b> ReadDoc for ReadDocAt<'a, 'b> {
         self.doc.parents_at(obj, self.heads)
     }
 
+    fn count_conflicts(&self) -> usize {
+        self.doc
+            .count_conflicts_for(Some(&self.doc.clock_at(self.heads)))
+    }
+
     fn parents_at<O: AsRef<ExId>>(
         &self,
         obj: O,
@@ -938,6 +943,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn diff_map_overwrite_then_delete_inverts() {
+        let mut doc = AutoCommit::default();
+        doc.put(ROOT, "key", "value1").unwrap();
+        doc.put(ROOT, "other", "unchanged").unwrap();
+        let heads1 = doc.get_heads();
+        doc.put(ROOT, "key", "value2").unwrap();
+        let heads2 = doc.get_heads();
+        doc.delete(ROOT, "key").unwrap();
+        let heads3 = doc.get_heads();
+
+        assert_eq!(
+            exp(doc.diff(&heads1, &heads2)),
+            vec![ObservedPatch {
+                path: "/key".into(),
+                action: ObservedAction::PutMap {
+                    value: "value2".into(),
+                    conflict: false,
+                },
+            }]
+        );
+        assert_eq!(
+            exp(doc.diff(&heads2, &heads3)),
+            vec![ObservedPatch {
+                path: "/key".into(),
+                action: ObservedAction::DelMap,
+            }]
+        );
+        assert_eq!(
+            exp(doc.diff(&heads3, &heads2)),
+            vec![ObservedPatch {
+                path: "/key".into(),
+                action: ObservedAction::PutMap {
+                    value: "value2".into(),
+                    conflict: false,
+                },
+            }]
+        );
+        assert_eq!(
+            exp(doc.diff(&heads3, &heads1)),
+            vec![ObservedPatch {
+                path: "/key".into(),
+                action: ObservedAction::PutMap {
+                    value: "value1".into(),
+                    conflict: false,
+                },
+            }]
+        );
+        assert_eq!(exp(doc.diff(&heads2, &heads2)), vec![]);
+    }
+
     #[test]
     fn basic_diff_map_del3() {
         let mut doc = AutoCommit::default();
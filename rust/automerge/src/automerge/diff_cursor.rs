@@ -0,0 +1,56 @@
+use crate::patches::TextRepresentation;
+use crate::{Automerge, ChangeHash, Patch};
+
+/// Tracks the heads of a document which were last seen so that [`Self::patches`] returns only
+/// what has changed since
+///
+/// Create one with [`Automerge::new_diff_cursor`]. This is useful in render loops which need to
+/// update a view of the document each time it changes.
+///
+/// ```
+/// # use automerge::{transaction::Transactable, Automerge, ROOT};
+/// let mut doc = Automerge::new();
+/// let mut cursor = doc.new_diff_cursor();
+/// let mut tx = doc.transaction();
+/// tx.put(ROOT, "key", "value").unwrap();
+/// tx.commit();
+/// assert_eq!(cursor.patches(&doc).len(), 1);
+/// assert!(cursor.patches(&doc).is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffCursor {
+    heads: Vec<ChangeHash>,
+    text_rep: TextRepresentation,
+}
+
+impl DiffCursor {
+    /// Use `text_rep` to represent text in the patches this cursor produces
+    pub fn with_text_rep(mut self, text_rep: TextRepresentation) -> Self {
+        self.text_rep = text_rep;
+        self
+    }
+
+    /// The heads of the document when the cursor was created or last polled
+    pub fn heads(&self) -> &[ChangeHash] {
+        &self.heads
+    }
+
+    /// The patches which describe the changes to `doc` since this cursor was created or last
+    /// polled, moving the cursor to the current heads of `doc`
+    pub fn patches(&mut self, doc: &Automerge) -> Vec<Patch> {
+        let heads = doc.get_heads();
+        let patches = doc.diff(&self.heads, &heads, self.text_rep);
+        self.heads = heads;
+        patches
+    }
+}
+
+impl Automerge {
+    /// Create a [`DiffCursor`] starting at the current heads of this document
+    pub fn new_diff_cursor(&self) -> DiffCursor {
+        DiffCursor {
+            heads: self.get_heads(),
+            text_rep: TextRepresentation::default(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, ObjType, Prop, Value};
+
+impl Automerge {
+    /// The ID of the map at `prop` of `obj`, creating an empty map there in a new change if
+    /// `prop` has no value
+    ///
+    /// If `prop` already holds a map no change is made. A property whose value was deleted counts
+    /// as unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidValueType`] if `prop` holds a value which is not a map,
+    /// which is left as it is
+    pub fn get_or_create_map<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+    ) -> Result<ExId, AutomergeError> {
+        let mut tx = self.transaction();
+        match get_or_create_map(&mut tx, obj.as_ref(), prop.into()) {
+            Ok((id, true)) => {
+                tx.commit();
+                Ok(id)
+            }
+            Ok((id, false)) => {
+                tx.rollback();
+                Ok(id)
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// The map at `prop` of `obj` in `tx` and whether it was created, see
+/// [`Automerge::get_or_create_map`]
+pub(crate) fn get_or_create_map<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+) -> Result<(ExId, bool), AutomergeError> {
+    match tx.get(obj, prop.clone())? {
+        Some((Value::Object(ObjType::Map), id)) => Ok((id, false)),
+        Some((value, _)) => Err(AutomergeError::InvalidValueType {
+            expected: ObjType::Map.to_string(),
+            unexpected: match value {
+                Value::Object(typ) => typ.to_string(),
+                Value::Scalar(_) => "a scalar".to_string(),
+            },
+        }),
+        None => Ok((tx.put_object(obj, prop, ObjType::Map)?, true)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, ROOT};
+
+    #[test]
+    fn existing_maps_are_reused_and_missing_ones_created() {
+        let mut doc = Automerge::new();
+        let settings = doc.get_or_create_map(ROOT, "settings").unwrap();
+        assert_eq!(doc.object_type(&settings).unwrap(), ObjType::Map);
+        let heads = doc.get_heads();
+        assert_eq!(doc.get_or_create_map(ROOT, "settings").unwrap(), settings);
+        assert_eq!(doc.get_heads(), heads);
+
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "name", "doc").unwrap();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        assert!(matches!(
+            doc.get_or_create_map(ROOT, "name"),
+            Err(AutomergeError::InvalidValueType { .. })
+        ));
+        assert!(matches!(
+            doc.get_or_create_map(ROOT, "list"),
+            Err(AutomergeError::InvalidValueType { .. })
+        ));
+        doc.insert(&list, 0, 1).unwrap();
+        let item = doc.get_or_create_map(&list, 1).unwrap_err();
+        assert!(matches!(item, AutomergeError::InvalidIndex(1)));
+        let nested = doc.get_or_create_map(ROOT, "nested").unwrap();
+        assert_eq!(doc.get_or_create_map(ROOT, "nested").unwrap(), nested);
+    }
+}
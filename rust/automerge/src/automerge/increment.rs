@@ -0,0 +1,55 @@
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, Prop, ReadDoc, ScalarValue, Value};
+
+impl Automerge {
+    /// Increment the counter at `prop` in the map or list `obj` by `by` in a new change
+    ///
+    /// Unlike [`Transactable::increment`] this tells apart a missing property from one which
+    /// doesn't hold a counter. If the property has conflicting values it is enough for one of them
+    /// to be a counter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingProp`] if there is no value at `prop` and
+    /// [`AutomergeError::MissingCounter`] if none of the values at `prop` are counters.
+    pub fn increment_by<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        by: i64,
+    ) -> Result<(), AutomergeError> {
+        let prop = prop.into();
+        check_counter(self, obj.as_ref(), &prop)?;
+        let mut tx = self.transaction();
+        match tx.increment(obj, prop, by) {
+            Ok(()) => {
+                tx.commit();
+                Ok(())
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Check that some value at `prop` in `obj` is a counter, see [`Automerge::increment_by`]
+pub(crate) fn check_counter<R: ReadDoc>(
+    doc: &R,
+    obj: &ExId,
+    prop: &Prop,
+) -> Result<(), AutomergeError> {
+    let values = doc.get_all(obj, prop.clone())?;
+    if values.is_empty() {
+        return Err(AutomergeError::MissingProp(prop.clone()));
+    }
+    if values.iter().any(
+        |(v, _)| matches!(v, Value::Scalar(s) if matches!(s.as_ref(), ScalarValue::Counter(_))),
+    ) {
+        Ok(())
+    } else {
+        Err(AutomergeError::MissingCounter)
+    }
+}
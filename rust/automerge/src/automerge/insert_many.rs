@@ -0,0 +1,64 @@
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, ScalarValue};
+
+impl Automerge {
+    /// Insert each of `vals` into the list `obj`, starting at `pos`, in a new change
+    ///
+    /// Returns the ID of each inserted element, in order, see
+    /// [`Transactable::list_insert_many`]. There is one insert op per value but the insertion
+    /// point is only looked up once, and as the ops are consecutive they encode compactly in the
+    /// change. If the insertion fails no change is made.
+    pub fn list_insert_many<O: AsRef<ExId>, I: IntoIterator<Item = ScalarValue>>(
+        &mut self,
+        obj: O,
+        pos: usize,
+        values: I,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        let mut tx = self.transaction();
+        match tx.list_insert_many(obj, pos, values) {
+            Ok(ids) => {
+                tx.commit();
+                Ok(ids)
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction::Transactable;
+    use crate::{Automerge, AutomergeError, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    #[test]
+    fn values_are_inserted_in_a_single_change() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+        tx.insert(&list, 0, "first").unwrap();
+        tx.commit();
+
+        let ids = doc
+            .list_insert_many(&list, 1, (0..100).map(ScalarValue::Int))
+            .unwrap();
+        assert_eq!(ids.len(), 100);
+        assert_eq!(doc.get_changes(&[]).len(), 2);
+        assert_eq!(doc.get_last_local_change().unwrap().len(), 100);
+        assert_eq!(doc.length(&list), 101);
+        assert_eq!(
+            doc.get(&list, 50).unwrap().unwrap(),
+            (49.into(), ids[49].clone())
+        );
+
+        let heads = doc.get_heads();
+        assert!(matches!(
+            doc.list_insert_many(ROOT, 0, vec![ScalarValue::Int(1)]),
+            Err(AutomergeError::InvalidOp(ObjType::Map))
+        ));
+        assert_eq!(doc.get_heads(), heads);
+    }
+}
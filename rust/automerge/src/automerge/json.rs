@@ -0,0 +1,728 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{Map, Number, Value as Json};
+
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{
+    Automerge, AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue, Value, ROOT,
+};
+
+impl Automerge {
+    /// Create a document whose root map has the contents of the JSON object `value`
+    ///
+    /// JSON objects become maps, arrays become lists and everything else becomes a scalar.
+    /// Strings become [`ScalarValue::Str`] rather than text objects and `null` becomes
+    /// [`ScalarValue::Null`]. A number becomes [`ScalarValue::Int`] only if
+    /// [`serde_json::Number::is_i64`] is true and [`ScalarValue::F64`] otherwise, so a number
+    /// written with a fraction or exponent such as `3.0` stays a float, as do integers too large
+    /// for an `i64`. The whole document is created in a single change.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidValueType`] if `value` is not an object.
+    pub fn import_json(value: &Json) -> Result<Automerge, AutomergeError> {
+        let root = match value {
+            Json::Object(map) => map,
+            other => {
+                return Err(AutomergeError::InvalidValueType {
+                    expected: "an object".to_string(),
+                    unexpected: json_type(other).to_string(),
+                })
+            }
+        };
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        import_map(&mut tx, &ROOT, root)?;
+        tx.commit();
+        Ok(doc)
+    }
+
+    /// The current state of this document as JSON
+    ///
+    /// Maps and tables become JSON objects, lists become arrays and text becomes a string.
+    /// Counters become their current value, timestamps the number of milliseconds since the
+    /// epoch and bytes an array of numbers. Where a property has conflicting values the value
+    /// returned by [`ReadDoc::get`] is used.
+    pub fn export_json(&self) -> Json {
+        json_at(self, Value::Object(ObjType::Map), ROOT, None)
+            .expect("the root and objects found in the document should exist")
+    }
+
+    /// The current state of the list `obj` as a JSON array
+    ///
+    /// Values are converted as in [`Self::export_json`], including any objects in the list. An
+    /// index with conflicting values becomes an object with a single `"__am_conflict"` key holding
+    /// an array of every value, in the order returned by [`ReadDoc::get_all`], so the array is
+    /// always as long as the list. A map in the list which has an `"__am_conflict"` key of its own
+    /// is wrapped in the same way, as a conflict with only that value, so it can't be mistaken for
+    /// a conflict. Real conflicts always have at least two values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a list.
+    pub fn list_to_json(&self, obj: &ExId) -> Result<Json, AutomergeError> {
+        match self.object_type(obj)? {
+            ObjType::List => {}
+            typ => return Err(AutomergeError::InvalidOp(typ)),
+        }
+        self.list_range(obj, ..)
+            .map(|item| {
+                if !item.conflict {
+                    return match json_at(self, item.value, item.id, None)? {
+                        Json::Object(map) if map.contains_key(CONFLICT_KEY) => {
+                            Ok(conflict_json(vec![Json::Object(map)]))
+                        }
+                        value => Ok(value),
+                    };
+                }
+                let values = self
+                    .get_all(obj, item.index)?
+                    .into_iter()
+                    .map(|(value, id)| json_at(self, value, id, None))
+                    .collect::<Result<_, _>>()?;
+                Ok(conflict_json(values))
+            })
+            .collect::<Result<_, _>>()
+            .map(Json::Array)
+    }
+
+    /// The current state of this document as JSON which preserves the type of every value
+    ///
+    /// Maps, lists, strings, booleans and null are written as plain JSON. Everything else is
+    /// written as an object naming its type, for example `{"__am_type": "counter", "value": 3}`:
+    ///
+    /// | `__am_type`  | `value`                                                    |
+    /// |--------------|------------------------------------------------------------|
+    /// | `text`       | the text as a string                                       |
+    /// | `table`      | an object                                                  |
+    /// | `map`        | an object, used only for maps with an `__am_type` key      |
+    /// | `int`        | a number                                                   |
+    /// | `uint`       | a number                                                   |
+    /// | `f64`        | a number, or `"NaN"`, `"Infinity"` or `"-Infinity"`        |
+    /// | `counter`    | the current value of the counter                           |
+    /// | `timestamp`  | milliseconds since the epoch                               |
+    /// | `bytes`      | a base64 string                                            |
+    /// | `unknown`    | `{"typeCode": 1, "bytes": "<base64>"}`                     |
+    ///
+    /// [`Self::from_json_typed`] reads this representation back. Where a property has
+    /// conflicting values the value returned by [`ReadDoc::get`] is used.
+    pub fn to_json_typed(&self) -> Json {
+        typed_json_at(self, Value::Object(ObjType::Map), ROOT)
+            .expect("the root and objects found in the document should exist")
+    }
+
+    /// Create a document from the output of [`Self::to_json_typed`] in a single change
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidTypedJson`] if `json` isn't a map, either a plain object or
+    /// the tagged `map` form, or contains a malformed `__am_type` object.
+    pub fn from_json_typed(json: &Json) -> Result<Automerge, AutomergeError> {
+        // A root map with an `__am_type` key is written in the tagged `map` form
+        let root = match parse_typed(json)? {
+            Typed::Map(ObjType::Map, map) => map,
+            _ => return Err(invalid_typed("the root must be a map")),
+        };
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        for (key, value) in root {
+            put_typed(&mut tx, &ROOT, key.as_str().into(), value)?;
+        }
+        tx.commit();
+        Ok(doc)
+    }
+}
+
+/// The JSON representation of `value`, which lives at `obj`, as of `heads` or the current state
+/// if `heads` is `None`
+pub(crate) fn json_at<R: ReadDoc>(
+    doc: &R,
+    value: Value<'_>,
+    obj: ExId,
+    heads: Option<&[ChangeHash]>,
+) -> Result<Json, AutomergeError> {
+    match value {
+        Value::Object(ObjType::Map | ObjType::Table) => {
+            let mut map = Map::new();
+            let items = match heads {
+                Some(heads) => doc.map_range_at(&obj, .., heads),
+                None => doc.map_range(&obj, ..),
+            };
+            for item in items {
+                let value = json_at(doc, item.value, item.id, heads)?;
+                map.insert(item.key.to_string(), value);
+            }
+            Ok(Json::Object(map))
+        }
+        Value::Object(ObjType::List) => {
+            let items = match heads {
+                Some(heads) => doc.list_range_at(&obj, .., heads),
+                None => doc.list_range(&obj, ..),
+            };
+            items
+                .map(|item| json_at(doc, item.value, item.id, heads))
+                .collect::<Result<_, _>>()
+                .map(Json::Array)
+        }
+        Value::Object(ObjType::Text) => {
+            let text = match heads {
+                Some(heads) => doc.text_at(&obj, heads)?,
+                None => doc.text(&obj)?,
+            };
+            Ok(Json::String(text))
+        }
+        // Scalars only fail to serialize if they contain non string map keys, which they don't
+        Value::Scalar(s) => Ok(serde_json::to_value(s.as_ref()).unwrap_or(Json::Null)),
+    }
+}
+
+/// The key holding the values of a conflicted index in [`Automerge::list_to_json`]
+const CONFLICT_KEY: &str = "__am_conflict";
+
+fn conflict_json(values: Vec<Json>) -> Json {
+    let mut conflict = Map::new();
+    conflict.insert(CONFLICT_KEY.to_string(), Json::Array(values));
+    Json::Object(conflict)
+}
+
+/// The key which names the type of a tagged value in [`Automerge::to_json_typed`]
+const TYPE_KEY: &str = "__am_type";
+
+fn tagged(type_name: &str, value: Json) -> Json {
+    let mut map = Map::new();
+    map.insert(TYPE_KEY.to_string(), Json::String(type_name.to_string()));
+    map.insert("value".to_string(), value);
+    Json::Object(map)
+}
+
+/// The typed JSON representation of `value`, which lives at `obj`
+fn typed_json_at<R: ReadDoc>(doc: &R, value: Value<'_>, obj: ExId) -> Result<Json, AutomergeError> {
+    match value {
+        Value::Object(obj_type @ (ObjType::Map | ObjType::Table)) => {
+            let mut map = Map::new();
+            for item in doc.map_range(&obj, ..) {
+                let value = typed_json_at(doc, item.value, item.id)?;
+                map.insert(item.key.to_string(), value);
+            }
+            if obj_type == ObjType::Table {
+                Ok(tagged("table", Json::Object(map)))
+            } else if map.contains_key(TYPE_KEY) {
+                Ok(tagged("map", Json::Object(map)))
+            } else {
+                Ok(Json::Object(map))
+            }
+        }
+        Value::Object(ObjType::List) => doc
+            .list_range(&obj, ..)
+            .map(|item| typed_json_at(doc, item.value, item.id))
+            .collect::<Result<_, _>>()
+            .map(Json::Array),
+        Value::Object(ObjType::Text) => Ok(tagged("text", Json::String(doc.text(&obj)?))),
+        Value::Scalar(s) => Ok(typed_scalar(&s)),
+    }
+}
+
+fn typed_scalar(value: &ScalarValue) -> Json {
+    match value {
+        ScalarValue::Str(s) => Json::String(s.to_string()),
+        ScalarValue::Boolean(b) => Json::Bool(*b),
+        ScalarValue::Null => Json::Null,
+        ScalarValue::Int(i) => tagged("int", Json::from(*i)),
+        ScalarValue::Uint(u) => tagged("uint", Json::from(*u)),
+        ScalarValue::F64(f) => {
+            let value = match Number::from_f64(*f) {
+                Some(n) => Json::Number(n),
+                None if f.is_nan() => Json::from("NaN"),
+                None if *f > 0.0 => Json::from("Infinity"),
+                None => Json::from("-Infinity"),
+            };
+            tagged("f64", value)
+        }
+        ScalarValue::Counter(c) => tagged("counter", Json::from(i64::from(c))),
+        ScalarValue::Timestamp(t) => tagged("timestamp", Json::from(*t)),
+        ScalarValue::Bytes(b) => tagged("bytes", Json::String(BASE64.encode(b))),
+        ScalarValue::Unknown { type_code, bytes } => {
+            let mut map = Map::new();
+            map.insert("typeCode".to_string(), Json::from(*type_code));
+            map.insert("bytes".to_string(), Json::String(BASE64.encode(bytes)));
+            tagged("unknown", Json::Object(map))
+        }
+    }
+}
+
+/// A typed JSON value read by [`Automerge::from_json_typed`]
+enum Typed<'a> {
+    Map(ObjType, &'a Map<String, Json>),
+    List(&'a [Json]),
+    Text(&'a str),
+    Scalar(ScalarValue),
+}
+
+fn parse_typed(value: &Json) -> Result<Typed<'_>, AutomergeError> {
+    let map = match value {
+        Json::Object(map) => map,
+        Json::Array(list) => return Ok(Typed::List(list)),
+        Json::String(s) => return Ok(Typed::Scalar(ScalarValue::Str(s.as_str().into()))),
+        Json::Bool(b) => return Ok(Typed::Scalar(ScalarValue::Boolean(*b))),
+        Json::Null => return Ok(Typed::Scalar(ScalarValue::Null)),
+        Json::Number(_) => return Err(invalid_typed("numbers must be tagged with their type")),
+    };
+    let type_name = match map.get(TYPE_KEY) {
+        None => return Ok(Typed::Map(ObjType::Map, map)),
+        Some(Json::String(type_name)) => type_name.as_str(),
+        Some(_) => return Err(invalid_typed("`__am_type` must be a string")),
+    };
+    let inner = match map.get("value") {
+        Some(inner) if map.len() == 2 => inner,
+        _ => {
+            return Err(invalid_typed(format!(
+                "a `{}` value must have exactly the keys `__am_type` and `value`",
+                type_name
+            )))
+        }
+    };
+    let malformed = || invalid_typed(format!("malformed `{}` value", type_name));
+    let typed = match (type_name, inner) {
+        ("map", Json::Object(map)) => Typed::Map(ObjType::Map, map),
+        ("table", Json::Object(map)) => Typed::Map(ObjType::Table, map),
+        ("text", Json::String(text)) => Typed::Text(text),
+        ("int", Json::Number(n)) => {
+            Typed::Scalar(ScalarValue::Int(n.as_i64().ok_or_else(malformed)?))
+        }
+        ("uint", Json::Number(n)) => {
+            Typed::Scalar(ScalarValue::Uint(n.as_u64().ok_or_else(malformed)?))
+        }
+        ("f64", Json::Number(n)) => {
+            Typed::Scalar(ScalarValue::F64(n.as_f64().ok_or_else(malformed)?))
+        }
+        ("f64", Json::String(s)) => Typed::Scalar(ScalarValue::F64(match s.as_str() {
+            "NaN" => f64::NAN,
+            "Infinity" => f64::INFINITY,
+            "-Infinity" => f64::NEG_INFINITY,
+            _ => return Err(malformed()),
+        })),
+        ("counter", Json::Number(n)) => {
+            Typed::Scalar(ScalarValue::counter(n.as_i64().ok_or_else(malformed)?))
+        }
+        ("timestamp", Json::Number(n)) => {
+            Typed::Scalar(ScalarValue::Timestamp(n.as_i64().ok_or_else(malformed)?))
+        }
+        ("bytes", Json::String(s)) => Typed::Scalar(ScalarValue::Bytes(
+            BASE64.decode(s).map_err(|_| malformed())?,
+        )),
+        ("unknown", Json::Object(unknown)) => {
+            let type_code = unknown
+                .get("typeCode")
+                .and_then(Json::as_u64)
+                .and_then(|code| u8::try_from(code).ok())
+                .ok_or_else(malformed)?;
+            let bytes = unknown
+                .get("bytes")
+                .and_then(Json::as_str)
+                .and_then(|s| BASE64.decode(s).ok())
+                .ok_or_else(malformed)?;
+            Typed::Scalar(ScalarValue::Unknown { type_code, bytes })
+        }
+        (
+            "map" | "table" | "text" | "int" | "uint" | "f64" | "counter" | "timestamp" | "bytes"
+            | "unknown",
+            _,
+        ) => return Err(malformed()),
+        (other, _) => return Err(invalid_typed(format!("unknown `__am_type` `{}`", other))),
+    };
+    Ok(typed)
+}
+
+/// Set `prop` of `obj` to the typed JSON `value`
+fn put_typed<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+    value: &Json,
+) -> Result<(), AutomergeError> {
+    let child = match parse_typed(value)? {
+        Typed::Scalar(s) => return tx.put(obj, prop, s),
+        Typed::Map(obj_type, _) => tx.put_object(obj, prop, obj_type)?,
+        Typed::List(_) => tx.put_object(obj, prop, ObjType::List)?,
+        Typed::Text(_) => tx.put_object(obj, prop, ObjType::Text)?,
+    };
+    fill_typed(tx, &child, value)
+}
+
+/// Insert the typed JSON `value` into the list `obj` at `index`
+fn insert_typed<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    index: usize,
+    value: &Json,
+) -> Result<(), AutomergeError> {
+    let child = match parse_typed(value)? {
+        Typed::Scalar(s) => return tx.insert(obj, index, s),
+        Typed::Map(obj_type, _) => tx.insert_object(obj, index, obj_type)?,
+        Typed::List(_) => tx.insert_object(obj, index, ObjType::List)?,
+        Typed::Text(_) => tx.insert_object(obj, index, ObjType::Text)?,
+    };
+    fill_typed(tx, &child, value)
+}
+
+/// Fill the newly created object `obj` with the contents of the typed JSON `value`
+fn fill_typed<T: Transactable>(tx: &mut T, obj: &ExId, value: &Json) -> Result<(), AutomergeError> {
+    match parse_typed(value)? {
+        Typed::Map(_, map) => {
+            for (key, value) in map {
+                put_typed(tx, obj, key.as_str().into(), value)?;
+            }
+        }
+        Typed::List(list) => {
+            for (index, value) in list.iter().enumerate() {
+                insert_typed(tx, obj, index, value)?;
+            }
+        }
+        Typed::Text(text) => tx.splice_text(obj, 0, 0, text)?,
+        Typed::Scalar(_) => {}
+    }
+    Ok(())
+}
+
+fn invalid_typed<S: Into<String>>(reason: S) -> AutomergeError {
+    AutomergeError::InvalidTypedJson(reason.into())
+}
+
+pub(crate) fn import_map<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    map: &Map<String, Json>,
+) -> Result<(), AutomergeError> {
+    for (key, value) in map {
+        put_json(tx, obj, key.as_str().into(), value)?;
+    }
+    Ok(())
+}
+
+fn import_list<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    list: &[Json],
+) -> Result<(), AutomergeError> {
+    for (index, value) in list.iter().enumerate() {
+        insert_json(tx, obj, index, value)?;
+    }
+    Ok(())
+}
+
+/// Set `prop` of `obj` to `value`, creating maps and lists for JSON objects and arrays
+pub(crate) fn put_json<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+    value: &Json,
+) -> Result<(), AutomergeError> {
+    match value {
+        Json::Object(map) => {
+            let child = tx.put_object(obj, prop, ObjType::Map)?;
+            import_map(tx, &child, map)
+        }
+        Json::Array(list) => {
+            let child = tx.put_object(obj, prop, ObjType::List)?;
+            import_list(tx, &child, list)
+        }
+        scalar => tx.put(obj, prop, to_scalar(scalar)),
+    }
+}
+
+/// Insert `value` into the list `obj` at `index`, creating maps and lists for JSON objects and
+/// arrays
+pub(crate) fn insert_json<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    index: usize,
+    value: &Json,
+) -> Result<(), AutomergeError> {
+    match value {
+        Json::Object(map) => {
+            let child = tx.insert_object(obj, index, ObjType::Map)?;
+            import_map(tx, &child, map)
+        }
+        Json::Array(list) => {
+            let child = tx.insert_object(obj, index, ObjType::List)?;
+            import_list(tx, &child, list)
+        }
+        scalar => tx.insert(obj, index, to_scalar(scalar)),
+    }
+}
+
+/// Convert a JSON value which is not an object or an array to a scalar
+fn to_scalar(value: &Json) -> ScalarValue {
+    match value {
+        Json::Null | Json::Object(_) | Json::Array(_) => ScalarValue::Null,
+        Json::Bool(b) => ScalarValue::Boolean(*b),
+        Json::String(s) => ScalarValue::Str(s.as_str().into()),
+        Json::Number(n) => number_to_scalar(n),
+    }
+}
+
+pub(crate) fn number_to_scalar(n: &Number) -> ScalarValue {
+    match n.as_i64() {
+        Some(i) => ScalarValue::Int(i),
+        // Every JSON number is representable as an f64, if only approximately
+        None => ScalarValue::F64(n.as_f64().unwrap_or(f64::NAN)),
+    }
+}
+
+pub(crate) fn json_type(value: &Json) -> &'static str {
+    match value {
+        Json::Null => "null",
+        Json::Bool(_) => "a boolean",
+        Json::Number(_) => "a number",
+        Json::String(_) => "a string",
+        Json::Array(_) => "an array",
+        Json::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::transaction::Transactable;
+    use crate::{hydrate, hydrate_list, hydrate_map};
+    use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    #[test]
+    fn import_json_builds_nested_objects() {
+        let doc = Automerge::import_json(&json!({
+            "name": "alice",
+            "age": 30,
+            "ratio": 0.5,
+            "admin": false,
+            "manager": null,
+            "tags": ["a", {"nested": [1, []]}]
+        }))
+        .unwrap();
+        assert_eq!(doc.get_changes(&[]).len(), 1);
+        let expected = hydrate_map!(
+            "name" => "alice",
+            "age" => 30,
+            "ratio" => 0.5,
+            "admin" => false,
+            "manager" => ScalarValue::Null,
+            "tags" => hydrate_list!["a", hydrate_map!("nested" => hydrate_list![1, hydrate_list![]])],
+        );
+        assert_eq!(doc.hydrate(None), expected);
+        assert_eq!(
+            doc.get(ROOT, "name").unwrap().unwrap().0.to_str(),
+            Some("alice")
+        );
+    }
+
+    #[test]
+    fn export_json_uses_current_values() {
+        let mut doc1 = AutoCommit::new();
+        let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc1.splice_text(&text, 0, 0, "hello").unwrap();
+        doc1.put_object(ROOT, "table", ObjType::Table).unwrap();
+        let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc1.insert(&list, 0, ScalarValue::counter(1)).unwrap();
+        doc1.insert(&list, 1, ScalarValue::Timestamp(1000)).unwrap();
+        doc1.insert(&list, 2, vec![1u8, 2]).unwrap();
+        doc1.insert(&list, 3, ScalarValue::Null).unwrap();
+        doc1.put(ROOT, "conflict", "a").unwrap();
+        let mut doc2 = doc1.fork();
+        doc1.put(ROOT, "conflict", "b").unwrap();
+        doc2.put(ROOT, "conflict", "c").unwrap();
+        doc1.merge(&mut doc2).unwrap();
+        doc1.increment(&list, 0, 2).unwrap();
+
+        let winner = doc1.get(ROOT, "conflict").unwrap().unwrap().0.into_owned();
+        let doc = doc1.document();
+        assert_eq!(
+            doc.export_json(),
+            json!({
+                "text": "hello",
+                "table": {},
+                "list": [3, 1000, [1, 2], null],
+                "conflict": winner.to_str().unwrap(),
+            })
+        );
+
+        let json = json!({"a": [1, {"b": null}], "c": "d"});
+        let imported = Automerge::import_json(&json).unwrap();
+        assert_eq!(imported.export_json(), json);
+    }
+
+    #[test]
+    fn to_json_typed_tags_non_json_values() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "counter", ScalarValue::counter(3)).unwrap();
+        doc.put(ROOT, "int", 1).unwrap();
+        doc.put(ROOT, "float", 1.0).unwrap();
+        doc.put(ROOT, "bytes", vec![0u8, 1, 2, 255]).unwrap();
+        doc.put(ROOT, "str", "plain").unwrap();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hi").unwrap();
+        let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+        doc.put(&map, "__am_type", "mine").unwrap();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, true).unwrap();
+        doc.insert(&list, 1, ScalarValue::Null).unwrap();
+
+        let typed = doc.to_json_typed();
+        assert_eq!(
+            typed,
+            json!({
+                "counter": {"__am_type": "counter", "value": 3},
+                "int": {"__am_type": "int", "value": 1},
+                "float": {"__am_type": "f64", "value": 1.0},
+                "bytes": {"__am_type": "bytes", "value": "AAEC/w=="},
+                "str": "plain",
+                "text": {"__am_type": "text", "value": "hi"},
+                "map": {"__am_type": "map", "value": {"__am_type": "mine"}},
+                "list": [true, null],
+            })
+        );
+        let restored = Automerge::from_json_typed(&typed).unwrap();
+        assert_eq!(restored.hydrate(None), doc.hydrate(None));
+    }
+
+    #[test]
+    fn from_json_typed_rejects_malformed_values() {
+        for bad in [
+            json!([]),
+            json!({"__am_type": "x"}),
+            json!({"__am_type": "table", "value": {}}),
+            json!({"n": 1}),
+            json!({"v": {"__am_type": "counter", "value": "3"}}),
+            json!({"v": {"__am_type": "counter"}}),
+            json!({"v": {"__am_type": "nope", "value": 1}}),
+            json!({"v": {"__am_type": "bytes", "value": "AAE"}}),
+            json!({"v": {"__am_type": "int", "value": 1, "extra": 2}}),
+        ] {
+            assert!(
+                matches!(
+                    Automerge::from_json_typed(&bad),
+                    Err(AutomergeError::InvalidTypedJson(_))
+                ),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn typed_json_round_trips_a_root_with_a_type_key() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "__am_type", "x").unwrap();
+        doc.put(ROOT, "n", 1).unwrap();
+
+        let typed = doc.to_json_typed();
+        assert_eq!(
+            typed,
+            json!({"__am_type": "map", "value": {"__am_type": "x", "n": {"__am_type": "int", "value": 1}}})
+        );
+        let restored = Automerge::from_json_typed(&typed).unwrap();
+        assert_eq!(restored.hydrate(None), doc.hydrate(None));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn typed_json_round_trips_scalars(value in crate::types::gen::gen_scalar_value()) {
+            let mut doc = AutoCommit::new();
+            doc.put(ROOT, "value", value.clone()).unwrap();
+            let text = serde_json::to_string(&doc.to_json_typed()).unwrap();
+            let restored =
+                Automerge::from_json_typed(&serde_json::from_str(&text).unwrap()).unwrap();
+            let (restored, _) = restored.get(ROOT, "value").unwrap().unwrap();
+            let restored = restored.to_scalar().unwrap().clone();
+            match (&value, &restored) {
+                (ScalarValue::F64(a), ScalarValue::F64(b)) if a.is_nan() => {
+                    proptest::prop_assert!(b.is_nan())
+                }
+                _ => proptest::prop_assert_eq!(&restored, &value),
+            }
+        }
+    }
+
+    #[test]
+    fn import_json_builds_arrays_of_objects() {
+        let doc = Automerge::import_json(&json!({
+            "rows": [[{"id": 1}, {"id": 2, "cells": [{"v": "x"}]}], []]
+        }))
+        .unwrap();
+        let expected = hydrate_map!(
+            "rows" => hydrate_list![
+                hydrate_list![
+                    hydrate_map!("id" => 1),
+                    hydrate_map!("id" => 2, "cells" => hydrate_list![hydrate_map!("v" => "x")]),
+                ],
+                hydrate_list![],
+            ],
+        );
+        assert_eq!(doc.hydrate(None), expected);
+    }
+
+    #[test]
+    fn import_json_only_uses_int_for_i64_numbers() {
+        let doc: Automerge = Automerge::import_json(
+            &serde_json::from_str(
+                r#"{"int": -7, "whole": 3.0, "large": 1e300, "huge": 18446744073709551615}"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let get = |key: &str| doc.get(ROOT, key).unwrap().unwrap().0.into_owned();
+        assert_eq!(get("int"), ScalarValue::Int(-7).into());
+        assert_eq!(get("whole"), ScalarValue::F64(3.0).into());
+        assert_eq!(get("large"), ScalarValue::F64(1e300).into());
+        assert_eq!(get("huge"), ScalarValue::F64(18446744073709551615.0).into());
+    }
+
+    #[test]
+    fn import_json_rejects_non_objects() {
+        assert!(matches!(
+            Automerge::import_json(&json!([1, 2])),
+            Err(AutomergeError::InvalidValueType { .. })
+        ));
+    }
+
+    #[test]
+    fn list_to_json_marks_conflicting_indices() {
+        let mut doc1 = AutoCommit::new();
+        doc1.set_actor(crate::ActorId::from([1]));
+        let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc1.insert(&list, 0, "a").unwrap();
+        let map = doc1.insert_object(&list, 1, ObjType::Map).unwrap();
+        doc1.put(&map, "n", 1).unwrap();
+        doc1.insert(&list, 2, "c").unwrap();
+        let mut doc2 = doc1.fork().with_actor(crate::ActorId::from([2]));
+        doc1.put(&list, 2, "x").unwrap();
+        doc2.put(&list, 2, "y").unwrap();
+        doc1.merge(&mut doc2).unwrap();
+
+        assert_eq!(
+            doc1.list_to_json(&list).unwrap(),
+            json!(["a", {"n": 1}, {"__am_conflict": ["x", "y"]}])
+        );
+        assert!(matches!(
+            doc1.list_to_json(&ROOT),
+            Err(AutomergeError::InvalidOp(ObjType::Map))
+        ));
+    }
+
+    #[test]
+    fn list_to_json_wraps_maps_which_look_like_conflicts() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        let map = doc.insert_object(&list, 0, ObjType::Map).unwrap();
+        let values = doc
+            .put_object(&map, "__am_conflict", ObjType::List)
+            .unwrap();
+        doc.insert(&values, 0, "x").unwrap();
+        doc.insert(&values, 1, "y").unwrap();
+
+        assert_eq!(
+            doc.list_to_json(&list).unwrap(),
+            json!([{"__am_conflict": [{"__am_conflict": ["x", "y"]}]}])
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+use super::json::{insert_json, json_at, put_json};
+use crate::exid::ExId;
+use crate::patches::{Patch, PatchAction, TextRepresentation};
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, Value};
+
+/// A single operation of an [RFC 6902] JSON Patch
+///
+/// This serializes to and deserializes from the JSON representation in the RFC, so a whole
+/// patch can be read with `serde_json::from_str::<Vec<JsonPatchOp>>`. Paths are [RFC 6901] JSON
+/// pointers.
+///
+/// [RFC 6902]: https://www.rfc-editor.org/rfc/rfc6902
+/// [RFC 6901]: https://www.rfc-editor.org/rfc/rfc6901
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatchOp {
+    /// Insert `value` into an array or set a key in an object
+    Add { path: String, value: Json },
+    /// Remove the value at `path`
+    Remove { path: String },
+    /// Replace the value at `path`, which must exist, with `value`
+    Replace { path: String, value: Json },
+    /// Remove the value at `from` and add it at `path`
+    Move { from: String, path: String },
+    /// Add a copy of the value at `from` at `path`
+    Copy { from: String, path: String },
+    /// Check that the value at `path` is equal to `value`
+    Test { path: String, value: Json },
+}
+
+impl Automerge {
+    /// Describe the difference between two states of the document as an [RFC 6902] JSON Patch
+    ///
+    /// The result is a list of `add`, `remove` and `replace` operations which, applied in order to
+    /// the JSON representation of the document at `before`, produce the JSON representation of
+    /// the document at `after`. Text objects are represented as JSON strings, so any edit to a text
+    /// object is emitted as a single `replace` of the whole string. Counters are represented by
+    /// their current value and marks are not represented at all.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidHash`] if any of the heads are not in this document.
+    ///
+    /// [RFC 6902]: https://www.rfc-editor.org/rfc/rfc6902
+    pub fn diff_as_json_patch(
+        &self,
+        before: &[ChangeHash],
+        after: &[ChangeHash],
+    ) -> Result<Vec<JsonPatchOp>, AutomergeError> {
+        for hash in before.iter().chain(after) {
+            if !self.history_index.contains_key(hash) {
+                return Err(AutomergeError::InvalidHash(*hash));
+            }
+        }
+        let patches = self.diff(before, after, TextRepresentation::String);
+        let mut builder = JsonPatchBuilder {
+            doc: self,
+            heads: after,
+            ops: Vec::new(),
+            created: HashSet::new(),
+            replaced: HashSet::new(),
+        };
+        for patch in patches {
+            builder.push(patch)?;
+        }
+        Ok(builder.ops)
+    }
+}
+
+impl Automerge {
+    /// Apply an [RFC 6902] JSON Patch to this document as a single change
+    ///
+    /// `add`, `remove`, `replace` and `test` are supported, with JSON objects and arrays becoming
+    /// maps and lists as in [`Self::import_json`]. Replacing a text object with a string edits
+    /// the text with [`crate::transaction::Transactable::text_set`] rather than replacing it, so
+    /// a patch from [`Self::diff_as_json_patch`] can be applied to another replica. Paths can't
+    /// refer to individual characters of text. If any operation fails none of the patch is
+    /// applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::UnsupportedJsonPatchOp`] for `move` and `copy` operations and
+    /// [`AutomergeError::InvalidJsonPatch`] if a path doesn't refer to a value it can operate on
+    /// or a `test` fails.
+    ///
+    /// [RFC 6902]: https://www.rfc-editor.org/rfc/rfc6902
+    pub fn apply_json_patch(&mut self, ops: &[JsonPatchOp]) -> Result<(), AutomergeError> {
+        let mut tx = self.transaction();
+        match apply_json_patch(&mut tx, ops) {
+            Ok(()) => {
+                tx.commit();
+                Ok(())
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Apply `ops` to `tx`, see [`Automerge::apply_json_patch`]
+pub(crate) fn apply_json_patch<T: Transactable>(
+    tx: &mut T,
+    ops: &[JsonPatchOp],
+) -> Result<(), AutomergeError> {
+    for op in ops {
+        match op {
+            JsonPatchOp::Add { path, value } => match resolve_parent(tx, path)? {
+                None => set_root(tx, path, value)?,
+                Some((obj, token)) => match tx.object_type(&obj)? {
+                    ObjType::List => {
+                        let index = if token == "-" {
+                            tx.length(&obj)
+                        } else {
+                            parse_index(&token, tx.length(&obj) + 1, path)?
+                        };
+                        insert_json(tx, &obj, index, value)?;
+                    }
+                    ObjType::Text => return Err(text_path(path)),
+                    ObjType::Map | ObjType::Table => set(tx, &obj, token.into(), value)?,
+                },
+            },
+            JsonPatchOp::Remove { path } => match resolve_parent(tx, path)? {
+                None => return Err(invalid(path, "the root can't be removed")),
+                Some((obj, token)) => {
+                    let prop = existing_prop(tx, &obj, token, path)?;
+                    tx.delete(&obj, prop)?;
+                }
+            },
+            JsonPatchOp::Replace { path, value } => match resolve_parent(tx, path)? {
+                None => set_root(tx, path, value)?,
+                Some((obj, token)) => {
+                    let prop = existing_prop(tx, &obj, token, path)?;
+                    set(tx, &obj, prop, value)?;
+                }
+            },
+            JsonPatchOp::Test { path, value } => {
+                let current = match resolve_parent(tx, path)? {
+                    None => json_at(&*tx, Value::Object(ObjType::Map), ExId::Root, None)?,
+                    Some((obj, token)) => {
+                        let prop = existing_prop(tx, &obj, token, path)?;
+                        // SAFETY: existing_prop checks the value exists
+                        let (current, id) = tx.get(&obj, prop)?.unwrap();
+                        json_at(&*tx, current, id, None)?
+                    }
+                };
+                if current != *value {
+                    return Err(invalid(path, "test failed"));
+                }
+            }
+            JsonPatchOp::Move { .. } => return Err(AutomergeError::UnsupportedJsonPatchOp("move")),
+            JsonPatchOp::Copy { .. } => return Err(AutomergeError::UnsupportedJsonPatchOp("copy")),
+        }
+    }
+    Ok(())
+}
+
+/// Find the object containing the value `path` refers to, returning `None` for the root
+fn resolve_parent<T: Transactable>(
+    tx: &T,
+    path: &str,
+) -> Result<Option<(ExId, String)>, AutomergeError> {
+    if path.is_empty() {
+        return Ok(None);
+    }
+    let mut tokens = match path.strip_prefix('/') {
+        Some(rest) => rest.split('/').map(unescape).collect::<Vec<_>>(),
+        None => return Err(invalid(path, "paths must start with `/`")),
+    };
+    // SAFETY: splitting always produces at least one token
+    let last = tokens.pop().unwrap();
+    let mut obj = ExId::Root;
+    for token in tokens {
+        let prop = existing_prop(tx, &obj, token, path)?;
+        match tx.get(&obj, prop)? {
+            Some((Value::Object(_), id)) => obj = id,
+            _ => return Err(invalid(path, "a parent is not an object or array")),
+        }
+    }
+    Ok(Some((obj, last)))
+}
+
+/// The property of `obj` which `token` refers to, which must have a value
+fn existing_prop<T: Transactable>(
+    tx: &T,
+    obj: &ExId,
+    token: String,
+    path: &str,
+) -> Result<Prop, AutomergeError> {
+    match tx.object_type(obj)? {
+        ObjType::Map | ObjType::Table => {
+            if tx.get(obj, token.as_str())?.is_none() {
+                return Err(invalid(path, "no value at this path"));
+            }
+            Ok(Prop::Map(token))
+        }
+        ObjType::List => parse_index(&token, tx.length(obj), path).map(Prop::Seq),
+        ObjType::Text => Err(text_path(path)),
+    }
+}
+
+/// Parse an array index which must be less than `bound`
+fn parse_index(token: &str, bound: usize, path: &str) -> Result<usize, AutomergeError> {
+    match token.parse::<usize>() {
+        Ok(index) if index < bound => Ok(index),
+        _ => Err(invalid(path, "not a valid array index")),
+    }
+}
+
+/// Set `prop` of `obj` to `value`, editing text objects in place when `value` is a string
+fn set<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+    value: &Json,
+) -> Result<(), AutomergeError> {
+    if let (Some((Value::Object(ObjType::Text), text)), Json::String(s)) =
+        (tx.get(obj, prop.clone())?, value)
+    {
+        return tx.text_set(&text, s);
+    }
+    put_json(tx, obj, prop, value)
+}
+
+/// Make the root map equal to the JSON object `value`
+fn set_root<T: Transactable>(tx: &mut T, path: &str, value: &Json) -> Result<(), AutomergeError> {
+    let map = match value {
+        Json::Object(map) => map,
+        _ => return Err(invalid(path, "the root must be an object")),
+    };
+    let removed = tx
+        .keys(ExId::Root)
+        .filter(|key| !map.contains_key(key))
+        .collect::<Vec<_>>();
+    for key in removed {
+        tx.delete(ExId::Root, key)?;
+    }
+    for (key, value) in map {
+        set(tx, &ExId::Root, key.as_str().into(), value)?;
+    }
+    Ok(())
+}
+
+fn invalid(path: &str, reason: &str) -> AutomergeError {
+    AutomergeError::InvalidJsonPatch(format!("{} at `{}`", reason, path))
+}
+
+fn text_path(path: &str) -> AutomergeError {
+    invalid(path, "characters of text can't be addressed")
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+struct JsonPatchBuilder<'a> {
+    doc: &'a Automerge,
+    heads: &'a [ChangeHash],
+    ops: Vec<JsonPatchOp>,
+    /// Objects whose whole value has already been written by an `add` or `replace`, patches
+    /// inside them are redundant
+    created: HashSet<ExId>,
+    /// Text objects which have already been replaced with their final string
+    replaced: HashSet<ExId>,
+}
+
+impl<'a> JsonPatchBuilder<'a> {
+    fn push(&mut self, patch: Patch) -> Result<(), AutomergeError> {
+        if self.created.contains(&patch.obj)
+            || patch.path.iter().any(|(obj, _)| self.created.contains(obj))
+        {
+            return Ok(());
+        }
+        let base = pointer(patch.path.iter().map(|(_, prop)| prop));
+        match patch.action {
+            PatchAction::PutMap { key, value, .. } => {
+                let path = format!("{}/{}", base, escape(&key));
+                let value = self.value(value)?;
+                self.ops.push(JsonPatchOp::Add { path, value });
+            }
+            PatchAction::PutSeq { index, value, .. } => {
+                let path = format!("{}/{}", base, index);
+                let value = self.value(value)?;
+                self.ops.push(JsonPatchOp::Replace { path, value });
+            }
+            PatchAction::Insert { index, values, .. } => {
+                for (offset, value) in values.iter().enumerate() {
+                    let path = format!("{}/{}", base, index + offset);
+                    let value = self.value(value.clone())?;
+                    self.ops.push(JsonPatchOp::Add { path, value });
+                }
+            }
+            PatchAction::DeleteMap { key } => {
+                let path = format!("{}/{}", base, escape(&key));
+                self.ops.push(JsonPatchOp::Remove { path });
+            }
+            PatchAction::DeleteSeq { index, length } => {
+                if self.doc.object_type(&patch.obj)? == ObjType::Text {
+                    self.replace_text(patch.obj, base)?;
+                } else {
+                    let path = format!("{}/{}", base, index);
+                    for _ in 0..length {
+                        self.ops.push(JsonPatchOp::Remove { path: path.clone() });
+                    }
+                }
+            }
+            PatchAction::SpliceText { .. } => self.replace_text(patch.obj, base)?,
+            PatchAction::Increment { prop, .. } => {
+                let path = format!("{}/{}", base, escape_prop(&prop));
+                let value = match self.doc.get_at(&patch.obj, prop, self.heads)? {
+                    Some((value, obj)) => self.value((value.to_owned(), obj))?,
+                    None => Json::Null,
+                };
+                self.ops.push(JsonPatchOp::Replace { path, value });
+            }
+            PatchAction::Mark { .. } => {}
+        }
+        Ok(())
+    }
+
+    fn replace_text(&mut self, obj: ExId, path: String) -> Result<(), AutomergeError> {
+        if self.replaced.insert(obj.clone()) {
+            let value = Json::String(self.doc.text_at(obj, self.heads)?);
+            self.ops.push(JsonPatchOp::Replace { path, value });
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, (value, obj): (Value<'_>, ExId)) -> Result<Json, AutomergeError> {
+        if value.is_object() {
+            self.created.insert(obj.clone());
+        }
+        json_at(self.doc, value, obj, Some(self.heads))
+    }
+}
+
+/// Build an RFC 6901 JSON pointer from a sequence of props
+fn pointer<'p, I: Iterator<Item = &'p Prop>>(props: I) -> String {
+    props.fold(String::new(), |mut path, prop| {
+        path.push('/');
+        path.push_str(&escape_prop(prop));
+        path
+    })
+}
+
+fn escape_prop(prop: &Prop) -> String {
+    match prop {
+        Prop::Map(key) => escape(key),
+        Prop::Seq(index) => index.to_string(),
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::JsonPatchOp;
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    #[test]
+    fn diff_as_json_patch_describes_changes() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "title", "hello").unwrap();
+        doc.put(ROOT, "a/b~c", 1).unwrap();
+        doc.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, "x").unwrap();
+        doc.insert(&list, 1, "y").unwrap();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+        let before = doc.get_heads();
+
+        doc.delete(ROOT, "a/b~c").unwrap();
+        doc.increment(ROOT, "count", 2).unwrap();
+        doc.delete(&list, 0).unwrap();
+        let nested = doc.insert_object(&list, 1, ObjType::Map).unwrap();
+        doc.put(&nested, "k", true).unwrap();
+        doc.splice_text(&text, 5, 0, " world").unwrap();
+        let after = doc.get_heads();
+
+        let patch = doc.diff_as_json_patch(&before, &after).unwrap();
+        assert_eq!(patch.len(), 5);
+        assert_eq!(
+            patch[0],
+            JsonPatchOp::Remove {
+                path: "/a~1b~0c".to_string()
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(patch).unwrap(),
+            json!([
+                {"op": "remove", "path": "/a~1b~0c"},
+                {"op": "replace", "path": "/count", "value": 3},
+                {"op": "remove", "path": "/list/0"},
+                {"op": "add", "path": "/list/1", "value": {"k": true}},
+                {"op": "replace", "path": "/text", "value": "hello world"},
+            ])
+        );
+
+        let backwards = doc.diff_as_json_patch(&after, &before).unwrap();
+        assert_eq!(
+            serde_json::to_value(backwards).unwrap(),
+            json!([
+                {"op": "add", "path": "/a~1b~0c", "value": 1},
+                {"op": "replace", "path": "/count", "value": 1},
+                {"op": "add", "path": "/list/0", "value": "x"},
+                {"op": "remove", "path": "/list/2"},
+                {"op": "replace", "path": "/text", "value": "hello"},
+            ])
+        );
+    }
+
+    #[test]
+    fn apply_json_patch_reproduces_diff() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "title", "hello").unwrap();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, "x").unwrap();
+        doc.insert(&list, 1, "y").unwrap();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+        let before = doc.get_heads();
+        let mut replica = doc.fork();
+
+        doc.delete(&list, 0).unwrap();
+        let nested = doc.insert_object(&list, 1, ObjType::Map).unwrap();
+        doc.put(&nested, "k", true).unwrap();
+        doc.splice_text(&text, 5, 0, " world").unwrap();
+        doc.put(ROOT, "title", "bye").unwrap();
+        let after = doc.get_heads();
+
+        let patch = doc.diff_as_json_patch(&before, &after).unwrap();
+        let replica_before = replica.get_heads();
+        replica.apply_json_patch(&patch).unwrap();
+        assert_eq!(replica.export_json(), doc.export_json());
+        assert_eq!(replica.get_changes(&replica_before).len(), 1);
+        // The text was edited rather than replaced
+        assert_eq!(replica.get(ROOT, "text").unwrap().unwrap().1, text);
+    }
+
+    #[test]
+    fn apply_json_patch_is_atomic() {
+        let mut doc = Automerge::import_json(&json!({"a": [1, 2], "b": {"c": "d"}})).unwrap();
+        let heads = doc.get_heads();
+        let ops: Vec<JsonPatchOp> = serde_json::from_value(json!([
+            {"op": "add", "path": "/a/-", "value": 3},
+            {"op": "add", "path": "/b/e~1f", "value": {"g": []}},
+            {"op": "test", "path": "/a", "value": [1, 2, 3]},
+            {"op": "remove", "path": "/a/0"},
+            {"op": "replace", "path": "/b/c", "value": null},
+        ]))
+        .unwrap();
+        doc.apply_json_patch(&ops).unwrap();
+        assert_eq!(
+            doc.export_json(),
+            json!({"a": [2, 3], "b": {"c": null, "e/f": {"g": []}}})
+        );
+
+        let heads_after = doc.get_heads();
+        for bad in [
+            json!([{"op": "add", "path": "/x", "value": 1}, {"op": "remove", "path": "/missing"}]),
+            json!([{"op": "add", "path": "/a/5", "value": 1}]),
+            json!([{"op": "test", "path": "/a/0", "value": 1}]),
+            json!([{"op": "add", "path": "no-slash", "value": 1}]),
+        ] {
+            let ops: Vec<JsonPatchOp> = serde_json::from_value(bad).unwrap();
+            assert!(matches!(
+                doc.apply_json_patch(&ops),
+                Err(AutomergeError::InvalidJsonPatch(_))
+            ));
+        }
+        let ops: Vec<JsonPatchOp> =
+            serde_json::from_value(json!([{"op": "move", "from": "/a", "path": "/z"}])).unwrap();
+        assert!(matches!(
+            doc.apply_json_patch(&ops),
+            Err(AutomergeError::UnsupportedJsonPatchOp("move"))
+        ));
+        assert_eq!(doc.get_heads(), heads_after);
+        assert_ne!(heads, heads_after);
+    }
+
+    #[test]
+    fn diff_as_json_patch_rejects_unknown_heads() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "a", 1).unwrap();
+        let heads = doc.get_heads();
+        let mut other = AutoCommit::new();
+        other.put(ROOT, "b", 1).unwrap();
+        assert!(doc.diff_as_json_patch(&other.get_heads(), &heads).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use crate::exid::ExId;
+use crate::patches::TextRepresentation;
+use crate::{Automerge, AutomergeError, ChangeHash, ObjType, PatchAction, Prop, ReadDoc, Value};
+
+/// A change to a single element of a list, see [`Automerge::list_diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListDiffEntry {
+    /// `value`, with ID `ExId`, was inserted at `index`
+    Insert(usize, Value<'static>, ExId),
+    /// The element holding the old value was deleted from before `index`
+    Delete(usize, Value<'static>),
+    /// The element at `index` was changed from the old value to the new value
+    Replace(usize, Value<'static>, Value<'static>),
+}
+
+impl Automerge {
+    /// Describe how the elements of the list `obj` differ between `before` and `after`
+    ///
+    /// The entries are in order and every index is an index into the list at `after`: an
+    /// inserted or replaced element is at that index in `after` and a deleted element was
+    /// immediately before the element which is at that index in `after`. Incremented counters
+    /// are reported as a [`ListDiffEntry::Replace`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a list and
+    /// [`AutomergeError::InvalidHash`] if any of the heads are not in this document.
+    pub fn list_diff<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        before: &[ChangeHash],
+        after: &[ChangeHash],
+    ) -> Result<Vec<ListDiffEntry>, AutomergeError> {
+        let obj = obj.as_ref();
+        for hash in before.iter().chain(after) {
+            if !self.history_index.contains_key(hash) {
+                return Err(AutomergeError::InvalidHash(*hash));
+            }
+        }
+        let typ = self.object_type(obj)?;
+        if typ != ObjType::List {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+
+        let old_values = self
+            .list_range_at(obj, .., before)
+            .map(|item| item.value.into_owned())
+            .collect::<Vec<_>>();
+        // The patches for a list walk through it from start to end, so the index of each patch
+        // is an index in `after` and adding the number of elements deleted so far, less the
+        // number inserted, gives the index in `before`.
+        let mut offset = 0_isize;
+        let old_index = |index: usize, offset: isize| (index as isize + offset) as usize;
+        let mut entries = Vec::new();
+        for patch in self.diff(before, after, TextRepresentation::Array) {
+            if patch.obj != *obj {
+                continue;
+            }
+            match patch.action {
+                PatchAction::Insert { index, values, .. } => {
+                    for (i, (value, id)) in values.iter().enumerate() {
+                        entries.push(ListDiffEntry::Insert(index + i, value.clone(), id.clone()));
+                    }
+                    offset -= values.len() as isize;
+                }
+                PatchAction::DeleteSeq { index, length } => {
+                    let start = old_index(index, offset);
+                    for value in &old_values[start..start + length] {
+                        entries.push(ListDiffEntry::Delete(index, value.clone()));
+                    }
+                    offset += length as isize;
+                }
+                PatchAction::PutSeq {
+                    index,
+                    value: (value, _),
+                    ..
+                } => {
+                    let old = old_values[old_index(index, offset)].clone();
+                    entries.push(ListDiffEntry::Replace(index, old, value));
+                }
+                PatchAction::Increment {
+                    prop: Prop::Seq(index),
+                    ..
+                } => {
+                    let old = old_values[old_index(index, offset)].clone();
+                    if let Some((value, _)) = self.get_at(obj, index, after)? {
+                        entries.push(ListDiffEntry::Replace(index, old, value.into_owned()));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+use crate::exid::ExId;
+use crate::{Automerge, AutomergeError, ChangeHash, ObjType, ReadDoc, Value};
+
+/// How a single key of a map differs between two sets of heads, see [`Automerge::map_diff`]
+///
+/// Values are given along with their IDs as returned by [`ReadDoc::get`], so nested objects can
+/// be read with the ID.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapDiffEntry {
+    /// The key was not set before and is now
+    Added(String, (Value<'static>, ExId)),
+    /// The key was set before and has been deleted
+    Removed(String, (Value<'static>, ExId)),
+    /// The key has a new value: the old value then the new value
+    Changed(String, (Value<'static>, ExId), (Value<'static>, ExId)),
+    /// The key now has conflicting values, in the order returned by [`ReadDoc::get_all`]
+    Conflicted(String, Vec<(Value<'static>, ExId)>),
+}
+
+impl Automerge {
+    /// Describe how the keys of the map `obj` differ between `before` and `after`
+    ///
+    /// Only keys whose values differ are included, in lexicographic order. A key whose value is
+    /// overwritten with an equal value is still [`MapDiffEntry::Changed`] as it is a different
+    /// operation. If `obj` did not exist at `before` every key is [`MapDiffEntry::Added`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a map or table and
+    /// [`AutomergeError::InvalidHash`] if any of the heads are not in this document.
+    pub fn map_diff<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        before: &[ChangeHash],
+        after: &[ChangeHash],
+    ) -> Result<Vec<MapDiffEntry>, AutomergeError> {
+        let obj = obj.as_ref();
+        for hash in before.iter().chain(after) {
+            if !self.history_index.contains_key(hash) {
+                return Err(AutomergeError::InvalidHash(*hash));
+            }
+        }
+        let typ = self.object_type(obj)?;
+        if !matches!(typ, ObjType::Map | ObjType::Table) {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+
+        let keys = self
+            .keys_at(obj, before)
+            .chain(self.keys_at(obj, after))
+            .collect::<BTreeSet<_>>();
+        let mut entries = Vec::new();
+        for key in keys {
+            let mut old = owned(self.get_all_at(obj, key.as_str(), before)?);
+            let mut new = owned(self.get_all_at(obj, key.as_str(), after)?);
+            if old == new {
+                continue;
+            }
+            let entry = if new.len() > 1 {
+                MapDiffEntry::Conflicted(key, new)
+            } else {
+                match (old.pop(), new.pop()) {
+                    (None, Some(value)) => MapDiffEntry::Added(key, value),
+                    (Some(value), None) => MapDiffEntry::Removed(key, value),
+                    (Some(old), Some(new)) => MapDiffEntry::Changed(key, old, new),
+                    (None, None) => continue,
+                }
+            };
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+fn owned(values: Vec<(Value<'_>, ExId)>) -> Vec<(Value<'static>, ExId)> {
+    values
+        .into_iter()
+        .map(|(value, id)| (value.into_owned(), id))
+        .collect()
+}
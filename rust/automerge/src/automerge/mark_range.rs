@@ -0,0 +1,80 @@
+use smol_str::SmolStr;
+
+use crate::exid::ExId;
+use crate::marks::{ExpandMark, Mark};
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, ScalarValue};
+
+impl Automerge {
+    /// Mark `start..end` of the sequence `obj` with `name` and `value` in a new change, without
+    /// letting the mark grow
+    ///
+    /// This is [`Transactable::mark`] with [`ExpandMark::None`], so text inserted immediately
+    /// before `start` or immediately after `end`, whether locally or by another actor, is not
+    /// marked. This suits marks tied to particular characters, such as comments, rather than
+    /// formatting which should carry on as the user types.
+    pub fn mark_range_exclusive(
+        &mut self,
+        obj: &ExId,
+        start: usize,
+        end: usize,
+        name: &SmolStr,
+        value: ScalarValue,
+    ) -> Result<(), AutomergeError> {
+        let mut tx = self.transaction();
+        let mark = Mark::new(name.to_string(), value, start, end);
+        match tx.mark(obj, mark, ExpandMark::None) {
+            Ok(()) => {
+                tx.commit();
+                Ok(())
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, Automerge, ObjType, ReadDoc, ROOT};
+
+    #[test]
+    fn exclusive_marks_do_not_grow_at_either_end() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        tx.splice_text(&text, 0, 0, "a comment here").unwrap();
+        tx.commit();
+
+        doc.mark_range_exclusive(&text, 2, 9, &"comment".into(), "c1".into())
+            .unwrap();
+        let mut tx = doc.transaction();
+        tx.splice_text(&text, 9, 0, "!").unwrap();
+        tx.splice_text(&text, 2, 0, "[").unwrap();
+        tx.commit();
+
+        assert_eq!(doc.text(&text).unwrap(), "a [comment! here");
+        let marks = doc.marks(&text).unwrap();
+        assert_eq!(marks.len(), 1);
+        assert_eq!((marks[0].start, marks[0].end), (3, 10));
+
+        // A mark which expands grows at the same place
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "a comment here").unwrap();
+        doc.mark(
+            &text,
+            Mark::new("bold".into(), true, 2, 9),
+            ExpandMark::Both,
+        )
+        .unwrap();
+        doc.splice_text(&text, 9, 0, "!").unwrap();
+        doc.splice_text(&text, 2, 0, "[").unwrap();
+        let marks = doc.marks(&text).unwrap();
+        assert_eq!((marks[0].start, marks[0].end), (2, 11));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use smol_str::SmolStr;
+
+use itertools::Itertools;
+
+use crate::exid::ExId;
+use crate::marks::MarkStateMachine;
+use crate::types::OpType;
+use crate::{Automerge, AutomergeError, ObjType, ReadDoc, ScalarValue};
+
+impl Automerge {
+    /// The marks active on the character at `char_index` of the sequence `obj`, by name
+    ///
+    /// A mark is active at `char_index` if it begins at or before `char_index` and ends after it,
+    /// so a mark covering `start..end` is active from `start` up to but not including `end`. The
+    /// marks are resolved as in [`ReadDoc::marks`], so where marks with the same name overlap only
+    /// the winning value is returned. `char_index` may be the length of `obj`, which is never
+    /// covered by a mark.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a list or text object and
+    /// [`AutomergeError::InvalidIndex`] if `char_index` is past the end of `obj`
+    pub fn get_marks_at_index<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        char_index: usize,
+    ) -> Result<HashMap<SmolStr, ScalarValue>, AutomergeError> {
+        let meta = self.exid_to_obj(obj.as_ref())?;
+        if !matches!(meta.typ, ObjType::List | ObjType::Text) {
+            return Err(AutomergeError::InvalidOp(meta.typ));
+        }
+        if char_index > self.length(obj.as_ref()) {
+            return Err(AutomergeError::InvalidIndex(char_index));
+        }
+        // Only the ops before the character at `char_index` are read, so this is cheaper the
+        // closer `char_index` is to the start
+        let mut index = 0;
+        let mut marks = MarkStateMachine::default();
+        for (_key, key_ops) in &self
+            .ops()
+            .iter_ops(&meta.id)
+            .group_by(|o| o.elemid_or_key())
+        {
+            if let Some(o) = key_ops.filter(|o| o.visible_or_mark(None)).last() {
+                match &o.action {
+                    OpType::Make(_) | OpType::Put(_) => {
+                        index += o.width(meta.encoding);
+                        if char_index < index {
+                            break;
+                        }
+                    }
+                    OpType::MarkBegin(_, data) => {
+                        marks.mark_or_unmark_begin(o.id, index, data, self);
+                    }
+                    OpType::MarkEnd(_) => {
+                        marks.mark_or_unmark_end(o.id, index, self);
+                    }
+                    OpType::Increment(_) | OpType::Delete => {}
+                }
+            }
+        }
+        Ok(marks.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, AutomergeError, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    #[test]
+    fn marks_are_active_from_their_start_up_to_their_end() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        doc.mark(
+            &text,
+            Mark::new("bold".into(), true, 0, 5),
+            ExpandMark::None,
+        )
+        .unwrap();
+        doc.mark(
+            &text,
+            Mark::new("link".into(), "https://automerge.org", 3, 8),
+            ExpandMark::None,
+        )
+        .unwrap();
+
+        let names = |index| {
+            let mut names = doc
+                .get_marks_at_index(&text, index)
+                .unwrap()
+                .into_keys()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(names(0), vec!["bold"]);
+        assert_eq!(names(3), vec!["bold", "link"]);
+        assert_eq!(names(5), vec!["link"]);
+        assert_eq!(names(8), Vec::<String>::new());
+        assert_eq!(names(11), Vec::<String>::new());
+
+        let marks = doc.get_marks_at_index(&text, 4).unwrap();
+        assert_eq!(
+            marks,
+            HashMap::from([
+                ("bold".into(), ScalarValue::from(true)),
+                ("link".into(), ScalarValue::from("https://automerge.org")),
+            ])
+        );
+    }
+
+    #[test]
+    fn overlapping_marks_agree_with_marks() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "abcdefgh").unwrap();
+        let color = |value: &str, start, end| Mark::new("color".into(), value, start, end);
+        doc.mark(&text, color("red", 0, 6), ExpandMark::None)
+            .unwrap();
+        doc.mark(&text, color("blue", 2, 4), ExpandMark::None)
+            .unwrap();
+        doc.unmark(&text, "color", 5, 7, ExpandMark::None).unwrap();
+        doc.mark(
+            &text,
+            Mark::new("bold".into(), true, 1, 7),
+            ExpandMark::None,
+        )
+        .unwrap();
+
+        let marks = doc.marks(&text).unwrap();
+        for index in 0..=doc.length(&text) {
+            let expected = marks
+                .iter()
+                .filter(|m| m.start <= index && index < m.end)
+                .map(|m| (m.name().into(), m.value().clone()))
+                .collect::<HashMap<_, _>>();
+            assert_eq!(doc.get_marks_at_index(&text, index).unwrap(), expected);
+        }
+        assert_eq!(
+            doc.get_marks_at_index(&text, 3).unwrap()["color"],
+            ScalarValue::from("blue")
+        );
+        assert!(!doc
+            .get_marks_at_index(&text, 5)
+            .unwrap()
+            .contains_key("color"));
+    }
+
+    #[test]
+    fn indices_past_the_end_are_rejected() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "abc").unwrap();
+        assert!(matches!(
+            doc.get_marks_at_index(&text, 4),
+            Err(AutomergeError::InvalidIndex(4))
+        ));
+        assert!(matches!(
+            doc.get_marks_at_index(ROOT, 0),
+            Err(AutomergeError::InvalidOp(ObjType::Map))
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::exid::ExId;
+use crate::transaction::{Snapshot, Transactable};
+use crate::{Automerge, AutomergeError, ObjType};
+
+impl Automerge {
+    /// Move the item at index `from` of the list `obj` so that it ends up at index `to`, in a new
+    /// change
+    ///
+    /// Lists have no native move, so this deletes the item and inserts a copy of it at `to`.
+    /// Objects are copied with their current contents and get a new id. If another replica edits
+    /// the item concurrently the edit applies to the deleted original rather than the copy, and
+    /// concurrent moves of the same item leave one copy for each move. Moving an item to its own
+    /// index does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a list and
+    /// [`AutomergeError::InvalidIndex`] if `from` or `to` is not an index of the list.
+    pub fn move_list_item<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        from: usize,
+        to: usize,
+    ) -> Result<(), AutomergeError> {
+        let mut tx = self.transaction();
+        match move_list_item(&mut tx, obj.as_ref(), from, to) {
+            Ok(()) => {
+                tx.commit();
+                Ok(())
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Move the item at `from` to `to` in the list `obj`, see [`Automerge::move_list_item`]
+pub(crate) fn move_list_item<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    from: usize,
+    to: usize,
+) -> Result<(), AutomergeError> {
+    match tx.object_type(obj)? {
+        ObjType::List => {}
+        typ => return Err(AutomergeError::InvalidOp(typ)),
+    }
+    let len = tx.length(obj);
+    if from >= len {
+        return Err(AutomergeError::InvalidIndex(from));
+    }
+    if to >= len {
+        return Err(AutomergeError::InvalidIndex(to));
+    }
+    if from == to {
+        return Ok(());
+    }
+    let (value, id) = tx
+        .get(obj, from)?
+        .expect("an index within the list has a value");
+    let item = Snapshot::read_item(tx, value, &id)?;
+    tx.delete(obj, from)?;
+    item.insert(tx, obj, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, Value, ROOT};
+
+    fn items(doc: &AutoCommit, list: &crate::ObjId) -> Vec<String> {
+        doc.values(list)
+            .map(|(value, _)| value.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn move_list_item_moves_forwards_and_backwards() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        for (index, item) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            doc.insert(&list, index, item).unwrap();
+        }
+
+        doc.move_list_item(&list, 0, 2).unwrap();
+        assert_eq!(items(&doc, &list), vec!["b", "c", "a", "d"]);
+        doc.move_list_item(&list, 3, 0).unwrap();
+        assert_eq!(items(&doc, &list), vec!["d", "b", "c", "a"]);
+
+        let heads = doc.get_heads();
+        doc.move_list_item(&list, 1, 1).unwrap();
+        assert_eq!(doc.get_heads(), heads);
+        assert!(matches!(
+            doc.move_list_item(&list, 4, 0),
+            Err(AutomergeError::InvalidIndex(4))
+        ));
+        assert!(matches!(
+            doc.move_list_item(ROOT, 0, 1),
+            Err(AutomergeError::InvalidOp(ObjType::Map))
+        ));
+    }
+
+    #[test]
+    fn move_list_item_copies_objects_in_one_change() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+        let map = tx.insert_object(&list, 0, ObjType::Map).unwrap();
+        tx.put(&map, "key", "value").unwrap();
+        tx.insert(&list, 1, "b").unwrap();
+        tx.commit();
+        let changes = doc.get_changes(&[]).len();
+
+        doc.move_list_item(&list, 0, 1).unwrap();
+        assert_eq!(doc.get_changes(&[]).len(), changes + 1);
+        assert_eq!(doc.get(&list, 0).unwrap().unwrap().0, Value::from("b"));
+        let (value, id) = doc.get(&list, 1).unwrap().unwrap();
+        assert_eq!(value, Value::Object(ObjType::Map));
+        assert_eq!(
+            doc.get(&id, "key").unwrap().unwrap().0,
+            Value::from("value")
+        );
+    }
+
+    #[test]
+    fn moved_text_keeps_its_marks() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        let text = doc.insert_object(&list, 0, ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        let comment = Mark::new("comment".into(), "c1", 6, 11);
+        doc.mark(&text, comment.clone(), ExpandMark::None).unwrap();
+        doc.insert(&list, 1, "after").unwrap();
+
+        doc.move_list_item(&list, 0, 1).unwrap();
+        let (_, copy) = doc.get(&list, 1).unwrap().unwrap();
+        assert_eq!(doc.text(&copy).unwrap(), "hello world");
+        assert_eq!(doc.marks(&copy).unwrap(), vec![comment]);
+    }
+}
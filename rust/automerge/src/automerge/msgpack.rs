@@ -0,0 +1,505 @@
+use std::convert::TryFrom;
+
+use rmp::decode;
+use rmp::encode;
+use rmp::Marker;
+
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, ObjType, Prop, ReadDoc, ScalarValue, Value, ROOT};
+
+/// The extension type of a counter, whose data is its current value as a big endian `i64`
+const COUNTER_EXT: i8 = 1;
+/// The extension type of an unknown scalar, whose data is its type code followed by its bytes
+const UNKNOWN_EXT: i8 = 2;
+/// The extension type MessagePack reserves for timestamps
+const TIMESTAMP_EXT: i8 = -1;
+
+/// How deeply arrays and maps may be nested in [`Automerge::from_msgpack`], the same limit
+/// `serde_json` uses
+const MAX_DEPTH: usize = 128;
+
+impl Automerge {
+    /// The current state of this document encoded as MessagePack
+    ///
+    /// This has the same structure as [`Self::export_json`]: maps and tables become MessagePack
+    /// maps, lists become arrays and text becomes a string. Scalars keep their type so
+    /// [`Self::from_msgpack`] can read them back exactly:
+    ///
+    /// * [`ScalarValue::Int`] uses the signed integer formats and [`ScalarValue::Uint`] the
+    ///   unsigned ones (never a positive fixint)
+    /// * [`ScalarValue::F64`] is a float 64 and [`ScalarValue::Bytes`] a bin
+    /// * [`ScalarValue::Timestamp`] is the standard timestamp extension (type -1)
+    /// * [`ScalarValue::Counter`] is extension type 1 holding the current value as a big endian
+    ///   `i64`
+    /// * [`ScalarValue::Unknown`] is extension type 2 holding the type code then the bytes
+    ///
+    /// Where a property has conflicting values the value returned by [`ReadDoc::get`] is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidMsgpack`] if a string, byte array, list or map is too
+    /// long to be represented in MessagePack, which is more than `u32::MAX` bytes or elements.
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, AutomergeError> {
+        let mut out = Vec::new();
+        encode_at(self, Value::Object(ObjType::Map), ROOT, &mut out)?;
+        Ok(out)
+    }
+
+    /// Create a document from the output of [`Self::to_msgpack`] in a single change
+    ///
+    /// Strings, including those which were text objects, become [`ScalarValue::Str`] and maps
+    /// become [`ObjType::Map`]s. Float 32s are read as [`ScalarValue::F64`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidMsgpack`] if `bytes` is not a single MessagePack map
+    /// with string keys, contains an unsupported extension type or is nested more than 128
+    /// levels deep.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Automerge, AutomergeError> {
+        let mut rest = bytes;
+        let root = match read(&mut rest, 0).map_err(invalid)? {
+            Decoded::Map(entries) => entries,
+            _ => return Err(invalid("the root must be a map")),
+        };
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes after the root map"));
+        }
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        for (key, value) in root {
+            put_decoded(&mut tx, &ROOT, key.into(), value)?;
+        }
+        tx.commit();
+        Ok(doc)
+    }
+}
+
+fn invalid(reason: &str) -> AutomergeError {
+    AutomergeError::InvalidMsgpack(reason.to_string())
+}
+
+/// The length of a string, byte array, list or map as MessagePack stores it
+fn encoded_len(len: usize) -> Result<u32, AutomergeError> {
+    u32::try_from(len).map_err(|_| invalid("a value is too long to encode"))
+}
+
+/// Append the MessagePack encoding of `value`, which lives at `obj`, to `out`
+fn encode_at<R: ReadDoc>(
+    doc: &R,
+    value: Value<'_>,
+    obj: ExId,
+    out: &mut Vec<u8>,
+) -> Result<(), AutomergeError> {
+    match value {
+        Value::Object(ObjType::Map | ObjType::Table) => {
+            encode::write_map_len(out, encoded_len(doc.length(&obj))?).map_err(write_failed)?;
+            for item in doc.map_range(&obj, ..) {
+                write_str(out, item.key)?;
+                encode_at(doc, item.value, item.id, out)?;
+            }
+            Ok(())
+        }
+        Value::Object(ObjType::List) => {
+            encode::write_array_len(out, encoded_len(doc.length(&obj))?).map_err(write_failed)?;
+            for item in doc.list_range(&obj, ..) {
+                encode_at(doc, item.value, item.id, out)?;
+            }
+            Ok(())
+        }
+        Value::Object(ObjType::Text) => write_str(out, &doc.text(&obj)?),
+        Value::Scalar(s) => encode_scalar(&s, out),
+    }
+}
+
+fn encode_scalar(value: &ScalarValue, out: &mut Vec<u8>) -> Result<(), AutomergeError> {
+    match value {
+        ScalarValue::Null => encode::write_nil(out).map_err(write_failed)?,
+        ScalarValue::Boolean(b) => encode::write_bool(out, *b).map_err(write_failed)?,
+        ScalarValue::Int(i) => write_int(out, *i).map_err(write_failed)?,
+        ScalarValue::Uint(u) => write_uint(out, *u).map_err(write_failed)?,
+        ScalarValue::F64(f) => encode::write_f64(out, *f).map_err(write_failed)?,
+        ScalarValue::Str(s) => write_str(out, s)?,
+        ScalarValue::Bytes(b) => {
+            encode::write_bin_len(out, encoded_len(b.len())?).map_err(write_failed)?;
+            out.extend_from_slice(b);
+        }
+        ScalarValue::Counter(c) => write_ext(out, COUNTER_EXT, &i64::from(c).to_be_bytes())?,
+        ScalarValue::Timestamp(millis) => {
+            let seconds = millis.div_euclid(1000);
+            let nanos = (millis.rem_euclid(1000) * 1_000_000) as u64;
+            if (0..1 << 34).contains(&seconds) {
+                let packed = (nanos << 34) | seconds as u64;
+                write_ext(out, TIMESTAMP_EXT, &packed.to_be_bytes())?;
+            } else {
+                let mut data = (nanos as u32).to_be_bytes().to_vec();
+                data.extend_from_slice(&seconds.to_be_bytes());
+                write_ext(out, TIMESTAMP_EXT, &data)?;
+            }
+        }
+        ScalarValue::Unknown { type_code, bytes } => {
+            let mut data = Vec::with_capacity(bytes.len() + 1);
+            data.push(*type_code);
+            data.extend_from_slice(bytes);
+            write_ext(out, UNKNOWN_EXT, &data)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writing to a `Vec` can't fail, but the encoder's signatures allow for other writers
+fn write_failed<E: std::fmt::Display>(e: E) -> AutomergeError {
+    invalid(&e.to_string())
+}
+
+/// Write `i` in the smallest signed format, so it reads back as a [`ScalarValue::Int`]
+///
+/// [`encode::write_sint`] uses the unsigned formats for positive values which don't fit in a
+/// positive fixint, so only the fixints are left to it.
+fn write_int(out: &mut Vec<u8>, i: i64) -> Result<(), encode::ValueWriteError> {
+    if (-32..=127).contains(&i) {
+        encode::write_sint(out, i).map(|_| ())
+    } else if let Ok(i) = i8::try_from(i) {
+        encode::write_i8(out, i)
+    } else if let Ok(i) = i16::try_from(i) {
+        encode::write_i16(out, i)
+    } else if let Ok(i) = i32::try_from(i) {
+        encode::write_i32(out, i)
+    } else {
+        encode::write_i64(out, i)
+    }
+}
+
+/// Write `u` in the smallest unsigned format, never a positive fixint, so it reads back as a
+/// [`ScalarValue::Uint`]
+fn write_uint(out: &mut Vec<u8>, u: u64) -> Result<(), encode::ValueWriteError> {
+    if let Ok(u) = u8::try_from(u) {
+        encode::write_u8(out, u)
+    } else if let Ok(u) = u16::try_from(u) {
+        encode::write_u16(out, u)
+    } else if let Ok(u) = u32::try_from(u) {
+        encode::write_u32(out, u)
+    } else {
+        encode::write_u64(out, u)
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) -> Result<(), AutomergeError> {
+    encode::write_str_len(out, encoded_len(s.len())?).map_err(write_failed)?;
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn write_ext(out: &mut Vec<u8>, ext_type: i8, data: &[u8]) -> Result<(), AutomergeError> {
+    encode::write_ext_meta(out, encoded_len(data.len())?, ext_type).map_err(write_failed)?;
+    out.extend_from_slice(data);
+    Ok(())
+}
+
+/// Errors while decoding are plain messages, converted to an [`AutomergeError`] once decoding
+/// finishes, which keeps the stack frames of the recursive decoder small
+type DecodeResult<T> = Result<T, &'static str>;
+
+/// A value read by [`Automerge::from_msgpack`]
+enum Decoded {
+    Scalar(ScalarValue),
+    List(Vec<Decoded>),
+    Map(Vec<(String, Decoded)>),
+}
+
+/// The values are read with the typed readers of [`decode`] after peeking at the marker, so the
+/// only way they can fail is by running out of input
+fn truncated<E>(_: E) -> &'static str {
+    "unexpected end of input"
+}
+
+/// Take the next `len` bytes from `rd`
+fn take<'a>(rd: &mut &'a [u8], len: u32) -> DecodeResult<&'a [u8]> {
+    let len = len as usize;
+    if rd.len() < len {
+        return Err("unexpected end of input");
+    }
+    let (data, rest) = rd.split_at(len);
+    *rd = rest;
+    Ok(data)
+}
+
+fn read_str(rd: &mut &[u8]) -> DecodeResult<String> {
+    let len = decode::read_str_len(rd).map_err(truncated)?;
+    String::from_utf8(take(rd, len)?.to_vec()).map_err(|_| "a string is not valid UTF-8")
+}
+
+/// Read the next value from `rd`, which is nested `depth` levels deep
+fn read(rd: &mut &[u8], depth: usize) -> DecodeResult<Decoded> {
+    if depth > MAX_DEPTH {
+        return Err("values are nested too deeply");
+    }
+    let marker = Marker::from_u8(*rd.first().ok_or("unexpected end of input")?);
+    let scalar = match marker {
+        Marker::FixPos(_)
+        | Marker::FixNeg(_)
+        | Marker::I8
+        | Marker::I16
+        | Marker::I32
+        | Marker::I64 => ScalarValue::Int(decode::read_int(rd).map_err(truncated)?),
+        Marker::U8 | Marker::U16 | Marker::U32 | Marker::U64 => {
+            ScalarValue::Uint(decode::read_int(rd).map_err(truncated)?)
+        }
+        Marker::F32 => ScalarValue::F64(f64::from(decode::read_f32(rd).map_err(truncated)?)),
+        Marker::F64 => ScalarValue::F64(decode::read_f64(rd).map_err(truncated)?),
+        Marker::Null => {
+            decode::read_nil(rd).map_err(truncated)?;
+            ScalarValue::Null
+        }
+        Marker::True | Marker::False => {
+            ScalarValue::Boolean(decode::read_bool(rd).map_err(truncated)?)
+        }
+        Marker::FixStr(_) | Marker::Str8 | Marker::Str16 | Marker::Str32 => {
+            ScalarValue::Str(read_str(rd)?.into())
+        }
+        Marker::Bin8 | Marker::Bin16 | Marker::Bin32 => {
+            let len = decode::read_bin_len(rd).map_err(truncated)?;
+            ScalarValue::Bytes(take(rd, len)?.to_vec())
+        }
+        Marker::FixExt1
+        | Marker::FixExt2
+        | Marker::FixExt4
+        | Marker::FixExt8
+        | Marker::FixExt16
+        | Marker::Ext8
+        | Marker::Ext16
+        | Marker::Ext32 => {
+            let meta = decode::read_ext_meta(rd).map_err(truncated)?;
+            read_ext(meta.typeid, take(rd, meta.size)?)?
+        }
+        Marker::FixArray(_) | Marker::Array16 | Marker::Array32 => {
+            let len = decode::read_array_len(rd).map_err(truncated)?;
+            // The length isn't used to preallocate as it may be far longer than the input
+            let mut items = Vec::new();
+            for _ in 0..len {
+                items.push(read(rd, depth + 1)?);
+            }
+            return Ok(Decoded::List(items));
+        }
+        Marker::FixMap(_) | Marker::Map16 | Marker::Map32 => {
+            let len = decode::read_map_len(rd).map_err(truncated)?;
+            let mut entries = Vec::new();
+            for _ in 0..len {
+                if !matches!(
+                    rd.first().map(|m| Marker::from_u8(*m)),
+                    Some(Marker::FixStr(_) | Marker::Str8 | Marker::Str16 | Marker::Str32)
+                ) {
+                    return Err("map keys must be strings");
+                }
+                let key = read_str(rd)?;
+                entries.push((key, read(rd, depth + 1)?));
+            }
+            return Ok(Decoded::Map(entries));
+        }
+        Marker::Reserved => return Err("0xc1 is never used"),
+    };
+    Ok(Decoded::Scalar(scalar))
+}
+
+fn read_ext(ext_type: i8, data: &[u8]) -> DecodeResult<ScalarValue> {
+    match (ext_type, data.len()) {
+        (COUNTER_EXT, 8) => {
+            let mut value = [0; 8];
+            value.copy_from_slice(data);
+            Ok(ScalarValue::counter(i64::from_be_bytes(value)))
+        }
+        (UNKNOWN_EXT, len) if len > 0 => Ok(ScalarValue::Unknown {
+            type_code: data[0],
+            bytes: data[1..].to_vec(),
+        }),
+        (TIMESTAMP_EXT, 4 | 8 | 12) => decode_timestamp(data).map(ScalarValue::Timestamp),
+        _ => Err("unsupported extension type"),
+    }
+}
+
+/// Decode the data of a timestamp extension to milliseconds since the epoch
+fn decode_timestamp(data: &[u8]) -> DecodeResult<i64> {
+    let (seconds, nanos) = match data.len() {
+        4 => {
+            let mut seconds = [0; 4];
+            seconds.copy_from_slice(data);
+            (i64::from(u32::from_be_bytes(seconds)), 0)
+        }
+        8 => {
+            let mut packed = [0; 8];
+            packed.copy_from_slice(data);
+            let packed = u64::from_be_bytes(packed);
+            ((packed & ((1 << 34) - 1)) as i64, (packed >> 34) as i64)
+        }
+        _ => {
+            let mut nanos = [0; 4];
+            nanos.copy_from_slice(&data[..4]);
+            let mut seconds = [0; 8];
+            seconds.copy_from_slice(&data[4..]);
+            (
+                i64::from_be_bytes(seconds),
+                i64::from(u32::from_be_bytes(nanos)),
+            )
+        }
+    };
+    let millis = i128::from(seconds) * 1000 + i128::from(nanos / 1_000_000);
+    i64::try_from(millis).map_err(|_| "timestamp out of range")
+}
+
+/// Set `prop` of `obj` to the decoded `value`
+fn put_decoded<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+    value: Decoded,
+) -> Result<(), AutomergeError> {
+    match value {
+        Decoded::Scalar(s) => tx.put(obj, prop, s),
+        Decoded::List(items) => {
+            let child = tx.put_object(obj, prop, ObjType::List)?;
+            insert_items(tx, &child, items)
+        }
+        Decoded::Map(entries) => {
+            let child = tx.put_object(obj, prop, ObjType::Map)?;
+            for (key, value) in entries {
+                put_decoded(tx, &child, key.into(), value)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn insert_items<T: Transactable>(
+    tx: &mut T,
+    list: &ExId,
+    items: Vec<Decoded>,
+) -> Result<(), AutomergeError> {
+    for (index, value) in items.into_iter().enumerate() {
+        match value {
+            Decoded::Scalar(s) => tx.insert(list, index, s)?,
+            Decoded::List(items) => {
+                let child = tx.insert_object(list, index, ObjType::List)?;
+                insert_items(tx, &child, items)?;
+            }
+            Decoded::Map(entries) => {
+                let child = tx.insert_object(list, index, ObjType::Map)?;
+                for (key, value) in entries {
+                    put_decoded(tx, &child, key.into(), value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction::Transactable;
+    use crate::types::gen::gen_scalar_value;
+    use crate::{hydrate, hydrate_list, hydrate_map};
+    use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    #[test]
+    fn msgpack_round_trips_nested_objects() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "name", "alice").unwrap();
+        doc.put(ROOT, "big", u64::MAX).unwrap();
+        doc.put(ROOT, "small", -100).unwrap();
+        doc.put(ROOT, "count", ScalarValue::counter(1)).unwrap();
+        doc.increment(ROOT, "count", 2).unwrap();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, ScalarValue::Timestamp(-1)).unwrap();
+        let nested = doc.insert_object(&list, 1, ObjType::Map).unwrap();
+        doc.put(&nested, "bytes", vec![1u8, 2]).unwrap();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+
+        let encoded = doc.document().to_msgpack().unwrap();
+        let decoded = Automerge::from_msgpack(&encoded).unwrap();
+        let expected = hydrate_map!(
+            "name" => "alice",
+            "big" => ScalarValue::Uint(u64::MAX),
+            "small" => -100,
+            "count" => ScalarValue::counter(3),
+            "list" => hydrate_list![
+                ScalarValue::Timestamp(-1),
+                hydrate_map!("bytes" => vec![1u8, 2]),
+            ],
+            "text" => "hello",
+        );
+        assert_eq!(decoded.hydrate(None), expected);
+    }
+
+    #[test]
+    fn msgpack_uses_standard_encodings() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "a", 1).unwrap();
+        assert_eq!(doc.document().to_msgpack().unwrap(), b"\x81\xa1a\x01");
+        doc.put(ROOT, "a", 1u64).unwrap();
+        assert_eq!(doc.document().to_msgpack().unwrap(), b"\x81\xa1a\xcc\x01");
+        doc.put(ROOT, "a", ScalarValue::Timestamp(1000)).unwrap();
+        assert_eq!(
+            doc.document().to_msgpack().unwrap(),
+            b"\x81\xa1a\xd7\xff\x00\x00\x00\x00\x00\x00\x00\x01"
+        );
+    }
+
+    #[test]
+    fn from_msgpack_rejects_malformed_input() {
+        for bad in [
+            &b"\x91\x01"[..],
+            b"\x81\xa1a",
+            b"\x81\x01\x01",
+            b"\x81\xa1a\xc1",
+            b"\x81\xa1a\xd4\x07\x00",
+            b"\x80\x80",
+            b"\xdf\xff\xff\xff\xff",
+        ] {
+            assert!(
+                matches!(
+                    Automerge::from_msgpack(bad),
+                    Err(AutomergeError::InvalidMsgpack(_))
+                ),
+                "{:?}",
+                bad
+            );
+        }
+        let mut deep = b"\x81\xa1a".to_vec();
+        deep.extend(std::iter::repeat(0x91).take(1000));
+        deep.push(0xc0);
+        assert!(Automerge::from_msgpack(&deep).is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn msgpack_round_trips_scalars(value in gen_scalar_value()) {
+            let mut doc = AutoCommit::new();
+            doc.put(ROOT, "value", value.clone()).unwrap();
+            let decoded = Automerge::from_msgpack(&doc.document().to_msgpack().unwrap()).unwrap();
+            let (decoded, _) = decoded.get(ROOT, "value").unwrap().unwrap();
+            let decoded = decoded.to_scalar().unwrap().clone();
+            match (&value, &decoded) {
+                (ScalarValue::F64(a), ScalarValue::F64(b)) if a.is_nan() => {
+                    proptest::prop_assert!(b.is_nan())
+                }
+                _ => proptest::prop_assert_eq!(&decoded, &value),
+            }
+        }
+
+        #[test]
+        fn msgpack_round_trips_unknown_and_timestamps(
+            type_code in 10u8..16,
+            bytes in proptest::collection::vec(proptest::num::u8::ANY, 0..20),
+            millis in proptest::num::i64::ANY,
+        ) {
+            let mut doc = AutoCommit::new();
+            let unknown = ScalarValue::Unknown { type_code, bytes };
+            doc.put(ROOT, "unknown", unknown.clone()).unwrap();
+            doc.put(ROOT, "timestamp", ScalarValue::Timestamp(millis)).unwrap();
+            let decoded = Automerge::from_msgpack(&doc.document().to_msgpack().unwrap()).unwrap();
+            let get = |key: &str| decoded.get(ROOT, key).unwrap().unwrap().0.into_owned();
+            proptest::prop_assert_eq!(get("unknown"), unknown.into());
+            proptest::prop_assert_eq!(get("timestamp"), ScalarValue::Timestamp(millis).into());
+        }
+    }
+}
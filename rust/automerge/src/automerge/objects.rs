@@ -0,0 +1,39 @@
+use crate::exid::ExId;
+use crate::{Automerge, ObjType};
+
+impl Automerge {
+    /// Iterate over the id of every object of type `typ` in the document, in causal order
+    ///
+    /// This walks the op set rather than the document tree, so objects which are no longer
+    /// referenced from the root, because the property or element holding them was deleted or
+    /// overwritten, are included. The root is yielded for [`ObjType::Map`]. [`ObjType::Map`] and
+    /// [`ObjType::Table`] are distinct, a table is only yielded for [`ObjType::Table`].
+    pub fn objects_of_type(&self, typ: ObjType) -> impl Iterator<Item = ExId> + '_ {
+        self.ops()
+            .iter_objs()
+            .filter(move |(_, obj_typ, _)| *obj_typ == typ)
+            .map(move |(obj, _, _)| self.id_to_exid(obj.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, ObjType, ROOT};
+
+    #[test]
+    fn only_objects_of_the_given_type_are_yielded() {
+        let mut doc = AutoCommit::new();
+        let title = doc.put_object(ROOT, "title", ObjType::Text).unwrap();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        let map = doc.insert_object(&list, 0, ObjType::Map).unwrap();
+        let body = doc.put_object(&map, "body", ObjType::Text).unwrap();
+        doc.delete(&list, 0).unwrap();
+
+        let texts = doc.objects_of_type(ObjType::Text).collect::<Vec<_>>();
+        assert_eq!(texts, vec![title, body]);
+        let maps = doc.objects_of_type(ObjType::Map).collect::<Vec<_>>();
+        assert_eq!(maps, vec![ROOT, map]);
+        assert_eq!(doc.objects_of_type(ObjType::Table).count(), 0);
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::patches::TextRepresentation;
+use crate::{Automerge, ChangeHash, Patch};
+
+type Observer = Arc<dyn Fn(&Patch) + Send + Sync>;
+
+/// The callbacks registered with [`Automerge::observe_changes`]
+#[derive(Clone, Default)]
+pub(crate) struct Observers(Vec<Observer>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observers")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl Observers {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Automerge {
+    /// Call `observer` with every [`Patch`] to the current state of this document from now on
+    ///
+    /// Observers are called after each transaction is committed and after each call which
+    /// applies changes from elsewhere, such as [`Self::apply_changes`], [`Self::merge`],
+    /// [`Self::load_incremental`] or receiving a sync message. The patches are the
+    /// [`Self::diff`] between the heads before and after, so an observer is not called for
+    /// changes which are already in the document or are still waiting for their dependencies.
+    /// Every observer receives every patch, in the order the observers were registered.
+    ///
+    /// Observers must be `Send` and `Sync` so that the document still is. They are shared with
+    /// clones of the document but not with forks.
+    pub fn observe_changes<F: Fn(&Patch) + Send + Sync + 'static>(&mut self, observer: F) {
+        self.observers.0.push(Arc::new(observer));
+    }
+
+    /// The heads to diff from when notifying observers, or `None` if there are no observers
+    pub(crate) fn observed_heads(&self) -> Option<Vec<ChangeHash>> {
+        if self.observers.is_empty() {
+            None
+        } else {
+            Some(self.get_heads())
+        }
+    }
+
+    /// Call the observers with the patches from `before` to the current heads
+    pub(crate) fn notify_observers(&self, before: Option<Vec<ChangeHash>>) {
+        let before = match before {
+            Some(before) => before,
+            None => return,
+        };
+        let after = self.get_heads();
+        if before == after {
+            return;
+        }
+        for patch in self.diff(&before, &after, TextRepresentation::default()) {
+            for observer in &self.observers.0 {
+                observer(&patch);
+            }
+        }
+    }
+}
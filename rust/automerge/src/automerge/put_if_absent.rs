@@ -0,0 +1,72 @@
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, Prop, ScalarValue};
+
+impl Automerge {
+    /// Set `prop` of `obj` to `value` in a new change, unless it already has a value
+    ///
+    /// Returns `true` if `value` was written and `false` if `prop` already had a value, in which
+    /// case no change is made. A property whose value was deleted counts as unset.
+    pub fn put_if_absent<O: AsRef<ExId>, P: Into<Prop>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        value: V,
+    ) -> Result<bool, AutomergeError> {
+        let mut tx = self.transaction();
+        match put_if_absent(&mut tx, obj.as_ref(), prop.into(), value.into()) {
+            Ok(true) => {
+                tx.commit();
+                Ok(true)
+            }
+            Ok(false) => {
+                tx.rollback();
+                Ok(false)
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Set `prop` of `obj` to `value` in `tx` if it has no value, see [`Automerge::put_if_absent`]
+pub(crate) fn put_if_absent<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    prop: Prop,
+    value: ScalarValue,
+) -> Result<bool, AutomergeError> {
+    if tx.get(obj, prop.clone())?.is_some() {
+        return Ok(false);
+    }
+    tx.put(obj, prop, value)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, Automerge, ReadDoc, Value, ROOT};
+
+    #[test]
+    fn put_if_absent_only_writes_unset_props() {
+        let mut doc = Automerge::new();
+        assert!(doc.put_if_absent(ROOT, "key", "first").unwrap());
+        let heads = doc.get_heads();
+        assert!(!doc.put_if_absent(ROOT, "key", "second").unwrap());
+        assert_eq!(doc.get_heads(), heads);
+        assert_eq!(
+            doc.get(ROOT, "key").unwrap().unwrap().0,
+            Value::from("first")
+        );
+
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "key", 1).unwrap();
+        assert!(!doc.put_if_absent(ROOT, "key", 2).unwrap());
+        doc.delete(ROOT, "key").unwrap();
+        assert!(doc.put_if_absent(ROOT, "key", 3).unwrap());
+        assert_eq!(doc.get(ROOT, "key").unwrap().unwrap().0, Value::from(3));
+    }
+}
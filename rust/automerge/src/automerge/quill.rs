@@ -0,0 +1,391 @@
+use serde_json::{Map, Value as Json};
+
+use super::json::number_to_scalar;
+use crate::exid::ExId;
+use crate::marks::{ExpandMark, Mark};
+use crate::text_value::TextValue;
+use crate::transaction::Transactable;
+use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ScalarValue};
+
+impl Automerge {
+    /// Apply the [Quill Delta] `delta` to the text object `obj` as a single change
+    ///
+    /// `delta` is the JSON of a delta, either an object with an `ops` array or the array of ops
+    /// itself. The ops are applied in order from the start of the text:
+    ///
+    /// * `{"retain": n}` moves over `n` characters, and sets the marks in its `attributes` on
+    ///   them. An attribute which is `null` removes the mark of that name.
+    /// * `{"insert": "text"}` inserts `text` with exactly the marks in its `attributes`, any mark
+    ///   the new text would otherwise have inherited from its neighbours is removed from it.
+    /// * `{"delete": n}` deletes `n` characters.
+    ///
+    /// Marks are set with [`ExpandMark::None`], as a delta gives the attributes of every
+    /// character it inserts. Lengths are measured in the same units as indices in
+    /// [`crate::transaction::Transactable::splice_text`]. Inserting an embed, which is an object
+    /// rather than a string, is not supported. If any op fails none of the delta is applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a text object,
+    /// [`AutomergeError::InvalidQuillDelta`] if `delta` isn't a delta this can apply and
+    /// [`AutomergeError::InvalidIndex`] if an op extends past the end of the text.
+    ///
+    /// [`Self::text_to_quill_delta`] gives the delta which creates a text object from scratch.
+    ///
+    /// [Quill Delta]: https://quilljs.com/docs/delta/
+    pub fn text_apply_quill_delta(
+        &mut self,
+        obj: &ExId,
+        delta: &str,
+    ) -> Result<(), AutomergeError> {
+        let mut tx = self.transaction();
+        match apply_quill_delta(&mut tx, obj, delta) {
+            Ok(()) => {
+                tx.commit();
+                Ok(())
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+
+    /// The text object `obj` as the JSON of a [Quill Delta] which inserts its text and marks
+    ///
+    /// The delta is an object with an `ops` array of inserts, one for each run of text with the
+    /// same marks, as in [`Self::text_spans`]. The marks of a run are the `attributes` of its
+    /// insert, which are left out if there are none. Applying the delta to an empty text object
+    /// with [`Self::text_apply_quill_delta`] recreates the text and its marks. An embedded object
+    /// is inserted as the `'\u{fffc}'` character [`crate::ReadDoc::text`] gives for it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a text object
+    ///
+    /// [Quill Delta]: https://quilljs.com/docs/delta/
+    pub fn text_to_quill_delta(&self, obj: &ExId) -> Result<String, AutomergeError> {
+        let mut ops: Vec<(String, Map<String, Json>)> = Vec::new();
+        for span in self.text_spans(obj)? {
+            let attributes = span
+                .marks
+                .iter()
+                // Scalars only fail to serialize if they contain non string map keys, which they
+                // don't
+                .map(|(name, value)| {
+                    let value = serde_json::to_value(value).unwrap_or(Json::Null);
+                    (name.clone(), value)
+                })
+                .collect::<Map<_, _>>();
+            match ops.last_mut() {
+                Some((text, last)) if *last == attributes => text.push_str(&span.text),
+                _ => ops.push((span.text, attributes)),
+            }
+        }
+        let ops = ops
+            .into_iter()
+            .map(|(text, attributes)| {
+                let mut op = Map::new();
+                op.insert("insert".to_string(), Json::String(text));
+                if !attributes.is_empty() {
+                    op.insert("attributes".to_string(), Json::Object(attributes));
+                }
+                Json::Object(op)
+            })
+            .collect();
+        let mut delta = Map::new();
+        delta.insert("ops".to_string(), Json::Array(ops));
+        Ok(Json::Object(delta).to_string())
+    }
+}
+
+/// A document a delta can be applied to, see [`apply_quill_delta`]
+pub(crate) trait QuillTarget: Transactable {
+    /// The document with the ops applied so far, to find the marks inserted text inherits
+    /// without reading every mark of the text as [`crate::ReadDoc::marks`] would
+    fn doc(&self) -> &Automerge;
+}
+
+impl QuillTarget for AutoCommit {
+    fn doc(&self) -> &Automerge {
+        &self.doc
+    }
+}
+
+/// Apply `delta` to the text object `obj` in `tx`, see [`Automerge::text_apply_quill_delta`]
+pub(crate) fn apply_quill_delta<T: QuillTarget>(
+    tx: &mut T,
+    obj: &ExId,
+    delta: &str,
+) -> Result<(), AutomergeError> {
+    let typ = tx.object_type(obj)?;
+    if typ != ObjType::Text {
+        return Err(AutomergeError::InvalidOp(typ));
+    }
+    let delta: Json = serde_json::from_str(delta).map_err(|e| invalid(&e.to_string()))?;
+    let ops = match &delta {
+        Json::Array(ops) => ops,
+        Json::Object(delta) => match delta.get("ops") {
+            Some(Json::Array(ops)) => ops,
+            _ => return Err(invalid("a delta object must have an `ops` array")),
+        },
+        _ => return Err(invalid("a delta must be an object or an array")),
+    };
+    let mut pos = 0;
+    for op in ops {
+        let op = op
+            .as_object()
+            .ok_or_else(|| invalid("an op must be an object"))?;
+        let attributes = attributes(op)?;
+        if let Some(insert) = op.get("insert") {
+            let text = insert
+                .as_str()
+                .ok_or_else(|| invalid("inserting embeds is not supported"))?;
+            check_end(tx, obj, pos)?;
+            tx.splice_text(obj, pos, 0, text)?;
+            let end = pos + TextValue::width(text);
+            // The inserted text is contiguous so every character of it inherits the same marks
+            if pos < end {
+                let inherited = tx
+                    .doc()
+                    .get_marks_at_index(obj, pos)?
+                    .into_keys()
+                    .filter(|name| attributes.iter().all(|(key, _)| key != name))
+                    .collect::<Vec<_>>();
+                for name in inherited {
+                    tx.unmark(obj, &name, pos, end, ExpandMark::None)?;
+                }
+            }
+            set_attributes(tx, obj, pos, end, attributes)?;
+            pos = end;
+        } else if let Some(retain) = op.get("retain") {
+            let end = pos + length(retain, "retain")?;
+            check_end(tx, obj, end)?;
+            set_attributes(tx, obj, pos, end, attributes)?;
+            pos = end;
+        } else if let Some(delete) = op.get("delete") {
+            let end = pos + length(delete, "delete")?;
+            check_end(tx, obj, end)?;
+            tx.splice_text(obj, pos, end - pos, "")?;
+        } else {
+            return Err(invalid("an op must be an insert, retain or delete"));
+        }
+    }
+    Ok(())
+}
+
+/// The `attributes` of `op` as mark names and values, `None` removing the mark
+fn attributes(op: &Map<String, Json>) -> Result<Vec<(&str, Option<ScalarValue>)>, AutomergeError> {
+    let attributes = match op.get("attributes") {
+        None | Some(Json::Null) => return Ok(Vec::new()),
+        Some(Json::Object(attributes)) => attributes,
+        Some(_) => return Err(invalid("attributes must be an object")),
+    };
+    attributes
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Json::Null => None,
+                Json::Bool(b) => Some(ScalarValue::Boolean(*b)),
+                Json::String(s) => Some(ScalarValue::Str(s.as_str().into())),
+                Json::Number(n) => Some(number_to_scalar(n)),
+                Json::Array(_) | Json::Object(_) => {
+                    return Err(invalid(&format!(
+                        "the value of attribute `{}` must be a scalar",
+                        name
+                    )))
+                }
+            };
+            Ok((name.as_str(), value))
+        })
+        .collect()
+}
+
+fn set_attributes<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    start: usize,
+    end: usize,
+    attributes: Vec<(&str, Option<ScalarValue>)>,
+) -> Result<(), AutomergeError> {
+    if start == end {
+        return Ok(());
+    }
+    for (name, value) in attributes {
+        match value {
+            Some(value) => tx.mark(
+                obj,
+                Mark::new(name.to_string(), value, start, end),
+                ExpandMark::None,
+            )?,
+            None => tx.unmark(obj, name, start, end, ExpandMark::None)?,
+        }
+    }
+    Ok(())
+}
+
+fn length(value: &Json, op: &str) -> Result<usize, AutomergeError> {
+    value
+        .as_u64()
+        .map(|n| n as usize)
+        .ok_or_else(|| invalid(&format!("`{}` must be a non-negative integer", op)))
+}
+
+fn check_end<T: Transactable>(tx: &T, obj: &ExId, end: usize) -> Result<(), AutomergeError> {
+    if end > tx.length(obj) {
+        Err(AutomergeError::InvalidIndex(end))
+    } else {
+        Ok(())
+    }
+}
+
+fn invalid(reason: &str) -> AutomergeError {
+    AutomergeError::InvalidQuillDelta(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exid::ExId;
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{Automerge, AutomergeError, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    /// The marks on `text` which cover at least one character
+    fn marks(doc: &Automerge, text: &ExId) -> Vec<(String, ScalarValue, usize, usize)> {
+        doc.marks(text)
+            .unwrap()
+            .into_iter()
+            .filter(|m| m.start < m.end)
+            .map(|m| (m.name().to_string(), m.value().clone(), m.start, m.end))
+            .collect()
+    }
+
+    #[test]
+    fn deltas_insert_delete_and_format_text() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        tx.commit();
+
+        doc.text_apply_quill_delta(
+            &text,
+            r#"{"ops": [{"insert": "Hello "}, {"insert": "world", "attributes": {"bold": true}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(doc.text(&text).unwrap(), "Hello world");
+        assert_eq!(
+            marks(&doc, &text),
+            vec![("bold".into(), true.into(), 6, 11)]
+        );
+
+        // Text inserted at the end of the bold range is not bold
+        doc.text_apply_quill_delta(
+            &text,
+            r#"[{"retain": 11}, {"insert": "!", "attributes": {"italic": true}}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            marks(&doc, &text),
+            vec![
+                ("bold".into(), true.into(), 6, 11),
+                ("italic".into(), true.into(), 11, 12),
+            ]
+        );
+
+        doc.text_apply_quill_delta(
+            &text,
+            r#"[{"delete": 1}, {"insert": "J"}, {"retain": 7, "attributes": {"bold": null, "link": "https://automerge.org"}}]"#,
+        )
+        .unwrap();
+        assert_eq!(doc.text(&text).unwrap(), "Jello world!");
+        assert_eq!(
+            marks(&doc, &text),
+            vec![
+                ("link".into(), "https://automerge.org".into(), 1, 8),
+                ("bold".into(), true.into(), 8, 11),
+                ("italic".into(), true.into(), 11, 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_to_quill_delta_round_trips() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        let copy = tx.put_object(ROOT, "copy", ObjType::Text).unwrap();
+        tx.splice_text(&text, 0, 0, "Hello bold world").unwrap();
+        tx.mark(
+            &text,
+            Mark::new("bold".into(), true, 6, 10),
+            ExpandMark::After,
+        )
+        .unwrap();
+        tx.mark(&text, Mark::new("size".into(), 12, 0, 5), ExpandMark::None)
+            .unwrap();
+        tx.commit();
+
+        let delta = doc.text_to_quill_delta(&text).unwrap();
+        assert_eq!(
+            delta,
+            r#"{"ops":[{"attributes":{"size":12},"insert":"Hello"},{"insert":" "},{"attributes":{"bold":true},"insert":"bold"},{"insert":" world"}]}"#
+        );
+        doc.text_apply_quill_delta(&copy, &delta).unwrap();
+        assert_eq!(doc.text(&copy).unwrap(), "Hello bold world");
+        assert_eq!(marks(&doc, &copy), marks(&doc, &text));
+        assert_eq!(doc.text_to_quill_delta(&copy).unwrap(), delta);
+
+        assert!(matches!(
+            doc.text_to_quill_delta(&ROOT),
+            Err(AutomergeError::InvalidOp(ObjType::Map))
+        ));
+    }
+
+    #[test]
+    fn inserted_text_drops_inherited_marks() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        tx.splice_text(&text, 0, 0, "ab").unwrap();
+        tx.mark(
+            &text,
+            Mark::new("bold".into(), true, 0, 2),
+            ExpandMark::After,
+        )
+        .unwrap();
+        tx.commit();
+
+        doc.text_apply_quill_delta(&text, r#"[{"retain": 2}, {"insert": "c"}]"#)
+            .unwrap();
+        assert_eq!(marks(&doc, &text), vec![("bold".into(), true.into(), 0, 2)]);
+    }
+
+    #[test]
+    fn invalid_deltas_are_not_applied() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        tx.splice_text(&text, 0, 0, "abc").unwrap();
+        tx.commit();
+        let heads = doc.get_heads();
+
+        assert!(matches!(
+            doc.text_apply_quill_delta(&text, r#"[{"delete": 1}, {"retain": 3}]"#),
+            Err(AutomergeError::InvalidIndex(3))
+        ));
+        assert!(matches!(
+            doc.text_apply_quill_delta(&text, r#"[{"insert": {"image": "a.png"}}]"#),
+            Err(AutomergeError::InvalidQuillDelta(_))
+        ));
+        assert!(matches!(
+            doc.text_apply_quill_delta(&text, "not json"),
+            Err(AutomergeError::InvalidQuillDelta(_))
+        ));
+        assert!(matches!(
+            doc.text_apply_quill_delta(&ROOT, "[]"),
+            Err(AutomergeError::InvalidOp(ObjType::Map))
+        ));
+        assert_eq!(doc.get_heads(), heads);
+        assert_eq!(doc.text(&text).unwrap(), "abc");
+    }
+}
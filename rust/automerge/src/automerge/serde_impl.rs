@@ -0,0 +1,422 @@
+//! `Serialize` and `Deserialize` for [`Automerge`] as the current value of the document
+//!
+//! Values which JSON can represent directly are written as is, maps as objects, lists as arrays,
+//! strings, booleans, null, signed integers and finite floats. Everything else is written as an
+//! object with a single key naming the type:
+//!
+//! | Value                 | Representation                                   |
+//! |-----------------------|--------------------------------------------------|
+//! | Text                  | `{"$text": "hello"}`                             |
+//! | Table                 | `{"$table": {"key": "value"}}`                   |
+//! | Uint                  | `{"$uint": 1}`                                   |
+//! | Counter               | `{"$counter": 1}`                                |
+//! | Timestamp             | `{"$timestamp": 1}`                              |
+//! | Bytes                 | `{"$bytes": "0102"}` (hex)                       |
+//! | Non finite F64        | `{"$f64": "NaN"}`, `"Infinity"` or `"-Infinity"` |
+//! | Unknown               | `{"$unknown": {"typeCode": 1, "bytes": "0102"}}` |
+//!
+//! Map keys which start with `$` are escaped with an extra `$` so they can't be mistaken for a
+//! type. Marks and conflicting values are not part of the representation.
+
+use std::fmt;
+
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::transaction::Snapshot;
+use crate::{Automerge, ObjType, ScalarValue, ROOT};
+
+const TAG_PREFIX: char = '$';
+
+impl Serialize for Automerge {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Snapshot::read(self, &ROOT)
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Automerge {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let entries = match Snapshot::deserialize(deserializer)? {
+            Snapshot::Map(ObjType::Map, entries) => entries,
+            _ => return Err(de::Error::custom("the root of a document must be a map")),
+        };
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        for (key, value) in entries {
+            value
+                .put(&mut tx, &ROOT, key.into())
+                .map_err(de::Error::custom)?;
+        }
+        tx.commit();
+        Ok(doc)
+    }
+}
+
+/// A single key map naming the type of `value`
+struct Tagged<'a, T>(&'a str, T);
+
+impl<'a, T: Serialize> Serialize for Tagged<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.0, &self.1)?;
+        map.end()
+    }
+}
+
+struct Entries<'a>(&'a [(String, Snapshot)]);
+
+impl<'a> Serialize for Entries<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0 {
+            if key.starts_with(TAG_PREFIX) {
+                map.serialize_entry(&format!("{}{}", TAG_PREFIX, key), value)?;
+            } else {
+                map.serialize_entry(key, value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Unknown {
+    type_code: u8,
+    bytes: String,
+}
+
+impl Serialize for Snapshot {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Snapshot::Map(ObjType::Table, entries) => {
+                Tagged("$table", Entries(entries)).serialize(serializer)
+            }
+            Snapshot::Map(_, entries) => Entries(entries).serialize(serializer),
+            Snapshot::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Snapshot::Text(text, _) => Tagged("$text", text).serialize(serializer),
+            Snapshot::Scalar(s) => match s {
+                ScalarValue::Str(s) => serializer.serialize_str(s),
+                ScalarValue::Int(i) => serializer.serialize_i64(*i),
+                ScalarValue::F64(f) if f.is_nan() => Tagged("$f64", "NaN").serialize(serializer),
+                ScalarValue::F64(f) if f.is_infinite() => {
+                    let name = if *f > 0.0 { "Infinity" } else { "-Infinity" };
+                    Tagged("$f64", name).serialize(serializer)
+                }
+                ScalarValue::F64(f) => serializer.serialize_f64(*f),
+                ScalarValue::Boolean(b) => serializer.serialize_bool(*b),
+                ScalarValue::Null => serializer.serialize_unit(),
+                ScalarValue::Uint(u) => Tagged("$uint", u).serialize(serializer),
+                ScalarValue::Counter(c) => Tagged("$counter", i64::from(c)).serialize(serializer),
+                ScalarValue::Timestamp(t) => Tagged("$timestamp", t).serialize(serializer),
+                ScalarValue::Bytes(b) => Tagged("$bytes", hex::encode(b)).serialize(serializer),
+                ScalarValue::Unknown { type_code, bytes } => Tagged(
+                    "$unknown",
+                    Unknown {
+                        type_code: *type_code,
+                        bytes: hex::encode(bytes),
+                    },
+                )
+                .serialize(serializer),
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Snapshot {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SnapshotVisitor)
+    }
+}
+
+struct SnapshotVisitor;
+
+impl<'de> Visitor<'de> for SnapshotVisitor {
+    type Value = Snapshot;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an automerge value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Snapshot, E> {
+        Ok(Snapshot::Scalar(ScalarValue::Boolean(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Snapshot, E> {
+        Ok(Snapshot::Scalar(ScalarValue::Int(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Snapshot, E> {
+        // Non negative integers are ints unless they are too big to be one
+        match i64::try_from(v) {
+            Ok(i) => Ok(Snapshot::Scalar(ScalarValue::Int(i))),
+            Err(_) => Ok(Snapshot::Scalar(ScalarValue::Uint(v))),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Snapshot, E> {
+        Ok(Snapshot::Scalar(ScalarValue::F64(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Snapshot, E> {
+        Ok(Snapshot::Scalar(ScalarValue::Str(v.into())))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Snapshot, E> {
+        Ok(Snapshot::Scalar(ScalarValue::Null))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Snapshot, E> {
+        self.visit_unit()
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Snapshot, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Snapshot::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Snapshot, A::Error> {
+        let first = match map.next_key::<String>()? {
+            Some(key) => key,
+            None => return Ok(Snapshot::Map(ObjType::Map, Vec::new())),
+        };
+        if let Some(tag) = type_tag(&first) {
+            let value = tagged_value(tag, &mut map)?;
+            if map.next_key::<de::IgnoredAny>()?.is_some() {
+                return Err(de::Error::custom(format!(
+                    "`{}` must be the only key in its object",
+                    first
+                )));
+            }
+            return Ok(value);
+        }
+        let mut entries = vec![(unescape(first), map.next_value()?)];
+        while let Some(key) = map.next_key::<String>()? {
+            if type_tag(&key).is_some() {
+                return Err(de::Error::custom(format!(
+                    "unescaped key `{}` in map, keys starting with `{}` must be escaped",
+                    key, TAG_PREFIX
+                )));
+            }
+            entries.push((unescape(key), map.next_value()?));
+        }
+        Ok(Snapshot::Map(ObjType::Map, entries))
+    }
+}
+
+/// The type named by `key` if `key` is a type tag rather than an escaped map key
+fn type_tag(key: &str) -> Option<&str> {
+    key.strip_prefix(TAG_PREFIX)
+        .filter(|rest| !rest.starts_with(TAG_PREFIX))
+}
+
+fn unescape(mut key: String) -> String {
+    if key.starts_with(TAG_PREFIX) {
+        key.remove(0);
+    }
+    key
+}
+
+fn tagged_value<'de, A: MapAccess<'de>>(tag: &str, map: &mut A) -> Result<Snapshot, A::Error> {
+    let scalar = match tag {
+        "text" => return Ok(Snapshot::Text(map.next_value()?, Vec::new())),
+        "table" => {
+            return match map.next_value()? {
+                Snapshot::Map(_, entries) => Ok(Snapshot::Map(ObjType::Table, entries)),
+                _ => Err(de::Error::custom("`$table` must be an object")),
+            }
+        }
+        "uint" => ScalarValue::Uint(map.next_value()?),
+        "counter" => ScalarValue::counter(map.next_value()?),
+        "timestamp" => ScalarValue::Timestamp(map.next_value()?),
+        "bytes" => ScalarValue::Bytes(decode_hex(&map.next_value::<String>()?)?),
+        "f64" => match map.next_value::<String>()?.as_str() {
+            "NaN" => ScalarValue::F64(f64::NAN),
+            "Infinity" => ScalarValue::F64(f64::INFINITY),
+            "-Infinity" => ScalarValue::F64(f64::NEG_INFINITY),
+            other => {
+                return Err(de::Error::custom(format!(
+                    "`{}` is not a non finite float",
+                    other
+                )))
+            }
+        },
+        "unknown" => {
+            let Unknown { type_code, bytes } = map.next_value()?;
+            ScalarValue::Unknown {
+                type_code,
+                bytes: decode_hex(&bytes)?,
+            }
+        }
+        other => {
+            return Err(de::Error::custom(format!(
+                "unknown type `{}{}`",
+                TAG_PREFIX, other
+            )))
+        }
+    };
+    Ok(Snapshot::Scalar(scalar))
+}
+
+fn decode_hex<E: de::Error>(s: &str) -> Result<Vec<u8>, E> {
+    hex::decode(s).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, ReadDoc, Value};
+
+    fn all_scalars() -> Vec<ScalarValue> {
+        vec![
+            ScalarValue::Bytes(vec![0, 1, 255]),
+            ScalarValue::Str("hello".into()),
+            ScalarValue::Int(-5),
+            ScalarValue::Int(i64::MAX),
+            ScalarValue::Uint(5),
+            ScalarValue::Uint(u64::MAX),
+            ScalarValue::F64(1.0),
+            ScalarValue::F64(-2.5),
+            ScalarValue::F64(f64::INFINITY),
+            ScalarValue::F64(f64::NEG_INFINITY),
+            ScalarValue::counter(10),
+            ScalarValue::Timestamp(1_690_000_000),
+            ScalarValue::Boolean(true),
+            ScalarValue::Null,
+        ]
+    }
+
+    fn roundtrip(doc: &mut AutoCommit) -> Automerge {
+        let json = serde_json::to_string(doc.document()).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn scalars_survive_json_roundtrip() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        for (index, value) in all_scalars().into_iter().enumerate() {
+            doc.insert(&list, index, value).unwrap();
+        }
+        let loaded = roundtrip(&mut doc);
+        let (_, loaded_list) = loaded.get(ROOT, "list").unwrap().unwrap();
+        let values = loaded
+            .values(&loaded_list)
+            .map(|(v, _)| v.into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            all_scalars()
+                .into_iter()
+                .map(Value::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn unknown_scalars_survive_json_roundtrip() {
+        // Unknown values only arrive in changes from other peers so we can't put one in a local
+        // document, check the value on its own instead
+        let unknown = ScalarValue::Unknown {
+            type_code: 20,
+            bytes: vec![1, 2, 3],
+        };
+        let json = serde_json::to_value(Snapshot::Scalar(unknown.clone())).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"$unknown": {"typeCode": 20, "bytes": "010203"}})
+        );
+        match serde_json::from_value(json).unwrap() {
+            Snapshot::Scalar(s) => assert_eq!(s, unknown),
+            other => panic!("unexpected snapshot {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nan_survives_json_roundtrip() {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, "nan", f64::NAN).unwrap();
+        let json = serde_json::to_value(doc.document()).unwrap();
+        assert_eq!(json, serde_json::json!({"nan": {"$f64": "NaN"}}));
+        let loaded: Automerge = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            loaded.get(ROOT, "nan").unwrap().unwrap().0,
+            Value::Scalar(s) if matches!(s.as_ref(), ScalarValue::F64(f) if f.is_nan())
+        ));
+    }
+
+    #[test]
+    fn objects_survive_json_roundtrip() {
+        let mut doc = AutoCommit::new();
+        let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+        doc.put(&map, "$text", "not a tag").unwrap();
+        doc.put(&map, "$$double", 1).unwrap();
+        doc.put_object(&map, "table", ObjType::Table).unwrap();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello").unwrap();
+        doc.put_object(ROOT, "empty", ObjType::Map).unwrap();
+
+        let json = serde_json::to_value(doc.document()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "empty": {},
+                "map": {
+                    "$$text": "not a tag",
+                    "$$$double": 1,
+                    "table": {"$table": {}},
+                },
+                "text": {"$text": "hello"},
+            })
+        );
+        let loaded: Automerge = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.hydrate(None), doc.hydrate(None));
+        let (_, loaded_map) = loaded.get(ROOT, "map").unwrap().unwrap();
+        let (_, loaded_table) = loaded.get(&loaded_map, "table").unwrap().unwrap();
+        assert_eq!(loaded.object_type(&loaded_table).unwrap(), ObjType::Table);
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        for json in [
+            r#"[1, 2]"#,
+            r#"{"a": {"$nope": 1}}"#,
+            r#"{"a": {"$uint": 1, "b": 2}}"#,
+            r#"{"a": 1, "$b": 2}"#,
+            r#"{"a": {"$f64": "1.0"}}"#,
+        ] {
+            assert!(serde_json::from_str::<Automerge>(json).is_err(), "{}", json);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::exid::ExId;
+use crate::transaction::Transactable;
+use crate::{Automerge, AutomergeError, Cursor};
+
+impl Automerge {
+    /// Splice `text` into the text object `obj` at `cursor` in a new change, see
+    /// [`Transactable::splice_text_at_cursor`]
+    ///
+    /// A [`Cursor`] doesn't record which object it belongs to, so `obj` must be given as well.
+    pub fn splice_text_at_cursor<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        cursor: &Cursor,
+        del: usize,
+        text: &str,
+    ) -> Result<(), AutomergeError> {
+        let mut tx = self.transaction();
+        match tx.splice_text_at_cursor(obj, cursor, del, text) {
+            Ok(()) => {
+                tx.commit();
+                Ok(())
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, ROOT};
+
+    #[test]
+    fn splice_at_cursor_follows_concurrent_edits() {
+        let mut doc1 = Automerge::new();
+        let mut tx = doc1.transaction();
+        let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+        tx.splice_text(&text, 0, 0, "hello world").unwrap();
+        tx.commit();
+        let cursor = doc1.get_cursor(&text, 6, None).unwrap();
+
+        let mut doc2 = doc1.fork();
+        let mut tx = doc2.transaction();
+        tx.splice_text(&text, 0, 0, "oh, ").unwrap();
+        tx.commit();
+        doc1.merge(&mut doc2).unwrap();
+
+        doc1.splice_text_at_cursor(&text, &cursor, 5, "there")
+            .unwrap();
+        assert_eq!(doc1.text(&text).unwrap(), "oh, hello there");
+
+        let mut doc = AutoCommit::new();
+        let other = doc.put_object(ROOT, "other", ObjType::Text).unwrap();
+        assert!(matches!(
+            doc.splice_text_at_cursor(&other, &cursor, 0, "x"),
+            Err(AutomergeError::InvalidCursor(_))
+        ));
+    }
+}
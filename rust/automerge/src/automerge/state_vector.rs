@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ActorId, Automerge, Change};
+
+/// The number of changes a document has from each actor
+///
+/// The changes of an actor are numbered by their sequence number, starting at one, and a
+/// document can only apply a change once it has every earlier change from the same actor, so the
+/// number of changes is also the highest sequence number the document has. Create one with
+/// [`Automerge::compute_state_vector`]. Comparing the state vectors of two documents shows which
+/// actors each has changes from that the other is missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateVector(BTreeMap<ActorId, u64>);
+
+impl StateVector {
+    /// The highest sequence number of the changes from `actor`, zero if there are none
+    pub fn get(&self, actor: &ActorId) -> u64 {
+        self.0.get(actor).copied().unwrap_or(0)
+    }
+
+    /// Iterate over each actor with its highest sequence number, in actor order
+    pub fn iter(&self) -> impl Iterator<Item = (&ActorId, u64)> {
+        self.0.iter().map(|(actor, seq)| (actor, *seq))
+    }
+
+    /// Whether every change described by `other` is also described by this state vector
+    pub fn covers(&self, other: &StateVector) -> bool {
+        other.iter().all(|(actor, seq)| self.get(actor) >= seq)
+    }
+}
+
+impl FromIterator<(ActorId, u64)> for StateVector {
+    fn from_iter<I: IntoIterator<Item = (ActorId, u64)>>(iter: I) -> Self {
+        StateVector(iter.into_iter().filter(|(_, seq)| *seq > 0).collect())
+    }
+}
+
+impl Automerge {
+    /// The highest sequence number of the changes this document has from each actor
+    pub fn compute_state_vector(&self) -> StateVector {
+        self.states
+            .iter()
+            .map(|(actor, changes)| {
+                (
+                    self.ops.m.actors.cache[*actor].clone(),
+                    changes.len() as u64,
+                )
+            })
+            .collect()
+    }
+
+    /// The changes this document has which are not described by `sv`, in the order they were
+    /// applied
+    ///
+    /// If `sv` is the state vector of another document, from [`Self::compute_state_vector`], these
+    /// are exactly the changes it needs to catch up with this document. Their dependencies are
+    /// either earlier in the result or already in the other document, so they can be applied
+    /// with [`Self::apply_changes`].
+    pub fn changes_for_state_vector(&self, sv: &StateVector) -> Vec<&Change> {
+        let mut change_indexes = self
+            .states
+            .iter()
+            .flat_map(|(actor, changes)| {
+                let have = sv.get(&self.ops.m.actors.cache[*actor]) as usize;
+                changes.get(have..).unwrap_or_default()
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        change_indexes.sort_unstable();
+        change_indexes
+            .into_iter()
+            .map(|i| &self.history[i])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateVector;
+    use crate::transaction::Transactable;
+    use crate::{ActorId, AutoCommit, Automerge, ROOT};
+
+    #[test]
+    fn state_vectors_count_the_changes_of_each_actor() {
+        let alice = ActorId::from(&[1][..]);
+        let bob = ActorId::from(&[2][..]);
+        let mut doc1 = AutoCommit::new().with_actor(alice.clone());
+        let mut doc2 = AutoCommit::new().with_actor(bob.clone());
+        assert_eq!(doc1.compute_state_vector(), StateVector::default());
+
+        for i in 0..3 {
+            doc1.put(ROOT, "a", i).unwrap();
+            doc1.commit();
+        }
+        doc2.put(ROOT, "b", 1).unwrap();
+        doc2.commit();
+
+        let before = doc1.compute_state_vector();
+        assert_eq!(before.get(&alice), 3);
+        assert_eq!(before.get(&bob), 0);
+
+        doc1.merge(&mut doc2).unwrap();
+        let after = doc1.compute_state_vector();
+        assert_eq!(
+            after.iter().collect::<Vec<_>>(),
+            vec![(&alice, 3), (&bob, 1)]
+        );
+        assert!(after.covers(&before));
+        assert!(after.covers(&doc2.compute_state_vector()));
+        assert!(!before.covers(&after));
+    }
+
+    #[test]
+    fn changes_for_state_vector_are_the_missing_changes() {
+        let put = |doc: &mut Automerge, key: &str| {
+            let mut tx = doc.transaction();
+            tx.put(ROOT, key, 1).unwrap();
+            tx.commit();
+        };
+        let mut doc1 = Automerge::new().with_actor(ActorId::from(&[1][..]));
+        put(&mut doc1, "a");
+        let mut doc2 = doc1.fork().with_actor(ActorId::from(&[2][..]));
+        put(&mut doc1, "b");
+        put(&mut doc2, "c");
+        doc1.merge(&mut doc2).unwrap();
+        put(&mut doc1, "d");
+
+        let missing = doc1.changes_for_state_vector(&doc2.compute_state_vector());
+        let hashes = missing.iter().map(|c| c.hash()).collect::<Vec<_>>();
+        let expected = doc1
+            .get_changes(&doc2.get_heads())
+            .iter()
+            .map(|c| c.hash())
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, expected);
+        assert_eq!(hashes.len(), 2);
+
+        doc2.apply_changes(missing.into_iter().cloned()).unwrap();
+        assert_eq!(doc2.get_heads(), doc1.get_heads());
+        assert!(doc1
+            .changes_for_state_vector(&doc2.compute_state_vector())
+            .is_empty());
+        assert_eq!(
+            doc1.changes_for_state_vector(&StateVector::default()).len(),
+            4
+        );
+    }
+}
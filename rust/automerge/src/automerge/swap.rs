@@ -0,0 +1,128 @@
+use crate::exid::ExId;
+use crate::transaction::{Snapshot, Transactable};
+use crate::{Automerge, AutomergeError, Prop};
+
+impl Automerge {
+    /// Swap the values of the properties `a` and `b` of the map or list `obj` in a new change
+    ///
+    /// Both values are read before either is written and both writes are made in the same change,
+    /// so no other replica ever sees one value without the other. Objects can't be moved, so a
+    /// swapped object is replaced by a copy of its current contents with a new id. Swapping a
+    /// property with itself does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::MissingProp`] if either property has no value.
+    pub fn swap<O: AsRef<ExId>, P1: Into<Prop>, P2: Into<Prop>>(
+        &mut self,
+        obj: O,
+        a: P1,
+        b: P2,
+    ) -> Result<(), AutomergeError> {
+        let mut tx = self.transaction();
+        match swap(&mut tx, obj.as_ref(), a.into(), b.into()) {
+            Ok(()) => {
+                tx.commit();
+                Ok(())
+            }
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Swap the values of `a` and `b` in `obj`, see [`Automerge::swap`]
+pub(crate) fn swap<T: Transactable>(
+    tx: &mut T,
+    obj: &ExId,
+    a: Prop,
+    b: Prop,
+) -> Result<(), AutomergeError> {
+    if a == b {
+        return Ok(());
+    }
+    let first = read(tx, obj, &a)?;
+    let second = read(tx, obj, &b)?;
+    second.put_value(tx, obj, a)?;
+    first.put_value(tx, obj, b)?;
+    Ok(())
+}
+
+fn read<T: Transactable>(tx: &T, obj: &ExId, prop: &Prop) -> Result<Snapshot, AutomergeError> {
+    match tx.get(obj, prop.clone())? {
+        Some((value, id)) => Snapshot::read_item(tx, value, &id),
+        None => Err(AutomergeError::MissingProp(prop.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, Value, ROOT};
+
+    #[test]
+    fn swap_exchanges_scalars_and_objects() {
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        doc.insert(&list, 0, "a").unwrap();
+        let map = doc.insert_object(&list, 1, ObjType::Map).unwrap();
+        doc.put(&map, "key", "value").unwrap();
+        doc.insert(&list, 2, "c").unwrap();
+        doc.commit();
+
+        let mut plain = doc.document().clone();
+        plain.swap(&list, 0, 1).unwrap();
+        doc.swap(&list, 0, 1).unwrap();
+        for doc in [doc.document(), &plain] {
+            let (value, id) = doc.get(&list, 0).unwrap().unwrap();
+            assert_eq!(value, Value::Object(ObjType::Map));
+            assert_ne!(id, map);
+            assert_eq!(
+                doc.get(&id, "key").unwrap().unwrap().0,
+                Value::from("value")
+            );
+            assert_eq!(doc.get(&list, 1).unwrap().unwrap().0, Value::from("a"));
+            assert_eq!(doc.length(&list), 3);
+        }
+
+        doc.put(ROOT, "x", 1).unwrap();
+        doc.put(ROOT, "y", 2).unwrap();
+        doc.swap(ROOT, "x", "y").unwrap();
+        assert_eq!(doc.get(ROOT, "x").unwrap().unwrap().0, Value::from(2));
+        assert_eq!(doc.get(ROOT, "y").unwrap().unwrap().0, Value::from(1));
+    }
+
+    #[test]
+    fn swap_with_a_missing_prop_changes_nothing() {
+        let mut doc = Automerge::new();
+        let mut tx = doc.transaction();
+        tx.put(ROOT, "x", 1).unwrap();
+        tx.commit();
+        let heads = doc.get_heads();
+        assert!(matches!(
+            doc.swap(ROOT, "x", "missing"),
+            Err(AutomergeError::MissingProp(_))
+        ));
+        assert_eq!(doc.get_heads(), heads);
+        doc.swap(ROOT, "x", "x").unwrap();
+        assert_eq!(doc.get_heads(), heads);
+    }
+
+    #[test]
+    fn swapped_text_keeps_its_marks() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        let bold = Mark::new("bold".into(), true, 0, 5);
+        doc.mark(&text, bold.clone(), ExpandMark::After).unwrap();
+        doc.put(ROOT, "other", 1).unwrap();
+
+        doc.swap(ROOT, "text", "other").unwrap();
+        let (_, copy) = doc.get(ROOT, "other").unwrap().unwrap();
+        assert_eq!(doc.text(&copy).unwrap(), "hello world");
+        assert_eq!(doc.marks(&copy).unwrap(), vec![bold]);
+    }
+}
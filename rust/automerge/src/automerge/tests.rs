@@ -1722,3 +1722,929 @@ fn get_changes_heads_empty() {
     let heads = doc.get_heads();
     assert_eq!(doc.get_changes(&heads), Vec::<&Change>::new());
 }
+
+#[test]
+fn keys_len_counts_visible_keys() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    doc1.put(ROOT, "b", 2).unwrap();
+    doc1.put(ROOT, "c", 3).unwrap();
+    doc1.delete(ROOT, "b").unwrap();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc1.insert(&list, 0, "x").unwrap();
+    assert_eq!(doc1.keys_len(ROOT), Ok(3));
+
+    // conflicting values for a key only count once
+    let mut doc2 = doc1.fork();
+    doc1.put(ROOT, "a", 4).unwrap();
+    doc2.put(ROOT, "a", 5).unwrap();
+    doc2.put(ROOT, "d", 6).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(doc1.keys_len(ROOT), Ok(4));
+    assert_eq!(doc1.keys_len(ROOT), Ok(doc1.keys(ROOT).count()));
+
+    assert_eq!(
+        doc1.keys_len(&list),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    );
+}
+
+#[test]
+fn contains_key_ignores_deleted_values() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.put(ROOT, "b", 2).unwrap();
+    doc.delete(ROOT, "b").unwrap();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "x").unwrap();
+    doc.insert(&list, 1, "y").unwrap();
+    doc.delete(&list, 1).unwrap();
+
+    assert_eq!(doc.contains_key(ROOT, "a"), Ok(true));
+    assert_eq!(doc.contains_key(ROOT, "b"), Ok(false));
+    assert_eq!(doc.contains_key(ROOT, "c"), Ok(false));
+    assert_eq!(doc.contains_key(&list, 0), Ok(true));
+    assert_eq!(doc.contains_key(&list, 1), Ok(false));
+}
+
+#[test]
+fn text_truncate() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+
+    assert_eq!(doc.text_truncate(&text, 20), Ok(0));
+    assert_eq!(doc.text_truncate(&text, 5), Ok(6));
+    assert_eq!(doc.text(&text).unwrap(), "hello");
+    assert_eq!(doc.text_truncate(&text, 0), Ok(5));
+    assert_eq!(doc.text(&text).unwrap(), "");
+
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    assert_eq!(
+        doc.text_truncate(&list, 0),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    );
+}
+
+#[test]
+fn clone_object_deep_copies_with_new_ids() {
+    let mut doc = AutoCommit::new();
+    let record = doc.put_object(ROOT, "record", ObjType::Map).unwrap();
+    doc.put(&record, "name", "alice").unwrap();
+    doc.put(&record, "count", ScalarValue::counter(1)).unwrap();
+    doc.increment(&record, "count", 2).unwrap();
+    let tags = doc.put_object(&record, "tags", ObjType::List).unwrap();
+    doc.insert(&tags, 0, "a").unwrap();
+    doc.insert_object(&tags, 1, ObjType::Map).unwrap();
+    let text = doc.put_object(&record, "notes", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+
+    // copying an object into itself should only copy the original contents
+    let copy = doc.clone_object(&record, &record, "copy").unwrap();
+    assert_ne!(copy, record);
+    assert_eq!(
+        doc.get(&copy, "name").unwrap().unwrap().0,
+        Value::from("alice")
+    );
+    assert_eq!(
+        doc.get(&copy, "count").unwrap().unwrap().0,
+        Value::counter(3)
+    );
+    assert_eq!(doc.get(&copy, "copy").unwrap(), None);
+    let (_, copied_tags) = doc.get(&copy, "tags").unwrap().unwrap();
+    assert_ne!(copied_tags, tags);
+    assert_eq!(doc.length(&copied_tags), 2);
+    assert_eq!(
+        doc.get(&copied_tags, 1).unwrap().unwrap().0,
+        Value::Object(ObjType::Map)
+    );
+    let (_, copied_text) = doc.get(&copy, "notes").unwrap().unwrap();
+    assert_eq!(doc.text(&copied_text).unwrap(), "hello");
+
+    // edits to the copy don't affect the original
+    doc.put(&copy, "name", "bob").unwrap();
+    assert_eq!(
+        doc.get(&record, "name").unwrap().unwrap().0,
+        Value::from("alice")
+    );
+
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "placeholder").unwrap();
+    let copied = doc.clone_object(&tags, &list, 0).unwrap();
+    assert_eq!(doc.object_type(&copied), Ok(ObjType::List));
+    assert_eq!(doc.length(&list), 1);
+}
+
+#[test]
+fn save_after_compresses_changes_and_loads_into_replica() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "foo", 1).unwrap();
+    doc.commit();
+    let mut replica = AutoCommit::load(&doc.save()).unwrap();
+    let heads = doc.get_heads();
+
+    // make a change which is large enough to be compressed
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, &"a".repeat(1000)).unwrap();
+    doc.commit();
+
+    let raw_len: usize = doc
+        .get_changes(&heads)
+        .iter()
+        .map(|c| c.raw_bytes().len())
+        .sum();
+    let incremental = doc.document().save_after(&heads);
+    assert!(incremental.len() < raw_len);
+
+    replica.load_incremental(&incremental).unwrap();
+    let full = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(replica.get_heads(), full.clone().get_heads());
+    assert_eq!(replica.hydrate(None), full.hydrate(None));
+    let heads = doc.get_heads();
+    assert!(doc.document().save_after(&heads).is_empty());
+}
+
+#[test]
+fn op_count_includes_overwritten_ops() {
+    let mut doc = AutoCommit::new();
+    assert_eq!(doc.op_count(), 0);
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.put(ROOT, "b", 2).unwrap();
+    assert_eq!(doc.op_count(), 2);
+    doc.put(ROOT, "a", 3).unwrap();
+    assert_eq!(doc.op_count(), 3);
+    doc.delete(ROOT, "b").unwrap();
+    assert_eq!(doc.op_count(), 3);
+    assert_eq!(doc.keys_len(ROOT), Ok(1));
+}
+
+#[test]
+fn text_pad() {
+    use crate::transaction::Alignment;
+
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "ab").unwrap();
+    doc.text_pad(&text, 4, '.', Alignment::Left).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "ab..");
+    doc.text_pad(&text, 6, '-', Alignment::Right).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "--ab..");
+    doc.text_pad(&text, 9, '*', Alignment::Center).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "*--ab..**");
+    doc.text_pad(&text, 3, '*', Alignment::Center).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "*--ab..**");
+
+    // Centering leaves the existing characters, with their marks and cursors, in place
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "ab").unwrap();
+    doc.mark(
+        &text,
+        Mark::new("bold".into(), true, 0, 2),
+        marks::ExpandMark::None,
+    )
+    .unwrap();
+    let cursor = doc.get_cursor(&text, 1, None).unwrap();
+    doc.text_pad(&text, 7, '_', Alignment::Center).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "__ab___");
+    let marks = doc.marks(&text).unwrap();
+    assert_eq!(marks.len(), 1);
+    assert_eq!((marks[0].start, marks[0].end), (2, 4));
+    assert_eq!(doc.get_cursor_position(&text, &cursor, None).unwrap(), 3);
+}
+
+#[test]
+fn iter_changes_topological_puts_deps_first() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork();
+    for i in 0..3 {
+        doc1.put(ROOT, "b", i).unwrap();
+        doc1.commit();
+        doc2.put(ROOT, "c", i).unwrap();
+        doc2.commit();
+    }
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1.clone()).unwrap();
+    doc1.put(ROOT, "d", 1).unwrap();
+    doc1.commit();
+    doc2.merge(&mut doc1.clone()).unwrap();
+
+    let order1 = doc1
+        .iter_changes_topological()
+        .map(|c| c.hash())
+        .collect::<Vec<_>>();
+    let order2 = doc2
+        .iter_changes_topological()
+        .map(|c| c.hash())
+        .collect::<Vec<_>>();
+    assert_eq!(order1.len(), 8);
+    assert_eq!(order1, order2);
+
+    let mut seen = HashSet::new();
+    for hash in &order1 {
+        let change = doc1.get_change_by_hash(hash).unwrap();
+        assert!(change.deps().iter().all(|d| seen.contains(d)));
+        seen.insert(*hash);
+    }
+}
+
+#[test]
+fn compact_preserves_state_and_conflicts() {
+    let mut doc1 = AutoCommit::new();
+    doc1.set_actor(ActorId::from(vec![1]));
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    for i in 0..10 {
+        doc1.insert(&list, i, i as i64).unwrap();
+    }
+    doc1.splice(&list, 2, 5, vec![]).unwrap();
+    let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc1.mark(
+        &text,
+        Mark::new("bold".to_string(), true, 0, 5),
+        marks::ExpandMark::After,
+    )
+    .unwrap();
+    doc1.put(ROOT, "counter", ScalarValue::counter(1)).unwrap();
+    doc1.increment(ROOT, "counter", 4).unwrap();
+    for i in 0..5 {
+        doc1.put(ROOT, "overwritten", i).unwrap();
+    }
+    doc1.put(ROOT, "deleted", "gone").unwrap();
+    doc1.delete(ROOT, "deleted").unwrap();
+    doc1.commit();
+
+    let mut doc2 = doc1.fork().with_actor(ActorId::from(vec![2]));
+    doc1.put(ROOT, "conflict", "one").unwrap();
+    let nested = doc2.put_object(ROOT, "conflict", ObjType::Map).unwrap();
+    doc2.put(&nested, "key", "two").unwrap();
+    doc1.put(&list, 0, "a").unwrap();
+    doc2.put(&list, 0, "b").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let compacted = doc1.document().compact().unwrap();
+    assert!(compacted.op_count() < doc1.op_count());
+    assert_eq!(compacted.hydrate(None), doc1.hydrate(None));
+
+    let conflict_values = |doc: &Automerge, obj: &ExId, prop: Prop| {
+        doc.get_all(obj, prop)
+            .unwrap()
+            .into_iter()
+            .map(|(v, _)| v.to_owned())
+            .collect::<Vec<_>>()
+    };
+    let compacted_list = compacted.get(ROOT, "list").unwrap().unwrap().1;
+    assert_eq!(
+        conflict_values(&compacted, &ROOT, "conflict".into()),
+        conflict_values(doc1.document(), &ROOT, "conflict".into())
+    );
+    assert_eq!(
+        conflict_values(&compacted, &compacted_list, 0.into()),
+        conflict_values(doc1.document(), &list, 0.into())
+    );
+
+    let compacted_text = compacted.get(ROOT, "text").unwrap().unwrap().1;
+    assert_eq!(
+        compacted.marks(&compacted_text).unwrap(),
+        doc1.marks(&text).unwrap()
+    );
+
+    let reloaded = Automerge::load(&compacted.save()).unwrap();
+    assert_eq!(reloaded.hydrate(None), doc1.hydrate(None));
+}
+
+#[test]
+fn clone_object_copies_current_counter_value() {
+    let mut doc = AutoCommit::new();
+    let src = doc.put_object(ROOT, "src", ObjType::Map).unwrap();
+    doc.put(&src, "count", ScalarValue::counter(1)).unwrap();
+    doc.increment(&src, "count", 2).unwrap();
+    let dst = doc.clone_object(&src, ROOT, "dst").unwrap();
+    doc.increment(&src, "count", 10).unwrap();
+
+    let reloaded = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(
+        reloaded.get(&dst, "count").unwrap().unwrap().0,
+        Value::counter(3)
+    );
+}
+
+#[test]
+fn pending_change_count_counts_uncommitted_ops() {
+    let mut doc = AutoCommit::new();
+    assert_eq!(doc.pending_change_count(), 0);
+    doc.put(ROOT, "a", 1).unwrap();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "x").unwrap();
+    assert_eq!(doc.pending_change_count(), 3);
+    doc.commit();
+    assert_eq!(doc.pending_change_count(), 0);
+}
+
+#[test]
+fn count_conflicts_counts_conflicted_props() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "a", 1).unwrap();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc1.insert(&list, 0, "x").unwrap();
+    doc1.insert(&list, 1, "y").unwrap();
+    assert_eq!(doc1.count_conflicts(), 0);
+
+    let mut doc2 = doc1.fork();
+    doc1.put(ROOT, "a", 2).unwrap();
+    doc1.put(&list, 1, "z").unwrap();
+    doc1.put(ROOT, "b", "only doc1").unwrap();
+    doc2.put(ROOT, "a", 3).unwrap();
+    doc2.put(&list, 1, "w").unwrap();
+    doc2.put(&list, 0, "v").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(doc1.count_conflicts(), 2);
+
+    let mut doc3 = doc1.fork();
+    doc3.put(ROOT, "a", 4).unwrap();
+    doc1.merge(&mut doc3).unwrap();
+    assert_eq!(doc1.count_conflicts(), 1);
+    assert_eq!(doc1.get_all(&list, 1).unwrap().len(), 2);
+}
+
+#[test]
+fn count_conflicts_skips_deleted_objects() {
+    let mut doc1 = AutoCommit::new();
+    let map = doc1.put_object(ROOT, "map", ObjType::Map).unwrap();
+    let mut doc2 = doc1.fork();
+    doc1.put(&map, "a", 1).unwrap();
+    doc2.put(&map, "a", 2).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(doc1.count_conflicts(), 1);
+
+    doc1.delete(ROOT, "map").unwrap();
+    assert_eq!(doc1.get_all(&map, "a").unwrap().len(), 2);
+    assert_eq!(doc1.count_conflicts(), 0);
+}
+
+#[test]
+fn cursor_follows_concurrent_inserts_and_deletes() {
+    let mut doc1 = AutoCommit::new();
+    let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "hello world").unwrap();
+    let cursor = doc1.get_cursor(&text, 6, None).unwrap();
+
+    let mut doc2 = doc1.fork();
+    doc2.splice_text(&text, 0, 0, "oh, ").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(doc1.text(&text).unwrap(), "oh, hello world");
+    assert_eq!(doc1.get_cursor_position(&text, &cursor, None).unwrap(), 10);
+
+    // Once the element under the cursor is deleted it resolves to the next surviving element
+    doc1.splice_text(&text, 9, 3, "").unwrap();
+    assert_eq!(doc1.text(&text).unwrap(), "oh, hellorld");
+    assert_eq!(doc1.get_cursor_position(&text, &cursor, None).unwrap(), 9);
+}
+
+#[test]
+fn list_insert_many_returns_ids_of_inserted_elements() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "a").unwrap();
+    doc.insert(&list, 1, "e").unwrap();
+    let ids = doc
+        .list_insert_many(&list, 1, ["b", "c", "d"].iter().map(|&s| s.into()))
+        .unwrap();
+    assert_eq!(ids.len(), 3);
+    let values: Vec<_> = doc
+        .list_range(&list, ..)
+        .map(|item| (item.value.into_string().unwrap(), item.id))
+        .collect();
+    assert_eq!(
+        values.iter().map(|(v, _)| v.as_str()).collect::<Vec<_>>(),
+        vec!["a", "b", "c", "d", "e"]
+    );
+    assert_eq!(
+        values[1..4].iter().map(|(_, id)| id).collect::<Vec<_>>(),
+        ids.iter().collect::<Vec<_>>()
+    );
+
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+    assert!(doc.list_insert_many(&map, 0, vec![1.into()]).is_err());
+}
+
+#[test]
+fn map_set_many_puts_every_entry() {
+    let mut doc = AutoCommit::new();
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+    doc.put(&map, "a", "old").unwrap();
+    doc.map_set_many(&map, vec![("a", ScalarValue::from(1)), ("b", "two".into())])
+        .unwrap();
+    assert_eq!(doc.get(&map, "a").unwrap().unwrap().0, Value::int(1));
+    assert_eq!(doc.get(&map, "b").unwrap().unwrap().0, Value::str("two"));
+
+    assert_eq!(
+        doc.map_set_many(&map, vec![("c", 3), ("", 4)]),
+        Err(AutomergeError::EmptyStringKey)
+    );
+    assert_eq!(doc.get(&map, "c").unwrap(), None);
+
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    assert_eq!(
+        doc.map_set_many(&list, vec![("c", 3)]),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    );
+}
+
+#[test]
+fn rollback_with_reports_discarded_ops_in_reverse() {
+    let mut doc = Automerge::new();
+    let mut tx = doc.transaction();
+    let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+    tx.insert(&list, 0, "a").unwrap();
+    tx.commit();
+
+    let mut tx = doc.transaction();
+    tx.put(ROOT, "x", 1).unwrap();
+    tx.put(ROOT, "y", "two").unwrap();
+    tx.put(&list, 0, "b").unwrap();
+    let mut seen = Vec::new();
+    let cancelled = tx.rollback_with(|obj, prop, action| seen.push((obj, prop, action)));
+
+    assert_eq!(cancelled, 3);
+    assert_eq!(
+        seen,
+        vec![
+            (list.clone(), Prop::Seq(0), OpType::Put("b".into())),
+            (ROOT, Prop::Map("y".into()), OpType::Put("two".into())),
+            (ROOT, Prop::Map("x".into()), OpType::Put(1.into())),
+        ]
+    );
+    assert_eq!(doc.get(ROOT, "x").unwrap(), None);
+    assert_eq!(doc.get(&list, 0).unwrap().unwrap().0, Value::str("a"));
+
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "x", 1).unwrap();
+    let mut seen = Vec::new();
+    assert_eq!(doc.rollback_with(|_, prop, _| seen.push(prop)), 1);
+    assert_eq!(seen, vec![Prop::Map("x".into())]);
+}
+
+#[test]
+fn change_accessors_expose_metadata_and_ops() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.commit();
+    let first = doc.get_heads();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "x").unwrap();
+    doc.commit_with(
+        CommitOptions::default()
+            .with_message("add list")
+            .with_time(1234),
+    );
+
+    let change = doc.get_last_local_change().unwrap().clone();
+    assert_eq!(change.message(), Some(&"add list".to_string()));
+    assert_eq!(change.timestamp(), 1234);
+    assert_eq!(change.actor_id(), doc.get_actor());
+    assert_eq!(change.deps(), first.as_slice());
+    assert_eq!(change.seq(), 2);
+
+    let actor = doc.get_actor().clone();
+    let ops: Vec<_> = change.ops().collect();
+    assert_eq!(ops.len(), change.len());
+    let list_id = ChangeOpId {
+        counter: 2,
+        actor: actor.clone(),
+    };
+    assert_eq!(
+        ops,
+        vec![
+            OpDetails {
+                id: list_id.clone(),
+                obj: None,
+                key: OpKey::Map("list".into()),
+                insert: false,
+                action: OpType::Make(ObjType::List),
+                pred: vec![],
+            },
+            OpDetails {
+                id: ChangeOpId { counter: 3, actor },
+                obj: Some(list_id),
+                key: OpKey::Seq(None),
+                insert: true,
+                action: OpType::Put("x".into()),
+                pred: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn change_op_details_reference_other_actors() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "key", 1).unwrap();
+    doc1.commit();
+    let first = doc1.get_last_local_change().unwrap().ops().next().unwrap();
+
+    let mut doc2 = doc1.fork();
+    doc2.put(ROOT, "key", 2).unwrap();
+    doc2.commit();
+    let ops: Vec<_> = doc2.get_last_local_change().unwrap().ops().collect();
+    assert_eq!(ops.len(), 1);
+    assert_eq!(ops[0].key, OpKey::Map("key".into()));
+    assert_eq!(ops[0].pred, vec![first.id]);
+    assert_eq!(&ops[0].id.actor, doc2.get_actor());
+}
+
+#[test]
+fn text_set_only_splices_changed_characters() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "the quick fox").unwrap();
+    doc.mark(
+        &text,
+        Mark::new("bold".into(), true, 4, 9),
+        marks::ExpandMark::None,
+    )
+    .unwrap();
+    let cursor = doc.get_cursor(&text, 10, None).unwrap();
+    doc.commit();
+
+    doc.text_set(&text, "the quick brown fox!").unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "the quick brown fox!");
+    assert_eq!(doc.pending_change_count(), 7);
+    assert_eq!(doc.get_cursor_position(&text, &cursor, None).unwrap(), 16);
+    let marks = doc.marks(&text).unwrap();
+    assert_eq!((marks[0].start, marks[0].end), (4, 9));
+
+    doc.text_set(&text, "").unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "");
+    doc.text_set(&text, "añ😀").unwrap();
+    doc.text_set(&text, "a😀ñ!").unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "a😀ñ!");
+
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+    assert_eq!(
+        doc.text_set(&map, "x"),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    );
+}
+
+#[test]
+fn heads_round_trip_through_a_string() {
+    let mut doc1 = AutoCommit::new();
+    assert_eq!(doc1.get_heads_as_string(), "");
+    assert_eq!(Automerge::parse_heads_string("").unwrap(), vec![]);
+
+    doc1.put(ROOT, "a", 1).unwrap();
+    let mut doc2 = doc1.fork();
+    doc1.put(ROOT, "b", 1).unwrap();
+    doc2.put(ROOT, "c", 1).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let heads = doc1.get_heads();
+    assert_eq!(heads.len(), 2);
+    let s = doc1.get_heads_as_string();
+    assert_eq!(s, format!("{},{}", heads[0], heads[1]));
+    assert_eq!(Automerge::parse_heads_string(&s).unwrap(), heads);
+    assert_eq!(
+        Automerge::parse_heads_string(&format!(" {} , {} ", heads[0], heads[1])).unwrap(),
+        heads
+    );
+    assert!(Automerge::parse_heads_string("abc").is_err());
+    assert!(Automerge::parse_heads_string(&format!("{},", heads[0])).is_err());
+}
+
+#[test]
+fn change_graph_is_topologically_ordered() {
+    // a diamond: base <- (left, right) <- merge
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "base", 1).unwrap();
+    let base = doc1.commit().unwrap();
+    let mut doc2 = doc1.fork();
+    doc1.put(ROOT, "left", 1).unwrap();
+    let left = doc1.commit().unwrap();
+    doc2.put(ROOT, "right", 1).unwrap();
+    let right = doc2.commit().unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    doc1.put(ROOT, "merge", 1).unwrap();
+    let merge = doc1.commit().unwrap();
+
+    let graph = doc1.change_graph();
+    assert_eq!(graph.len(), 4);
+    let position = |hash: &ChangeHash| graph.iter().position(|n| &n.hash == hash).unwrap();
+    for node in &graph {
+        for dep in &node.deps {
+            assert!(position(dep) < position(&node.hash));
+        }
+    }
+    assert_eq!(position(&base), 0);
+    assert_eq!(position(&merge), 3);
+    let merge_node = &graph[3];
+    let mut merge_deps = merge_node.deps.clone();
+    merge_deps.sort();
+    let mut expected = vec![left, right];
+    expected.sort();
+    assert_eq!(merge_deps, expected);
+    assert_eq!(merge_node.actor, *doc1.get_actor());
+    assert_eq!(merge_node.seq, 3);
+
+    assert_eq!(doc1.change_graph(), graph);
+    let mut reloaded = AutoCommit::load(&doc1.save()).unwrap();
+    assert_eq!(reloaded.change_graph(), graph);
+}
+
+#[test]
+fn mark_all_covers_the_whole_text() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let pending = doc.pending_ops();
+    doc.mark_all(&text, "bold", true, marks::ExpandMark::Both)
+        .unwrap();
+    assert_eq!(doc.pending_ops(), pending);
+    assert_eq!(doc.marks(&text).unwrap(), vec![]);
+
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc.mark_all(&text, "bold", true, marks::ExpandMark::None)
+        .unwrap();
+    assert_eq!(
+        doc.marks(&text).unwrap(),
+        vec![Mark::new("bold".into(), true, 0, 11)]
+    );
+
+    assert!(doc
+        .mark_all(ROOT, "bold", true, marks::ExpandMark::None)
+        .is_err());
+}
+
+#[test]
+fn remove_all_marks_removes_each_run_once() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "the quick brown fox").unwrap();
+    let none = marks::ExpandMark::None;
+    doc.mark(&text, Mark::new("bold".into(), true, 0, 3), none)
+        .unwrap();
+    doc.mark(&text, Mark::new("bold".into(), "heavy", 3, 6), none)
+        .unwrap();
+    doc.mark(&text, Mark::new("bold".into(), true, 10, 15), none)
+        .unwrap();
+    doc.mark(&text, Mark::new("italic".into(), true, 4, 9), none)
+        .unwrap();
+    doc.commit();
+
+    doc.remove_all_marks(&text, "bold").unwrap();
+    // Each unmark is a begin and an end op, one unmark for 0..6 and one for 10..15
+    assert_eq!(doc.pending_ops(), 4);
+    assert_eq!(
+        doc.marks(&text).unwrap(),
+        vec![Mark::new("italic".into(), true, 4, 9)]
+    );
+
+    doc.remove_all_marks(&text, "bold").unwrap();
+    assert_eq!(doc.pending_ops(), 4);
+}
+
+#[test]
+fn encode_compact_loads_to_the_same_state() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    for _ in 0..50 {
+        doc.splice_text(&text, 0, 0, "some text to delete ")
+            .unwrap();
+        doc.commit();
+        doc.splice_text(&text, 0, 20, "").unwrap();
+        doc.commit();
+    }
+    doc.splice_text(&text, 0, 0, "final").unwrap();
+    doc.put(ROOT, "key", 1).unwrap();
+
+    let encoded = doc.encode_compact().unwrap();
+    assert!(encoded.len() < doc.save().len());
+    let loaded = Automerge::load(&encoded).unwrap();
+    assert_eq!(loaded.hydrate(None), doc.hydrate(None));
+    assert_eq!(loaded.get_changes(&[]).len(), 1);
+}
+
+#[test]
+fn diff_cursor_returns_patches_since_last_poll() {
+    let mut doc = Automerge::new();
+    let mut tx = doc.transaction();
+    let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+    tx.commit();
+    let mut cursor = doc.new_diff_cursor();
+    assert_eq!(cursor.heads(), doc.get_heads().as_slice());
+
+    let mut tx = doc.transaction();
+    tx.insert(&list, 0, "a").unwrap();
+    tx.commit();
+    let patches = cursor.patches(&doc);
+    assert_eq!(patches.len(), 1);
+    assert!(matches!(
+        patches[0].action,
+        PatchAction::Insert { index: 0, .. }
+    ));
+    assert!(cursor.patches(&doc).is_empty());
+
+    let mut tx = doc.transaction();
+    tx.put(ROOT, "key", 1).unwrap();
+    tx.delete(&list, 0).unwrap();
+    tx.commit();
+    let patches = cursor.patches(&doc);
+    assert_eq!(patches.len(), 2);
+    assert_eq!(cursor.heads(), doc.get_heads().as_slice());
+}
+
+#[test]
+fn map_diff_describes_each_changed_key() {
+    let mut doc1 = AutoCommit::new();
+    let map = doc1.put_object(ROOT, "map", ObjType::Map).unwrap();
+    doc1.put(&map, "removed", 1).unwrap();
+    doc1.put(&map, "changed", 1).unwrap();
+    doc1.put(&map, "conflicted", 1).unwrap();
+    doc1.put(&map, "same", 1).unwrap();
+    let before = doc1.get_heads();
+
+    let mut doc2 = doc1.fork();
+    doc1.delete(&map, "removed").unwrap();
+    doc1.put(&map, "changed", 2).unwrap();
+    let added = doc1.put_object(&map, "added", ObjType::List).unwrap();
+    doc1.put(&map, "conflicted", 2).unwrap();
+    doc2.put(&map, "conflicted", 3).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    let after = doc1.get_heads();
+
+    let diff = doc1.map_diff(&map, &before, &after).unwrap();
+    let conflicted = doc1
+        .get_all(&map, "conflicted")
+        .unwrap()
+        .into_iter()
+        .map(|(v, id)| (v.into_owned(), id))
+        .collect::<Vec<_>>();
+    assert_eq!(conflicted.len(), 2);
+    let changed_old = doc1.get_at(&map, "changed", &before).unwrap().unwrap();
+    let changed_new = doc1.get(&map, "changed").unwrap().unwrap();
+    let removed = doc1.get_at(&map, "removed", &before).unwrap().unwrap();
+    assert_eq!(
+        diff,
+        vec![
+            MapDiffEntry::Added(
+                "added".into(),
+                (Value::Object(ObjType::List), added.clone())
+            ),
+            MapDiffEntry::Changed(
+                "changed".into(),
+                (changed_old.0.into_owned(), changed_old.1),
+                (changed_new.0.into_owned(), changed_new.1),
+            ),
+            MapDiffEntry::Conflicted("conflicted".into(), conflicted),
+            MapDiffEntry::Removed("removed".into(), (removed.0.into_owned(), removed.1)),
+        ]
+    );
+
+    assert!(doc1.map_diff(&added, &before, &after).is_err());
+    assert!(doc1.map_diff(&map, &after, &after).unwrap().is_empty());
+}
+
+#[test]
+fn list_diff_uses_indices_in_the_after_state() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    for (i, value) in ["a", "b", "c", "d"].iter().enumerate() {
+        doc.insert(&list, i, *value).unwrap();
+    }
+    doc.insert(&list, 4, ScalarValue::counter(1)).unwrap();
+    let before = doc.get_heads();
+
+    doc.insert(&list, 0, "x").unwrap();
+    let x = doc.get(&list, 0).unwrap().unwrap().1;
+    doc.delete(&list, 2).unwrap();
+    doc.delete(&list, 2).unwrap();
+    doc.put(&list, 2, "D").unwrap();
+    doc.increment(&list, 3, 2).unwrap();
+    let after = doc.get_heads();
+    assert_eq!(
+        doc.list_range(&list, ..)
+            .map(|item| item.value.into_owned())
+            .collect::<Vec<_>>(),
+        vec![
+            Value::from("x"),
+            Value::from("a"),
+            Value::from("D"),
+            Value::counter(3)
+        ]
+    );
+
+    assert_eq!(
+        doc.list_diff(&list, &before, &after).unwrap(),
+        vec![
+            ListDiffEntry::Insert(0, "x".into(), x),
+            ListDiffEntry::Delete(2, "b".into()),
+            ListDiffEntry::Delete(2, "c".into()),
+            ListDiffEntry::Replace(2, "d".into(), "D".into()),
+            ListDiffEntry::Replace(3, Value::counter(1), Value::counter(3)),
+        ]
+    );
+    assert!(doc.list_diff(ROOT, &before, &after).is_err());
+}
+
+#[test]
+fn observers_receive_every_patch() {
+    use std::sync::{Arc, Mutex};
+
+    let first = Arc::new(Mutex::new(Vec::new()));
+    let second = Arc::new(Mutex::new(Vec::new()));
+    let mut doc = Automerge::new();
+    let log = Arc::clone(&first);
+    doc.observe_changes(move |patch| log.lock().unwrap().push(patch.clone()));
+    let log = Arc::clone(&second);
+    doc.observe_changes(move |patch| log.lock().unwrap().push(patch.clone()));
+
+    let mut tx = doc.transaction();
+    tx.put(ROOT, "a", 1).unwrap();
+    tx.put(ROOT, "b", 2).unwrap();
+    tx.commit();
+    assert_eq!(first.lock().unwrap().len(), 2);
+
+    let mut other = doc.fork();
+    let mut tx = other.transaction();
+    tx.put(ROOT, "c", 3).unwrap();
+    tx.commit();
+    doc.merge(&mut other).unwrap();
+    let patches = first.lock().unwrap().clone();
+    assert_eq!(patches.len(), 3);
+    match &patches[2].action {
+        PatchAction::PutMap { key, value, .. } => {
+            assert_eq!(key, "c");
+            assert_eq!(value.0, Value::from(3));
+        }
+        other => panic!("unexpected patch {:?}", other),
+    }
+    assert_eq!(*second.lock().unwrap(), patches);
+
+    // Nothing new, so nothing is observed
+    doc.merge(&mut other).unwrap();
+    assert_eq!(first.lock().unwrap().len(), 3);
+
+    let mut loaded = Automerge::new();
+    let log = Arc::clone(&first);
+    loaded.observe_changes(move |patch| log.lock().unwrap().push(patch.clone()));
+    loaded.load_incremental(&doc.save()).unwrap();
+    assert_eq!(first.lock().unwrap().len(), 6);
+}
+
+#[test]
+fn actors_sort_by_first_seen_seq() {
+    let first = ActorId::from(&[3][..]);
+    let second = ActorId::from(&[1][..]);
+    let third = ActorId::from(&[2][..]);
+
+    let mut doc = AutoCommit::new().with_actor(first.clone());
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.put(ROOT, "b", 1).unwrap();
+    doc.commit();
+    let mut fork = doc.fork().with_actor(second.clone());
+    fork.put(ROOT, "c", 1).unwrap();
+    doc.merge(&mut fork).unwrap();
+    let mut fork = doc.fork().with_actor(third.clone());
+    fork.put(ROOT, "d", 1).unwrap();
+    doc.merge(&mut fork).unwrap();
+    doc.put(ROOT, "e", 1).unwrap();
+    doc.commit();
+
+    let doc = doc.document();
+    assert_eq!(doc.actor_first_seen_seq(&first), Some(1));
+    assert_eq!(doc.actor_first_seen_seq(&second), Some(3));
+    assert_eq!(doc.actor_first_seen_seq(&third), Some(4));
+    assert_eq!(doc.actor_first_seen_seq(&ActorId::from(&[4][..])), None);
+
+    let mut actors = vec![third.clone(), first.clone(), second.clone()];
+    actors.sort();
+    assert_eq!(actors, vec![second.clone(), third.clone(), first.clone()]);
+    actors.sort_by_key(|actor| doc.actor_first_seen_seq(actor));
+    assert_eq!(actors, vec![first, second, third]);
+}
+
+#[test]
+fn concurrent_actors_tie_on_first_seen_seq() {
+    let first = ActorId::from(&[3][..]);
+    let second = ActorId::from(&[1][..]);
+    let third = ActorId::from(&[2][..]);
+
+    let mut doc = AutoCommit::new().with_actor(first.clone());
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.commit();
+    let mut fork1 = doc.fork().with_actor(third.clone());
+    let mut fork2 = doc.fork().with_actor(second.clone());
+    fork1.put(ROOT, "b", 1).unwrap();
+    fork2.put(ROOT, "c", 1).unwrap();
+    doc.merge(&mut fork1).unwrap();
+    doc.merge(&mut fork2).unwrap();
+
+    let doc = doc.document();
+    assert_eq!(doc.actor_first_seen_seq(&second), Some(2));
+    assert_eq!(doc.actor_first_seen_seq(&third), Some(2));
+
+    let mut actors = vec![third.clone(), first.clone(), second.clone()];
+    actors.sort_by_key(|actor| (doc.actor_first_seen_seq(actor), actor.clone()));
+    assert_eq!(actors, vec![first, second, third]);
+}
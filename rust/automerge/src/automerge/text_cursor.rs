@@ -0,0 +1,90 @@
+use crate::exid::ExId;
+use crate::{Automerge, AutomergeError, Cursor, ObjType, ReadDoc};
+
+impl Automerge {
+    /// Convert the offset `offset` in the text object `obj` into a [`Cursor`]
+    ///
+    /// This is the inverse of [`ReadDoc::get_cursor_position`]. Offsets are in the units of the
+    /// document's text encoding, the same as [`ReadDoc::length`] and
+    /// [`crate::transaction::Transactable::splice_text`]: Unicode code points, or UTF-16 code
+    /// units when compiled for WebAssembly.
+    ///
+    /// A cursor always points at a character, so an offset equal to the length of the text gives
+    /// the cursor of the last character, which [`ReadDoc::get_cursor_position`] resolves to one
+    /// less than the length. Add one to the resolved position to get back to the end of the
+    /// text.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a text object and
+    /// [`AutomergeError::InvalidIndex`] if `offset` is past the end of the text, or if the text
+    /// is empty as there is then no character to point at
+    pub fn text_to_cursor<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        offset: usize,
+    ) -> Result<Cursor, AutomergeError> {
+        let meta = self.exid_to_obj(obj.as_ref())?;
+        if meta.typ != ObjType::Text {
+            return Err(AutomergeError::InvalidOp(meta.typ));
+        }
+        let length = self.length(obj.as_ref());
+        if offset < length {
+            self.get_cursor(obj, offset, None)
+        } else if offset == length && length > 0 {
+            self.get_cursor(obj, length - 1, None)
+        } else {
+            Err(AutomergeError::InvalidIndex(offset))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, AutomergeError, ObjType, ReadDoc, ROOT};
+
+    #[test]
+    fn offsets_follow_the_text_encoding() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "héllo 🌍!").unwrap();
+
+        let offset = doc.length(&text) - 1;
+        let cursor = doc.text_to_cursor(&text, offset).unwrap();
+        doc.splice_text(&text, 0, 1, "H").unwrap();
+        doc.splice_text(&text, 6, 0, "wide ").unwrap();
+        assert_eq!(
+            doc.get_cursor_position(&text, &cursor, None).unwrap(),
+            offset + 5
+        );
+        assert_eq!(doc.text(&text).unwrap(), "Héllo wide 🌍!");
+    }
+
+    #[test]
+    fn the_length_of_the_text_gives_the_cursor_of_the_last_character() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        assert!(matches!(
+            doc.text_to_cursor(&text, 0),
+            Err(AutomergeError::InvalidIndex(0))
+        ));
+
+        doc.splice_text(&text, 0, 0, "ab€").unwrap();
+        let end = doc.text_to_cursor(&text, 3).unwrap();
+        assert_eq!(end, doc.text_to_cursor(&text, 2).unwrap());
+        assert_eq!(doc.get_cursor_position(&text, &end, None).unwrap(), 2);
+
+        doc.splice_text(&text, 0, 0, "cd").unwrap();
+        assert_eq!(doc.get_cursor_position(&text, &end, None).unwrap(), 4);
+
+        assert!(matches!(
+            doc.text_to_cursor(&text, 6),
+            Err(AutomergeError::InvalidIndex(6))
+        ));
+        assert!(matches!(
+            doc.text_to_cursor(ROOT, 0),
+            Err(AutomergeError::InvalidOp(ObjType::Map))
+        ));
+    }
+}
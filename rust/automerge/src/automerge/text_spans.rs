@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use crate::exid::ExId;
+use crate::marks::Span;
+use crate::{Automerge, AutomergeError, ObjType};
+
+impl Automerge {
+    /// Split the text object `obj` into maximal runs of characters which share the same marks
+    ///
+    /// Each embedded object (a block element, rendered as `'\u{fffc}'` by [`crate::ReadDoc::text`])
+    /// is returned as a span of its own, even if it has the same marks as its neighbours.
+    /// Concatenating the text of every span gives the same string as [`crate::ReadDoc::text`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a text object
+    pub fn text_spans<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<Span>, AutomergeError> {
+        let meta = self.exid_to_obj(obj.as_ref())?;
+        if meta.typ != ObjType::Text {
+            return Err(AutomergeError::InvalidOp(meta.typ));
+        }
+        let marks = self.calculate_marks(obj, None)?;
+        let mut spans: Vec<Span> = Vec::new();
+        let mut index = 0;
+        let mut last_was_block = false;
+        for top in self.ops().top_ops(&meta.id, None) {
+            let is_block = top.op.action.is_make();
+            let active = marks
+                .iter()
+                .filter(|m| m.start <= index && index < m.end)
+                .map(|m| (m.name().to_string(), m.value().clone()))
+                .collect::<BTreeMap<_, _>>();
+            index += top.op.width(meta.encoding);
+            match spans.last_mut() {
+                Some(span) if !is_block && !last_was_block && span.marks == active => {
+                    span.text.push_str(top.op.to_str());
+                }
+                _ => spans.push(Span {
+                    text: top.op.to_str().to_string(),
+                    marks: active,
+                }),
+            }
+            last_was_block = is_block;
+        }
+        Ok(spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::marks::{ExpandMark, Mark};
+    use crate::transaction::Transactable;
+    use crate::{AutoCommit, ObjType, ReadDoc, ScalarValue, ROOT};
+
+    #[test]
+    fn overlapping_marks_split_into_three_spans() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "hello world").unwrap();
+        doc.mark(
+            &text,
+            Mark::new("bold".into(), true, 0, 7),
+            ExpandMark::None,
+        )
+        .unwrap();
+        doc.mark(
+            &text,
+            Mark::new("italic".into(), true, 3, 11),
+            ExpandMark::None,
+        )
+        .unwrap();
+
+        let spans = doc.text_spans(&text).unwrap();
+        let summary = spans
+            .iter()
+            .map(|s| (s.text.as_str(), s.marks.keys().cloned().collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("hel", vec!["bold".to_string()]),
+                ("lo w", vec!["bold".to_string(), "italic".to_string()]),
+                ("orld", vec!["italic".to_string()]),
+            ]
+        );
+        assert_eq!(spans[1].marks["bold"], ScalarValue::Boolean(true));
+    }
+
+    #[test]
+    fn embedded_objects_are_their_own_spans() {
+        let mut doc = AutoCommit::new();
+        let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+        doc.splice_text(&text, 0, 0, "ab").unwrap();
+        doc.insert_object(&text, 1, ObjType::Map).unwrap();
+        doc.insert_object(&text, 1, ObjType::Map).unwrap();
+
+        let spans = doc.text_spans(&text).unwrap();
+        let texts = spans.iter().map(|s| s.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["a", "\u{fffc}", "\u{fffc}", "b"]);
+        assert_eq!(texts.concat(), doc.text(&text).unwrap());
+        assert!(doc.text_spans(ROOT).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::exid::ExId;
+use crate::{Automerge, ObjType, Prop, ReadDoc, ScalarValue, Value, ROOT};
+
+/// Callbacks for each object and value in a document, see [`Automerge::walk`]
+///
+/// Every method does nothing by default, so a visitor only implements the ones it needs. Each
+/// map and list is bracketed by a call to [`Self::enter_map`] or [`Self::enter_list`] and a
+/// call to [`Self::leave_object`], with its values visited in between. Text objects are leaves,
+/// visited with a single call to [`Self::text`]. Tables are visited as maps.
+pub trait DocumentVisitor {
+    /// Start visiting the map `obj`, which is at `prop` of its parent or the root if `prop` is
+    /// `None`
+    fn enter_map(&mut self, _prop: Option<&Prop>, _obj: &ExId) {}
+
+    /// Start visiting the list `obj`, which is at `prop` of its parent
+    fn enter_list(&mut self, _prop: &Prop, _obj: &ExId) {}
+
+    /// Finish visiting the map or list entered most recently
+    fn leave_object(&mut self) {}
+
+    /// The scalar `value` created by the op `id` is at `prop` of the current object
+    fn scalar(&mut self, _prop: &Prop, _value: &ScalarValue, _id: &ExId) {}
+
+    /// The text object `obj`, which contains `text`, is at `prop` of the current object
+    fn text(&mut self, _prop: &Prop, _text: &str, _obj: &ExId) {}
+
+    /// `prop` of the current object has several conflicting `values`, as returned by
+    /// [`ReadDoc::get_all`]
+    ///
+    /// This is called before the winning value, the last of `values`, is visited as usual.
+    fn conflict(&mut self, _prop: &Prop, _values: &[(Value<'_>, ExId)]) {}
+}
+
+impl<V: DocumentVisitor + ?Sized> DocumentVisitor for &mut V {
+    fn enter_map(&mut self, prop: Option<&Prop>, obj: &ExId) {
+        (**self).enter_map(prop, obj)
+    }
+
+    fn enter_list(&mut self, prop: &Prop, obj: &ExId) {
+        (**self).enter_list(prop, obj)
+    }
+
+    fn leave_object(&mut self) {
+        (**self).leave_object()
+    }
+
+    fn scalar(&mut self, prop: &Prop, value: &ScalarValue, id: &ExId) {
+        (**self).scalar(prop, value, id)
+    }
+
+    fn text(&mut self, prop: &Prop, text: &str, obj: &ExId) {
+        (**self).text(prop, text, obj)
+    }
+
+    fn conflict(&mut self, prop: &Prop, values: &[(Value<'_>, ExId)]) {
+        (**self).conflict(prop, values)
+    }
+}
+
+impl Automerge {
+    /// Visit every object and value in the current state of the document with `visitor`
+    ///
+    /// The document is walked depth first from the root, with the keys of a map in order and the
+    /// elements of a list by index. Pass `&mut visitor` to keep the visitor once the walk is done.
+    pub fn walk<V: DocumentVisitor>(&self, mut visitor: V) {
+        visitor.enter_map(None, &ROOT);
+        self.walk_map(&mut visitor, &ROOT);
+        visitor.leave_object();
+    }
+
+    fn walk_map<V: DocumentVisitor>(&self, visitor: &mut V, obj: &ExId) {
+        for item in self.map_range(obj, ..) {
+            let prop = Prop::Map(item.key.to_string());
+            self.walk_value(visitor, obj, prop, item.value, item.id, item.conflict);
+        }
+    }
+
+    fn walk_list<V: DocumentVisitor>(&self, visitor: &mut V, obj: &ExId) {
+        for item in self.list_range(obj, ..) {
+            let prop = Prop::Seq(item.index);
+            self.walk_value(visitor, obj, prop, item.value, item.id, item.conflict);
+        }
+    }
+
+    fn walk_value<V: DocumentVisitor>(
+        &self,
+        visitor: &mut V,
+        obj: &ExId,
+        prop: Prop,
+        value: Value<'_>,
+        id: ExId,
+        conflict: bool,
+    ) {
+        // The objects visited come from the document so reading them can't fail
+        if conflict {
+            let values = self.get_all(obj, prop.clone()).unwrap_or_default();
+            visitor.conflict(&prop, &values);
+        }
+        match value {
+            Value::Scalar(value) => visitor.scalar(&prop, &value, &id),
+            Value::Object(ObjType::Map | ObjType::Table) => {
+                visitor.enter_map(Some(&prop), &id);
+                self.walk_map(visitor, &id);
+                visitor.leave_object();
+            }
+            Value::Object(ObjType::List) => {
+                visitor.enter_list(&prop, &id);
+                self.walk_list(visitor, &id);
+                visitor.leave_object();
+            }
+            Value::Object(ObjType::Text) => {
+                let text = self.text(&id).unwrap_or_default();
+                visitor.text(&prop, &text, &id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DocumentVisitor;
+    use crate::exid::ExId;
+    use crate::transaction::Transactable;
+    use crate::{ActorId, AutoCommit, ObjType, Prop, ReadDoc, ScalarValue, Value, ROOT};
+
+    /// Writes each callback as a line, indented by depth
+    #[derive(Default)]
+    struct Outline {
+        depth: usize,
+        lines: Vec<String>,
+    }
+
+    impl Outline {
+        fn line(&mut self, line: String) {
+            self.lines
+                .push(format!("{}{}", "  ".repeat(self.depth), line));
+        }
+    }
+
+    impl DocumentVisitor for Outline {
+        fn enter_map(&mut self, prop: Option<&Prop>, _obj: &ExId) {
+            self.line(format!("map {:?}", prop));
+            self.depth += 1;
+        }
+
+        fn enter_list(&mut self, prop: &Prop, _obj: &ExId) {
+            self.line(format!("list {}", prop));
+            self.depth += 1;
+        }
+
+        fn leave_object(&mut self) {
+            self.depth -= 1;
+        }
+
+        fn scalar(&mut self, prop: &Prop, value: &ScalarValue, _id: &ExId) {
+            self.line(format!("{} = {}", prop, value));
+        }
+
+        fn text(&mut self, prop: &Prop, text: &str, _obj: &ExId) {
+            self.line(format!("{} = text {:?}", prop, text));
+        }
+
+        fn conflict(&mut self, prop: &Prop, values: &[(Value<'_>, ExId)]) {
+            self.line(format!("{} has {} values", prop, values.len()));
+        }
+    }
+
+    #[test]
+    fn walk_visits_the_whole_document_depth_first() {
+        let mut doc = AutoCommit::new().with_actor(ActorId::from(&[1][..]));
+        let config = doc.put_object(ROOT, "config", ObjType::Map).unwrap();
+        doc.put(&config, "debug", true).unwrap();
+        let items = doc.put_object(ROOT, "items", ObjType::List).unwrap();
+        doc.insert(&items, 0, 1).unwrap();
+        let item = doc.insert_object(&items, 1, ObjType::Map).unwrap();
+        let title = doc.put_object(&item, "title", ObjType::Text).unwrap();
+        doc.splice_text(&title, 0, 0, "hello").unwrap();
+
+        let mut other = doc.fork().with_actor(ActorId::from(&[2][..]));
+        doc.put(ROOT, "name", "mine").unwrap();
+        other.put(ROOT, "name", "theirs").unwrap();
+        doc.merge(&mut other).unwrap();
+
+        let mut outline = Outline::default();
+        doc.document().walk(&mut outline);
+        assert_eq!(
+            outline.lines,
+            vec![
+                "map None",
+                "  map Some(Map(\"config\"))",
+                "    debug = true",
+                "  list items",
+                "    0 = 1",
+                "    map Some(Seq(1))",
+                "      title = text \"hello\"",
+                "  name has 2 values",
+                "  name = \"theirs\"",
+            ]
+        );
+        assert_eq!(outline.depth, 0);
+    }
+
+    #[test]
+    fn conflicts_are_reported_on_their_own_key() {
+        let mut doc = AutoCommit::new().with_actor(ActorId::from(&[1][..]));
+        let mut other = doc.fork().with_actor(ActorId::from(&[2][..]));
+        doc.put(ROOT, "a", 1).unwrap();
+        other.put(ROOT, "a", 2).unwrap();
+        doc.put(ROOT, "b", 3).unwrap();
+        doc.put(ROOT, "c", 4).unwrap();
+        other.put(ROOT, "c", 5).unwrap();
+        doc.put(ROOT, "d", 6).unwrap();
+        doc.merge(&mut other).unwrap();
+
+        let mut outline = Outline::default();
+        doc.document().walk(&mut outline);
+        assert_eq!(
+            outline.lines,
+            vec![
+                "map None",
+                "  a has 2 values",
+                "  a = 2",
+                "  b = 3",
+                "  c has 2 values",
+                "  c = 4",
+                "  d = 6",
+            ]
+        );
+        let conflicts = doc
+            .map_range(ROOT, ..)
+            .map(|item| (item.key, item.conflict))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            vec![("a", true), ("b", false), ("c", true), ("d", false)]
+        );
+    }
+}
@@ -2,11 +2,14 @@ use std::{borrow::Cow, num::NonZeroU64};
 
 use crate::{
     columnar::Key as StoredKey,
+    error::InvalidChange,
     storage::{
         change::{Unverified, Verified},
+        load::Budget,
         parse, Change as StoredChange, ChangeOp, Chunk, Compressed, ReadChangeOpError,
     },
-    types::{ActorId, ChangeHash, ElemId},
+    types::{ActorId, ChangeHash, ElemId, OpId, OpType},
+    ChangeOpId,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -29,9 +32,25 @@ impl Change {
     pub(crate) fn new_from_unverified(
         stored: StoredChange<'static, Unverified>,
         compressed: Option<Compressed<'static>>,
+    ) -> Result<Self, ReadChangeOpError> {
+        Self::new_from_unverified_with_budget(stored, compressed, &mut Budget::unlimited())
+    }
+
+    /// Verify the ops in `stored`, stopping as soon as they exhaust `budget`
+    pub(crate) fn new_from_unverified_with_budget(
+        stored: StoredChange<'static, Unverified>,
+        compressed: Option<Compressed<'static>>,
+        budget: &mut Budget,
     ) -> Result<Self, ReadChangeOpError> {
         let mut len = 0;
-        let stored = stored.verify_ops(|_| len += 1)?;
+        let stored = stored.verify_ops(|op| {
+            len += 1;
+            budget.spend_op()?;
+            if op.is_make() {
+                budget.spend_object()?;
+            }
+            Ok(())
+        })?;
         let compression = if let Some(c) = compressed {
             CompressionState::Compressed(c)
         } else {
@@ -44,14 +63,17 @@ impl Change {
         })
     }
 
+    /// The actor which made this change
     pub fn actor_id(&self) -> &ActorId {
         self.stored.actor()
     }
 
+    /// The actors, other than [`Self::actor_id`], referenced by the ops in this change
     pub fn other_actor_ids(&self) -> &[ActorId] {
         self.stored.other_actors()
     }
 
+    /// The number of ops in this change
     pub fn len(&self) -> usize {
         self.len
     }
@@ -60,30 +82,37 @@ impl Change {
         self.len == 0
     }
 
+    /// The counter of the last op in this change
     pub fn max_op(&self) -> u64 {
         self.stored.start_op().get() + (self.len as u64) - 1
     }
 
+    /// The counter of the first op in this change
     pub fn start_op(&self) -> NonZeroU64 {
         self.stored.start_op()
     }
 
+    /// The message the change was committed with, if any
     pub fn message(&self) -> Option<&String> {
         self.stored.message().as_ref()
     }
 
+    /// The hashes of the changes this change depends on
     pub fn deps(&self) -> &[ChangeHash] {
         self.stored.dependencies()
     }
 
+    /// The hash of this change
     pub fn hash(&self) -> ChangeHash {
         self.stored.hash()
     }
 
+    /// The position of this change in the changes made by [`Self::actor_id`], starting at 1
     pub fn seq(&self) -> u64 {
         self.stored.seq()
     }
 
+    /// The time the change was committed with, as given to [`crate::transaction::CommitOptions`]
     pub fn timestamp(&self) -> i64 {
         self.stored.timestamp()
     }
@@ -104,6 +133,19 @@ impl Change {
         }
     }
 
+    /// Like [`Self::bytes`] but without caching the compressed bytes, so this will compress the
+    /// change every time it is called on a change which has not already been compressed
+    pub(crate) fn compressed_bytes(&self) -> Cow<'_, [u8]> {
+        match &self.compression {
+            CompressionState::Compressed(c) => c.bytes(),
+            CompressionState::TooSmallToCompress => Cow::Borrowed(self.stored.bytes()),
+            CompressionState::NotCompressed => match self.stored.compress() {
+                Some(compressed) => Cow::Owned(compressed.bytes().into_owned()),
+                None => Cow::Borrowed(self.stored.bytes()),
+            },
+        }
+    }
+
     pub fn raw_bytes(&self) -> &[u8] {
         self.stored.bytes()
     }
@@ -112,6 +154,46 @@ impl Change {
         self.stored.iter_ops()
     }
 
+    /// The operations in this change, in the order they were made
+    ///
+    /// This decodes each operation as it is produced, [`Self::decode`] decodes all of them along
+    /// with the rest of the change at once.
+    pub fn ops(&self) -> impl Iterator<Item = OpDetails> + '_ {
+        let actors = std::iter::once(self.actor_id())
+            .chain(self.other_actor_ids().iter())
+            .collect::<Vec<_>>();
+        let op_id = move |id: OpId| ChangeOpId {
+            counter: id.counter(),
+            actor: actors[id.actor()].clone(),
+        };
+        self.iter_ops()
+            .zip(self.start_op().get()..)
+            .map(move |(o, counter)| {
+                let key = match o.key {
+                    StoredKey::Elem(e) if e.is_head() => OpKey::Seq(None),
+                    StoredKey::Elem(ElemId(id)) => OpKey::Seq(Some(op_id(id))),
+                    StoredKey::Prop(p) => OpKey::Map(p.to_string()),
+                };
+                OpDetails {
+                    id: ChangeOpId {
+                        counter,
+                        actor: self.actor_id().clone(),
+                    },
+                    obj: if o.obj.is_root() {
+                        None
+                    } else {
+                        Some(op_id(*o.obj.opid()))
+                    },
+                    key,
+                    insert: o.insert,
+                    action: OpType::from_action_and_value(o.action, o.val, o.mark_name, o.expand),
+                    pred: o.pred.into_iter().map(&op_id).collect(),
+                }
+            })
+    }
+
+    /// Any bytes following the ops in the change, which this version of automerge doesn't
+    /// understand
     pub fn extra_bytes(&self) -> &[u8] {
         self.stored.extra_bytes()
     }
@@ -121,11 +203,64 @@ impl Change {
         Self::try_from(&bytes[..])
     }
 
+    /// Decode the whole change, including its ops
     pub fn decode(&self) -> crate::ExpandedChange {
         crate::ExpandedChange::from(self)
     }
 }
 
+/// An operation in a [`Change`], as produced by [`Change::ops`]
+///
+/// Objects and list elements are identified by the ID of the operation which created them. A
+/// change on its own doesn't say where those are in the document, use
+/// [`crate::ReadDoc::parents`] with a document containing the change to find the path to an
+/// object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpDetails {
+    /// The ID of this operation
+    pub id: ChangeOpId,
+    /// The object this operation modifies, `None` for the root map
+    pub obj: Option<ChangeOpId>,
+    /// The property within `obj` this operation modifies
+    pub key: OpKey,
+    /// Whether this operation inserts a new element after `key` in a sequence
+    pub insert: bool,
+    /// What the operation does, including the value it puts if any
+    pub action: OpType,
+    /// The operations this operation overwrites
+    pub pred: Vec<ChangeOpId>,
+}
+
+/// The property an [`OpDetails`] modifies
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OpKey {
+    /// A key in a map
+    Map(String),
+    /// An element of a sequence, identified by the ID of the operation which inserted it. `None`
+    /// is the start of the sequence, which is only used by inserts.
+    Seq(Option<ChangeOpId>),
+}
+
+/// A change in the dependency graph of a document, see [`crate::Automerge::change_graph`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangeNode {
+    pub hash: ChangeHash,
+    pub deps: Vec<ChangeHash>,
+    pub actor: ActorId,
+    pub seq: u64,
+}
+
+impl From<&Change> for ChangeNode {
+    fn from(c: &Change) -> Self {
+        ChangeNode {
+            hash: c.hash(),
+            deps: c.deps().to_vec(),
+            actor: c.actor_id().clone(),
+            seq: c.seq(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 enum CompressionState {
     /// We haven't tried to compress this change
@@ -179,6 +314,27 @@ impl<'a> TryFrom<&'a [u8]> for Change {
     }
 }
 
+/// Check that the checksum embedded in an encoded change matches the hash of its contents
+///
+/// This accepts both compressed and uncompressed changes (e.g. the output of
+/// [`Change::raw_bytes`]) and returns the hash of the change if it is valid. Use this to reject
+/// changes received over an untrusted channel before applying them to a document.
+pub fn verify_change_hash(raw_change_bytes: &[u8]) -> Result<ChangeHash, InvalidChange> {
+    let input = parse::Input::new(raw_change_bytes);
+    let (remaining, chunk) = Chunk::parse(input).map_err(|e| InvalidChange::Parse(Box::new(e)))?;
+    if !remaining.is_empty() {
+        return Err(InvalidChange::LeftoverData);
+    }
+    if !chunk.checksum_valid() {
+        return Err(InvalidChange::BadChecksum);
+    }
+    match chunk {
+        Chunk::Change(c) => Ok(c.hash()),
+        Chunk::CompressedChange(c, _) => Ok(c.hash()),
+        Chunk::Document(_) => Err(InvalidChange::WrongChunkType),
+    }
+}
+
 impl<'a> TryFrom<StoredChange<'a, Unverified>> for Change {
     type Error = ReadChangeOpError;
 
@@ -1,7 +1,7 @@
 use crate::storage::load::Error as LoadError;
 use crate::types::{ActorId, ScalarValue};
 use crate::value::DataType;
-use crate::{ChangeHash, Cursor, LoadChangeError, ObjType, PatchAction};
+use crate::{ChangeHash, Cursor, LoadChangeError, ObjType, PatchAction, Prop};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,12 +26,24 @@ pub enum AutomergeError {
     InvalidHash(ChangeHash),
     #[error("index {0} is out of bounds")]
     InvalidIndex(usize),
+    #[cfg(feature = "json-patch")]
+    #[error("{0}")]
+    InvalidJsonPatch(String),
+    #[cfg(feature = "json")]
+    #[error("invalid typed JSON: {0}")]
+    InvalidTypedJson(String),
+    #[cfg(feature = "msgpack")]
+    #[error("invalid MessagePack: {0}")]
+    InvalidMsgpack(String),
     #[error("invalid obj id `{0}`")]
     InvalidObjId(String),
     #[error("invalid obj id format `{0}`")]
     InvalidObjIdFormat(String),
     #[error("invalid op for object of type `{0}`")]
     InvalidOp(ObjType),
+    #[cfg(feature = "json")]
+    #[error("invalid Quill delta: {0}")]
+    InvalidQuillDelta(String),
     #[error("seq {0} is out of bounds")]
     InvalidSeq(u64),
     #[error("cursor {0} is invalid")]
@@ -44,21 +56,39 @@ pub enum AutomergeError {
         unexpected: String,
     },
     #[error(transparent)]
-    Load(#[from] LoadError),
+    Load(LoadError),
     #[error(transparent)]
     LoadChangeError(#[from] LoadChangeError),
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
     #[error("increment operations must be against a counter value")]
     MissingCounter,
     #[error("hash {0} does not correspond to a change in this document")]
     MissingHash(ChangeHash),
     #[error("change's deps should already be in the document")]
     MissingDeps,
+    #[error("there is no value at `{0}`")]
+    MissingProp(Prop),
     #[error("compressed chunk was not a change")]
     NonChangeCompressed,
     #[error("id was not an object id")]
     NotAnObject,
     #[error(transparent)]
     HydrateError(#[from] HydrateError),
+    #[cfg(feature = "json-patch")]
+    #[error("JSON Patch `{0}` operations are not supported")]
+    UnsupportedJsonPatchOp(&'static str),
+    #[error("failed to write document: {0}")]
+    Write(#[source] std::io::Error),
+}
+
+impl From<LoadError> for AutomergeError {
+    fn from(e: LoadError) -> Self {
+        match e {
+            LoadError::LimitExceeded(l) => AutomergeError::LimitExceeded(l),
+            e => AutomergeError::Load(e),
+        }
+    }
 }
 
 impl PartialEq for AutomergeError {
@@ -74,6 +104,29 @@ impl From<AutomergeError> for wasm_bindgen::JsValue {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum InvalidChange {
+    #[error("unable to parse change: {0}")]
+    Parse(Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("leftover data after parsing")]
+    LeftoverData,
+    #[error("wrong chunk type")]
+    WrongChunkType,
+    #[error("checksum does not match the contents of the change")]
+    BadChecksum,
+}
+
+/// A limit set in [`crate::LoadLimits`] was exceeded whilst loading a document
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    #[error("document contains more than {0} ops")]
+    Ops(usize),
+    #[error("document contains more than {0} objects")]
+    Objects(usize),
+    #[error("document decompresses to more than {0} bytes")]
+    Bytes(usize),
+}
+
 #[derive(Error, Debug)]
 #[error("Invalid actor ID: {0}")]
 pub struct InvalidActorId(pub String);
@@ -68,6 +68,17 @@ impl ExId {
         }
     }
 
+    /// The ID of the operation this ID refers to, or `None` for the root object
+    pub fn op_id(&self) -> Option<ChangeOpId> {
+        match self {
+            ExId::Root => None,
+            ExId::Id(counter, actor, _) => Some(ChangeOpId {
+                counter: *counter,
+                actor: actor.clone(),
+            }),
+        }
+    }
+
     pub(crate) fn to_internal_obj(&self) -> ObjId {
         match self {
             ExId::Root => ObjId::root(),
@@ -76,6 +87,23 @@ impl ExId {
     }
 }
 
+/// The identity of an operation, its lamport counter and the actor which created it
+///
+/// Unlike [`ExId`] this carries no index into the actor table of a particular document, so it is
+/// suitable for tooling which works with ops from many documents. The ordering compares the
+/// counter first and then the actor, which is the order automerge uses to resolve conflicts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChangeOpId {
+    pub counter: u64,
+    pub actor: ActorId,
+}
+
+impl fmt::Display for ChangeOpId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.counter, self.actor)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ObjIdFromBytesError {
     #[error("no version tag")]
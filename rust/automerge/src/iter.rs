@@ -1,12 +1,16 @@
+mod changes;
 mod keys;
 mod list_range;
 mod map_range;
 mod top_ops;
 mod values;
+mod words;
 
+pub use changes::TopologicalChanges;
 pub use keys::Keys;
 pub use list_range::{ListRange, ListRangeItem};
 pub use map_range::{MapRange, MapRangeItem};
 pub use values::Values;
+pub use words::Words;
 
 pub(crate) use top_ops::{TopOp, TopOps};
@@ -0,0 +1,78 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+
+use crate::{Change, ChangeHash};
+
+/// Iterator created by the [`crate::Automerge::iter_changes_topological()`] method
+///
+/// This is Kahn's algorithm. Building the iterator counts the unprocessed dependencies of every
+/// change, after that each call to `next` pops the ready change with the lowest hash and releases
+/// its children. Ties are broken by hash so that every document containing the same set of changes
+/// produces them in the same order.
+pub struct TopologicalChanges<'a> {
+    history: &'a [Change],
+    children: Vec<Vec<usize>>,
+    pending_deps: Vec<usize>,
+    ready: BinaryHeap<Reverse<(ChangeHash, usize)>>,
+}
+
+impl<'a> TopologicalChanges<'a> {
+    pub(crate) fn new(history: &'a [Change], history_index: &HashMap<ChangeHash, usize>) -> Self {
+        let mut children = vec![Vec::new(); history.len()];
+        let mut pending_deps = vec![0; history.len()];
+        let mut ready = BinaryHeap::new();
+        for (index, change) in history.iter().enumerate() {
+            for dep in change.deps() {
+                if let Some(dep_index) = history_index.get(dep) {
+                    children[*dep_index].push(index);
+                    pending_deps[index] += 1;
+                }
+            }
+            if pending_deps[index] == 0 {
+                ready.push(Reverse((change.hash(), index)));
+            }
+        }
+        Self {
+            history,
+            children,
+            pending_deps,
+            ready,
+        }
+    }
+}
+
+impl<'a> fmt::Debug for TopologicalChanges<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopologicalChanges")
+            .field("remaining", &self.len())
+            .finish()
+    }
+}
+
+impl<'a> Iterator for TopologicalChanges<'a> {
+    type Item = &'a Change;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, index)) = self.ready.pop()?;
+        for child in std::mem::take(&mut self.children[index]) {
+            self.pending_deps[child] -= 1;
+            if self.pending_deps[child] == 0 {
+                let hash = self.history[child].hash();
+                self.ready.push(Reverse((hash, child)));
+            }
+        }
+        Some(&self.history[index])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len();
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for TopologicalChanges<'a> {
+    fn len(&self) -> usize {
+        self.ready.len() + self.pending_deps.iter().filter(|p| **p > 0).count()
+    }
+}
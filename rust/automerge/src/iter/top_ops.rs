@@ -49,7 +49,9 @@ impl<'a> Iterator for TopOps<'a> {
                         }
                     }
                     Some(_) => {
-                        result_op = self.last_op.take().map(|(_op_pos, op)| op);
+                        // Take the conflict flag before `num_ops` starts counting the next key
+                        let conflict = self.num_ops > 1;
+                        result_op = self.last_op.take().map(|(_op_pos, op)| (op, conflict));
                         if visible {
                             self.last_op = Some((self.pos, op));
                             self.num_ops = 1;
@@ -75,13 +77,11 @@ impl<'a> Iterator for TopOps<'a> {
                     break;
                 }
             } else {
-                result_op = self.last_op.take().map(|(_op_pos, op)| op);
+                let conflict = self.num_ops > 1;
+                result_op = self.last_op.take().map(|(_op_pos, op)| (op, conflict));
                 break;
             }
         }
-        result_op.map(|op| TopOp {
-            op,
-            conflict: self.num_ops > 1,
-        })
+        result_op.map(|(op, conflict)| TopOp { op, conflict })
     }
 }
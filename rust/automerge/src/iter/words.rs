@@ -0,0 +1,84 @@
+use unicode_segmentation::{UWordBoundIndices, UnicodeSegmentation};
+
+/// Iterator over the words of a string, created by [`crate::Automerge::text_words`]
+///
+/// Yields `(index, word)` pairs where `index` is the number of characters before the word. The
+/// string is split with the Unicode word boundary rules of [UAX #29] and the segments containing
+/// a letter or digit are the words, so "don't", "3,000.5" and "naïve_case" are single words and a
+/// combining accent stays with the letter it follows. Scripts written without spaces, such as
+/// Chinese or Thai, are split into single characters rather than dictionary words.
+///
+/// [UAX #29]: https://www.unicode.org/reports/tr29/#Word_Boundaries
+#[derive(Debug, Clone)]
+pub struct Words<'a> {
+    text: &'a str,
+    bounds: UWordBoundIndices<'a>,
+    /// The byte offset of the character `index` counts up to
+    byte: usize,
+    /// The number of characters before `byte`
+    index: usize,
+}
+
+impl<'a> Words<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Words {
+            text,
+            bounds: text.split_word_bound_indices(),
+            byte: 0,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (byte, segment) in self.bounds.by_ref() {
+            self.index += self.text[self.byte..byte].chars().count();
+            self.byte = byte;
+            if segment.chars().any(char::is_alphanumeric) {
+                return Some((self.index, segment));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Words;
+
+    #[test]
+    fn splits_on_spaces_and_punctuation() {
+        let words = Words::new("  Hello, world! It's 3,000.5 e.g. naïve_case ").collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            vec![
+                (2, "Hello"),
+                (9, "world"),
+                (16, "It's"),
+                (21, "3,000.5"),
+                (29, "e.g"),
+                (34, "naïve_case"),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_joiners_are_not_part_of_words() {
+        let words = Words::new("rock'n'roll' 1. 'quoted'").collect::<Vec<_>>();
+        assert_eq!(words, vec![(0, "rock'n'roll"), (13, "1"), (17, "quoted")]);
+        assert_eq!(Words::new(" ,. ").next(), None);
+    }
+
+    #[test]
+    fn combining_marks_stay_with_their_letter() {
+        let words =
+            Words::new("cafe\u{301} au lait, \u{1F469}\u{200D}\u{1F4BB} codes").collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            vec![(0, "cafe\u{301}"), (6, "au"), (9, "lait"), (19, "codes")]
+        );
+    }
+}
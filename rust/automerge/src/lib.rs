@@ -107,6 +107,28 @@
 //! this you can use [`AutoSerde`], which implements `serde::Serialize` for an
 //! automerge document.
 //!
+//! With the `serde-document` feature enabled [`Automerge`] itself implements `serde::Serialize`
+//! and `serde::Deserialize` using a JSON friendly representation of the current value of the
+//! document which preserves the type of every value, so a document can be written to JSON and
+//! read back without losing counters, timestamps, text objects and so on.
+//!
+//! With the `json-patch` feature enabled [`Automerge::diff_as_json_patch`] describes the
+//! difference between two sets of heads as an RFC 6902 JSON Patch made of [`JsonPatchOp`]s, and
+//! [`Automerge::apply_json_patch`] applies such a patch to a document.
+//!
+//! With the `json` feature enabled [`Automerge::import_json`] creates a document from an
+//! existing JSON object and [`Automerge::export_json`] converts the current state of a document
+//! to JSON. [`Automerge::to_json_typed`] and [`Automerge::from_json_typed`] convert to and from a
+//! JSON representation which tags counters, timestamps, bytes and numbers with their type.
+//! [`Automerge::text_apply_quill_delta`] applies a Quill Delta from a rich text editor to a text
+//! object and [`Automerge::text_to_quill_delta`] converts a text object to one.
+//!
+//! With the `cbor` feature enabled [`Automerge::export_cbor`] encodes the same structure as
+//! [`Automerge::export_json`] as CBOR, with bytes as byte strings and timestamps as CBOR dates.
+//!
+//! With the `msgpack` feature enabled [`Automerge::to_msgpack`] encodes the same structure as
+//! MessagePack, keeping the type of every scalar, and [`Automerge::from_msgpack`] reads it back.
+//!
 //! ## Example
 //!
 //! Let's create a document representing an address book.
@@ -280,20 +302,30 @@ mod value;
 #[cfg(feature = "optree-visualisation")]
 mod visualisation;
 
-pub use crate::automerge::{Automerge, OnPartialLoad, SaveOptions};
+#[cfg(feature = "json-patch")]
+pub use crate::automerge::JsonPatchOp;
+pub use crate::automerge::{
+    ApplyReport, Automerge, DiffCursor, DocumentVisitor, ListDiffEntry, MapDiffEntry,
+    OnPartialLoad, SaveOptions, StateVector,
+};
 pub use autocommit::AutoCommit;
 pub use autoserde::AutoSerde;
-pub use change::{Change, LoadError as LoadChangeError};
+pub use change::{
+    verify_change_hash, Change, ChangeNode, LoadError as LoadChangeError, OpDetails, OpKey,
+};
 pub use cursor::Cursor;
 pub use error::AutomergeError;
 pub use error::InvalidActorId;
+pub use error::InvalidChange;
 pub use error::InvalidChangeHashSlice;
-pub use exid::{ExId as ObjId, ObjIdFromBytesError};
+pub use error::LimitExceeded;
+pub use exid::{ChangeOpId, ExId as ObjId, ObjIdFromBytesError};
 pub use legacy::Change as ExpandedChange;
 pub use parents::{Parent, Parents};
 pub use patches::{Patch, PatchAction, PatchLog};
 pub use read::ReadDoc;
 pub use sequence_tree::SequenceTree;
+pub use storage::LoadLimits;
 pub use types::{ActorId, ChangeHash, ObjType, OpType, ParseChangeHashError, Prop};
 pub use value::{ScalarValue, Value};
 
@@ -6,6 +6,7 @@ use crate::types::OpId;
 use crate::value::ScalarValue;
 use crate::Automerge;
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 
 /// Marks let you store out-of-bound information about sequences.
 ///
@@ -158,6 +159,18 @@ impl<'a> MarkStateMachine<'a> {
         result
     }
 
+    /// The winning value of each mark open at the current position, leaving out unmarks
+    pub(crate) fn current(&self) -> HashMap<SmolStr, ScalarValue> {
+        let mut current = HashMap::new();
+        for (_, mark) in self.state.iter().rev() {
+            current
+                .entry(mark.data.name.clone())
+                .or_insert_with(|| mark.value().clone());
+        }
+        current.retain(|_, value| !value.is_null());
+        current
+    }
+
     fn find(&self, target: OpId, doc: &Automerge) -> Result<usize, usize> {
         let metadata = &doc.ops().m;
         self.state
@@ -238,3 +251,11 @@ impl ExpandMark {
         matches!(self, Self::After | Self::Both)
     }
 }
+
+/// A run of text in which every character has the same marks, see
+/// [`crate::Automerge::text_spans`]
+#[derive(PartialEq, Debug, Clone)]
+pub struct Span {
+    pub text: String,
+    pub marks: BTreeMap<String, ScalarValue>,
+}
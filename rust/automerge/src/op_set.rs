@@ -137,6 +137,19 @@ impl OpSetInternal {
             .unwrap_or_default()
     }
 
+    /// Every op for `key` in the map `obj`, visible or not, in lamport order
+    pub(crate) fn ops_for_map_key<'a>(
+        &'a self,
+        obj: &ObjId,
+        key: String,
+    ) -> impl Iterator<Item = &'a Op> {
+        self.trees
+            .get(obj)
+            .map(|tree| tree.internal.ops_for_map_key(&self.m, key))
+            .into_iter()
+            .flatten()
+    }
+
     pub(crate) fn top_ops<'a>(&'a self, obj: &ObjId, clock: Option<Clock>) -> TopOps<'a> {
         self.trees
             .get(obj)
@@ -305,9 +318,17 @@ impl OpSetInternal {
     }
 
     pub(crate) fn text(&self, obj: &ObjId, clock: Option<Clock>) -> String {
-        self.top_ops(obj, clock)
-            .map(|top| top.op.to_str())
-            .collect()
+        let mut text = String::new();
+        self.text_into(obj, clock, &mut text);
+        text
+    }
+
+    /// Replace the contents of `buf` with the text of `obj`
+    pub(crate) fn text_into(&self, obj: &ObjId, clock: Option<Clock>, buf: &mut String) {
+        buf.clear();
+        for top in self.top_ops(obj, clock) {
+            buf.push_str(top.op.to_str());
+        }
     }
 
     pub(crate) fn keys<'a>(&'a self, obj: &ObjId, clock: Option<Clock>) -> Keys<'a> {
@@ -364,6 +364,19 @@ impl OpTreeInternal {
         }
     }
 
+    /// Every op for `key` in this map, visible or not, in lamport order
+    pub(crate) fn ops_for_map_key<'a>(
+        &'a self,
+        meta: &OpSetMetadata,
+        key: String,
+    ) -> impl Iterator<Item = &'a Op> {
+        let key = meta.props.lookup(&key).map(Key::Map);
+        let pos = key.map(|key| self.binary_search_by(|op| meta.key_cmp(&op.key, &key)));
+        self.iter()
+            .skip(pos.unwrap_or(self.len()))
+            .take_while(move |op| Some(op.key) == key)
+    }
+
     fn binary_search_by<F>(&self, f: F) -> usize
     where
         F: Fn(&Op) -> Ordering,
@@ -144,6 +144,21 @@ impl PatchLog {
         self.active
     }
 
+    /// The number of events recorded so far
+    pub(crate) fn num_events(&self) -> usize {
+        self.events.len()
+    }
+
+    /// The events recorded after the first `start`
+    pub(crate) fn events_since(&self, start: usize) -> &[(ObjId, Event)] {
+        &self.events[start..]
+    }
+
+    /// Forget every event after the first `len`
+    pub(crate) fn truncate_events(&mut self, len: usize) {
+        self.events.truncate(len);
+    }
+
     pub(crate) fn delete(&mut self, obj: ObjId, prop: &Prop) {
         match prop {
             Prop::Map(key) => self.delete_map(obj, key),
@@ -414,6 +429,7 @@ impl PatchLog {
 
     pub(crate) fn merge(&mut self, other: Self) {
         self.events.extend(other.events);
+        self.expose.extend(other.expose);
     }
 
     pub(crate) fn text_rep(&self) -> TextRepresentation {
@@ -44,6 +44,9 @@ pub trait ReadDoc {
     ///
     /// For a map this returns the keys of the map.
     /// For a list this returns the element ids (opids) encoded as strings.
+    ///
+    /// The values corresponding to these keys, in the same order, are returned by
+    /// [`Self::values`].
     fn keys<O: AsRef<ExId>>(&self, obj: O) -> Keys<'_>;
 
     /// Get the keys of the object `obj` as at `heads`
@@ -105,7 +108,11 @@ pub trait ReadDoc {
     /// Iterate over the values in a map, list, or text object
     ///
     /// The returned iterator yields `(value, exid)` tuples, where the second element
-    /// is the ID of the operation which created the value.
+    /// is the ID of the operation which created the value. For a map the values are in the same
+    /// order as the keys returned by [`Self::keys`].
+    ///
+    /// If the given object is not in this document the iterator will be empty, use
+    /// [`Self::object_type`] first if you need to distinguish this from an empty object.
     fn values<O: AsRef<ExId>>(&self, obj: O) -> Values<'_>;
 
     /// Iterate over the values in a map, list, or text object as at `heads`
@@ -211,7 +218,9 @@ pub trait ReadDoc {
     ///
     /// If there are multiple conflicting values for a given key this method
     /// will return all of them, with each value tagged by the ID of the
-    /// operation which created it.
+    /// operation which created it. The values are sorted by the ID of the operation, so the
+    /// value which wins the conflict, the one returned by [`Self::get`], is last. A key with a
+    /// single value gives a single element and a key with no value an empty vector.
     fn get_all<O: AsRef<ExId>, P: Into<Prop>>(
         &self,
         obj: O,
@@ -220,7 +229,8 @@ pub trait ReadDoc {
 
     /// Get all possibly conflicting values for a key as at `heads`
     ///
-    /// See `[Self::get_all]`
+    /// These are the values which were visible at `heads`, even if the conflict has been resolved
+    /// since, in the same order as [`Self::get_all`]. See `[Self::get_all]`
     fn get_all_at<O: AsRef<ExId>, P: Into<Prop>>(
         &self,
         obj: O,
@@ -234,4 +244,54 @@ pub trait ReadDoc {
 
     /// Get a change by its hash.
     fn get_change_by_hash(&self, hash: &ChangeHash) -> Option<&Change>;
+
+    /// The message of the change with hash `hash`
+    ///
+    /// Returns `None` if the change is not in this document or was committed without a message.
+    fn change_message(&self, hash: &ChangeHash) -> Option<&str> {
+        self.get_change_by_hash(hash)?.message().map(|m| m.as_str())
+    }
+
+    /// The timestamp of the change with hash `hash`, or `None` if the change is not in this
+    /// document
+    fn change_timestamp(&self, hash: &ChangeHash) -> Option<i64> {
+        self.get_change_by_hash(hash).map(|c| c.timestamp())
+    }
+
+    /// Count the properties in the document which have conflicting values
+    ///
+    /// A property is conflicted if [`Self::get_all`] would return more than one value for it. This
+    /// counts map keys and sequence indices in every object in the document and takes time
+    /// proportional to the number of operations in the document.
+    ///
+    /// The default implementation walks every object reachable from the root, including the
+    /// values which lost a conflict, using the other methods of this trait.
+    fn count_conflicts(&self) -> usize {
+        let mut conflicts = 0;
+        let mut objs = vec![crate::ROOT];
+        while let Some(obj) = objs.pop() {
+            let props = match self.object_type(&obj) {
+                Ok(ObjType::Map | ObjType::Table) => {
+                    self.keys(&obj).map(Prop::Map).collect::<Vec<_>>()
+                }
+                Ok(ObjType::List | ObjType::Text) => {
+                    (0..self.length(&obj)).map(Prop::Seq).collect()
+                }
+                Err(_) => Vec::new(),
+            };
+            for prop in props {
+                let values = self.get_all(&obj, prop).unwrap_or_default();
+                if values.len() > 1 {
+                    conflicts += 1;
+                }
+                objs.extend(
+                    values
+                        .into_iter()
+                        .filter(|(value, _)| value.is_object())
+                        .map(|(_, id)| id),
+                );
+            }
+        }
+        conflicts
+    }
 }
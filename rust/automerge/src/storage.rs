@@ -9,6 +9,7 @@ pub(crate) mod load;
 pub(crate) mod parse;
 pub(crate) mod save;
 
+pub use load::LoadLimits;
 pub(crate) use {
     change::{AsChangeOp, Change, ChangeOp, Compressed, ReadChangeOpError},
     chunk::{CheckSum, Chunk, ChunkType, Header},
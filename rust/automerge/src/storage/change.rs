@@ -166,16 +166,18 @@ impl<'a> Change<'a, Unverified> {
     /// Verify all the ops in this change executing `f` for each one
     ///
     /// `f` will be called for each op in this change, allowing callers to collect additional
-    /// information about the ops (e.g. all the actor IDs in the change, or the number of ops)
+    /// information about the ops (e.g. all the actor IDs in the change, or the number of ops).
+    /// Verification stops at the first error returned by `f`.
     ///
     /// # Errors
     /// * If there is an error reading an operation
-    pub(crate) fn verify_ops<F: FnMut(ChangeOp)>(
+    /// * If `f` returns an error
+    pub(crate) fn verify_ops<F: FnMut(ChangeOp) -> Result<(), ReadChangeOpError>>(
         self,
         mut f: F,
     ) -> Result<Change<'a, Verified>, ReadChangeOpError> {
         for op in self.iter_ops() {
-            f(op?);
+            f(op?)?;
         }
         if u32::try_from(u64::from(self.start_op)).is_err() {
             return Err(ReadChangeOpError::CounterTooLarge);
@@ -47,6 +47,13 @@ pub(crate) struct ChangeOp {
     pub(crate) mark_name: Option<smol_str::SmolStr>,
 }
 
+impl ChangeOp {
+    /// Whether this op creates an object, see [`crate::types::OpType::action_index`]
+    pub(crate) fn is_make(&self) -> bool {
+        matches!(self.action, 0 | 2 | 4 | 6)
+    }
+}
+
 impl<'a, A: AsChangeOp<'a, ActorId = usize, OpId = OpId>> From<A> for ChangeOp {
     fn from(a: A) -> Self {
         ChangeOp {
@@ -338,6 +345,8 @@ pub enum ReadChangeOpError {
     InvalidOpType(#[from] InvalidOpType),
     #[error("counter too large")]
     CounterTooLarge,
+    #[error(transparent)]
+    LimitExceeded(#[from] crate::error::LimitExceeded),
 }
 
 #[derive(Clone)]
@@ -34,6 +34,22 @@ pub(crate) mod error {
         Document(#[from] document::ParseError),
         #[error("unable to decompresse compressed chunk")]
         Deflate,
+        #[error("decompressed chunk was larger than allowed")]
+        InflateLimit,
+    }
+
+    impl Chunk {
+        /// Whether parsing stopped because decompressing the chunk would have produced more data
+        /// than allowed
+        pub(crate) fn is_inflate_limit(&self) -> bool {
+            matches!(
+                self,
+                Self::InflateLimit
+                    | Self::Document(document::ParseError::RawColumns(
+                        crate::storage::columns::raw_column::ParseError::InflateLimit
+                    ))
+            )
+        }
     }
 
     #[derive(thiserror::Error, Debug)]
@@ -50,6 +66,15 @@ pub(crate) mod error {
 impl<'a> Chunk<'a> {
     pub(crate) fn parse(
         input: parse::Input<'a>,
+    ) -> parse::ParseResult<'a, Chunk<'a>, error::Chunk> {
+        Self::parse_with_inflate_limit(input, usize::MAX)
+    }
+
+    /// Parse a chunk, failing with [`error::Chunk::is_inflate_limit`] if decompressing any part of
+    /// the chunk would produce more than `max_inflated_len` bytes
+    pub(crate) fn parse_with_inflate_limit(
+        input: parse::Input<'a>,
+        max_inflated_len: usize,
     ) -> parse::ParseResult<'a, Chunk<'a>, error::Chunk> {
         let (i, header) = Header::parse::<error::Chunk>(input)?;
         let parse::Split {
@@ -68,7 +93,7 @@ impl<'a> Chunk<'a> {
             }
             ChunkType::Document => {
                 let (remaining, doc) =
-                    Document::parse(chunk_input, header).map_err(|e| e.lift())?;
+                    Document::parse(chunk_input, header, max_inflated_len).map_err(|e| e.lift())?;
                 if !remaining.is_empty() {
                     return Err(parse::ParseError::Error(error::Chunk::LeftoverData));
                 }
@@ -76,11 +101,15 @@ impl<'a> Chunk<'a> {
             }
             ChunkType::Compressed => {
                 let compressed = &input.unconsumed_bytes()[header.data_bytes()];
-                let mut decoder = flate2::bufread::DeflateDecoder::new(compressed);
+                let decoder = flate2::bufread::DeflateDecoder::new(compressed);
                 let mut decompressed = Vec::new();
                 decoder
+                    .take((max_inflated_len as u64).saturating_add(1))
                     .read_to_end(&mut decompressed)
                     .map_err(|_| parse::ParseError::Error(error::Chunk::Deflate))?;
+                if decompressed.len() > max_inflated_len {
+                    return Err(parse::ParseError::Error(error::Chunk::InflateLimit));
+                }
                 let inner_header = header.with_data(ChunkType::Change, &decompressed);
                 let mut inner_chunk = Vec::with_capacity(inner_header.len() + decompressed.len());
                 inner_header.write(&mut inner_chunk);
@@ -179,15 +208,22 @@ pub(crate) struct Header {
 
 impl Header {
     pub(crate) fn new(chunk_type: ChunkType, data: &[u8]) -> Self {
-        let hash = hash(chunk_type, data);
+        Self::for_parts(chunk_type, &[data])
+    }
+
+    /// The header of a chunk whose data is `parts` laid end to end, without copying them into
+    /// one buffer
+    pub(crate) fn for_parts(chunk_type: ChunkType, parts: &[&[u8]]) -> Self {
+        let hash = hash_parts(chunk_type, parts);
+        let data_len = parts.iter().map(|part| part.len()).sum::<usize>();
         Self {
             hash,
             checksum: hash.checksum().into(),
-            data_len: data.len(),
+            data_len,
             header_size: MAGIC_BYTES.len()
                 + 4 // checksum
                 + 1 // chunk type
-                + (ulebsize(data.len() as u64) as usize),
+                + (ulebsize(data_len as u64) as usize),
             chunk_type,
         }
     }
@@ -284,10 +320,18 @@ impl Header {
 }
 
 fn hash(typ: ChunkType, data: &[u8]) -> ChangeHash {
-    let mut out = vec![u8::from(typ)];
-    leb128::write::unsigned(&mut out, data.len() as u64).unwrap();
-    out.extend(data);
-    let hash_result = Sha256::digest(out);
-    let array: [u8; 32] = hash_result.into();
+    hash_parts(typ, &[data])
+}
+
+fn hash_parts(typ: ChunkType, parts: &[&[u8]]) -> ChangeHash {
+    let mut header = vec![u8::from(typ)];
+    let data_len = parts.iter().map(|part| part.len()).sum::<usize>();
+    leb128::write::unsigned(&mut header, data_len as u64).unwrap();
+    let mut hasher = Sha256::new();
+    hasher.update(header);
+    for part in parts {
+        hasher.update(part);
+    }
+    let array: [u8; 32] = hasher.finalize().into();
     ChangeHash(array)
 }
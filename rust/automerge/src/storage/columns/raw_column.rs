@@ -73,14 +73,25 @@ impl<T: compression::ColumnCompression> RawColumn<T> {
         }
     }
 
+    /// Write the (decompressed) data of this column to `out`, failing if decompressing would make
+    /// `out` longer than `max_len`
     fn decompress(
         &self,
         input: &[u8],
         out: &mut Vec<u8>,
+        max_len: usize,
     ) -> Result<(ColumnSpec, usize), ParseError> {
         let len = if self.spec.deflate() {
-            let mut inflater = flate2::bufread::DeflateDecoder::new(&input[self.data.clone()]);
-            inflater.read_to_end(out).map_err(ParseError::Deflate)?
+            let allowed = max_len.saturating_sub(out.len());
+            let inflater = flate2::bufread::DeflateDecoder::new(&input[self.data.clone()]);
+            let len = inflater
+                .take((allowed as u64).saturating_add(1))
+                .read_to_end(out)
+                .map_err(ParseError::Deflate)?;
+            if len > allowed {
+                return Err(ParseError::InflateLimit);
+            }
+            len
         } else {
             out.extend(&input[self.data.clone()]);
             self.data.len()
@@ -135,6 +146,9 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
 
     /// Read each column from `input` and write to `out`, decompressing any compressed columns
     ///
+    /// Decompression stops with [`ParseError::InflateLimit`] if it would make `out` longer than
+    /// `max_len`.
+    ///
     /// # Returns
     /// The `RawColumns` corresponding to the data written to `out`
     ///
@@ -144,6 +158,7 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
         &self,
         input: &[u8],
         out: &mut Vec<u8>,
+        max_len: usize,
     ) -> Result<RawColumns<compression::Uncompressed>, ParseError> {
         let mut result = Vec::with_capacity(self.0.len());
         let mut start = 0;
@@ -152,7 +167,7 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
                 out.extend(&input[decomp.data.clone()]);
                 (decomp.spec, decomp.data.len())
             } else {
-                col.decompress(input, out)?
+                col.decompress(input, out, max_len)?
             };
             result.push(RawColumn {
                 spec,
@@ -199,6 +214,8 @@ pub(crate) enum ParseError {
     Leb128(#[from] parse::leb128::Error),
     #[error(transparent)]
     Deflate(#[from] std::io::Error),
+    #[error("decompressed column data was larger than allowed")]
+    InflateLimit,
 }
 
 impl RawColumns<compression::Unknown> {
@@ -1,6 +1,8 @@
-use std::{borrow::Cow, ops::Range};
+use std::{borrow::Cow, io::Write, ops::Range};
 
-use super::{parse, shift_range, ChunkType, Columns, Header, RawColumns};
+use super::{
+    columns::compression::Uncompressed, parse, shift_range, ChunkType, Columns, Header, RawColumns,
+};
 
 use crate::{convert, ActorId, ChangeHash};
 
@@ -34,6 +36,54 @@ pub(crate) struct Document<'a> {
     head_indices: Vec<u64>,
 }
 
+/// Write the actors and heads which start the data of a document chunk to `out`
+fn write_prefix(actors: &[ActorId], heads_with_indices: &[(ChangeHash, usize)], out: &mut Vec<u8>) {
+    leb128::write::unsigned(out, actors.len() as u64).unwrap();
+    for actor in actors {
+        leb128::write::unsigned(out, actor.to_bytes().len() as u64).unwrap();
+        out.extend(actor.to_bytes());
+    }
+    leb128::write::unsigned(out, heads_with_indices.len() as u64).unwrap();
+    for (head, _) in heads_with_indices {
+        out.extend(head.as_bytes());
+    }
+}
+
+/// Write the head indices which end the data of a document chunk to `out`, returning them
+fn write_head_indices(heads_with_indices: &[(ChangeHash, usize)], out: &mut Vec<u8>) -> Vec<u64> {
+    let head_indices = heads_with_indices
+        .iter()
+        .map(|(_, i)| *i as u64)
+        .collect::<Vec<_>>();
+    for index in &head_indices {
+        leb128::write::unsigned(out, *index).unwrap();
+    }
+    head_indices
+}
+
+/// Write the metadata of the columns `raw_columns`, whose data is `data`, to `meta` and return
+/// their data, compressed if `compress` says so
+fn encode_columns(
+    raw_columns: RawColumns<Uncompressed>,
+    data: Vec<u8>,
+    compress: &CompressConfig,
+    meta: &mut Vec<u8>,
+) -> Vec<u8> {
+    match compress {
+        CompressConfig::None => {
+            raw_columns.write(meta);
+            data
+        }
+        CompressConfig::Threshold(threshold) => {
+            let mut compressed = Vec::new();
+            raw_columns
+                .compress(&data, &mut compressed, *threshold)
+                .write(meta);
+            compressed
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum ParseError {
     #[error(transparent)]
@@ -62,12 +112,16 @@ impl<'a> Document<'a> {
     /// let chunkbytes: &[u8] = todo!();
     /// let input = Input::new(chunkbytes);
     /// let (i, header) = Header::parse(input)?;
-    /// let (i, doc) = Document::parse(i, header)?;
+    /// let (i, doc) = Document::parse(i, header, usize::MAX)?;
     /// # }
     /// ```
+    ///
+    /// Parsing fails if decompressing the column data would produce more than `max_inflated_len`
+    /// bytes.
     pub(crate) fn parse(
         input: parse::Input<'a>,
         header: Header,
+        max_inflated_len: usize,
     ) -> parse::ParseResult<'a, Document<'a>, ParseError> {
         let i = input;
 
@@ -160,20 +214,23 @@ impl<'a> Document<'a> {
             compressed,
             changes,
             ops,
-        } = compression::decompress(compression::Args {
-            prefix: prefix.start,
-            suffix: suffix.start,
-            original: Cow::Borrowed(input.bytes()),
-            changes: compression::Cols {
-                data: changes,
-                raw_columns: change_meta,
-            },
-            ops: compression::Cols {
-                data: ops,
-                raw_columns: ops_meta,
+        } = compression::decompress(
+            compression::Args {
+                prefix: prefix.start,
+                suffix: suffix.start,
+                original: Cow::Borrowed(input.bytes()),
+                changes: compression::Cols {
+                    data: changes,
+                    raw_columns: change_meta,
+                },
+                ops: compression::Cols {
+                    data: ops,
+                    raw_columns: ops_meta,
+                },
+                extra_args: (),
             },
-            extra_args: (),
-        })
+            max_inflated_len,
+        )
         .map_err(|e| parse::ParseError::Error(ParseError::RawColumns(e)))?;
 
         let ops_layout = Columns::parse(op_bytes.len(), ops.iter()).map_err(|e| {
@@ -233,15 +290,7 @@ impl<'a> Document<'a> {
         actors.sort_unstable();
 
         let mut data = Vec::with_capacity(ops_out.len() + change_out.len());
-        leb128::write::unsigned(&mut data, actors.len() as u64).unwrap();
-        for actor in &actors {
-            leb128::write::unsigned(&mut data, actor.to_bytes().len() as u64).unwrap();
-            data.extend(actor.to_bytes());
-        }
-        leb128::write::unsigned(&mut data, heads_with_indices.len() as u64).unwrap();
-        for (head, _) in &heads_with_indices {
-            data.extend(head.as_bytes());
-        }
+        write_prefix(&actors, &heads_with_indices, &mut data);
         let prefix_len = data.len();
 
         change_meta.raw_columns().write(&mut data);
@@ -254,13 +303,7 @@ impl<'a> Document<'a> {
         data.extend(ops_out);
         let suffix_start = data.len();
 
-        let head_indices = heads_with_indices
-            .iter()
-            .map(|(_, i)| *i as u64)
-            .collect::<Vec<_>>();
-        for index in &head_indices {
-            leb128::write::unsigned(&mut data, *index).unwrap();
-        }
+        let head_indices = write_head_indices(&heads_with_indices, &mut data);
 
         let header = Header::new(ChunkType::Document, &data);
         let mut bytes = Vec::with_capacity(data.len() + header.len());
@@ -308,6 +351,55 @@ impl<'a> Document<'a> {
         }
     }
 
+    /// Encode a document chunk as [`Self::new`] does and write it to `out`
+    ///
+    /// Only the encoded columns are held in memory, the chunk itself is never assembled: its
+    /// checksum is computed over each part in turn and the parts are then written one after
+    /// another. With [`CompressConfig::Threshold`] each set of columns is compressed, and its
+    /// uncompressed encoding dropped, before the next is encoded.
+    pub(crate) fn write<'b, I, C, IC, D, O, W>(
+        mut actors: Vec<ActorId>,
+        heads_with_indices: Vec<(ChangeHash, usize)>,
+        ops: I,
+        changes: IC,
+        compress: CompressConfig,
+        mut out: W,
+    ) -> std::io::Result<()>
+    where
+        I: Iterator<Item = D> + Clone + ExactSizeIterator,
+        O: convert::OpId<usize>,
+        D: AsDocOp<'b, OpId = O>,
+        C: AsChangeMeta<'b>,
+        IC: Iterator<Item = C> + Clone,
+        W: Write,
+    {
+        actors.sort_unstable();
+        let mut prefix = Vec::new();
+        write_prefix(&actors, &heads_with_indices, &mut prefix);
+
+        // Encode (and maybe compress) the changes, then the ops, writing the metadata of each
+        let mut meta = Vec::new();
+        let mut change_out = Vec::new();
+        let change_meta = DocChangeColumns::encode(changes, &mut change_out);
+        let change_out =
+            encode_columns(change_meta.raw_columns(), change_out, &compress, &mut meta);
+        let mut ops_out = Vec::new();
+        let ops_meta = DocOpColumns::encode(ops, &mut ops_out);
+        let ops_out = encode_columns(ops_meta.raw_columns(), ops_out, &compress, &mut meta);
+
+        let mut suffix = Vec::new();
+        write_head_indices(&heads_with_indices, &mut suffix);
+
+        let parts = [&prefix[..], &meta, &change_out, &ops_out, &suffix];
+        let mut header = Vec::new();
+        Header::for_parts(ChunkType::Document, &parts).write(&mut header);
+        out.write_all(&header)?;
+        for part in parts {
+            out.write_all(part)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn iter_ops(
         &'a self,
     ) -> impl Iterator<Item = Result<DocOp, ReadDocOpError>> + Clone + 'a {
@@ -333,6 +425,11 @@ impl<'a> Document<'a> {
         self.header.checksum_valid()
     }
 
+    /// The length of the chunk once any compressed columns have been decompressed
+    pub(crate) fn uncompressed_len(&self) -> usize {
+        self.bytes.len()
+    }
+
     pub(crate) fn actors(&self) -> &[ActorId] {
         &self.actors
     }
@@ -48,8 +48,11 @@ pub(super) fn compress(args: Args<'_, compression::Uncompressed, CompressArgs>)
     result.unwrap()
 }
 
+/// Decompress a document chunk, failing if the decompressed column data would be longer than
+/// `max_len`
 pub(super) fn decompress<'a>(
     args: Args<'a, compression::Unknown, ()>,
+    max_len: usize,
 ) -> Result<Decompressed<'a>, raw_column::ParseError> {
     match (
         args.changes.raw_columns.uncompressed(),
@@ -64,7 +67,7 @@ pub(super) fn decompress<'a>(
             op_bytes: args.ops.data,
         }),
         _ => Ok(
-            Compression::<'a, Decompressing, _>::new(args, Decompressing)
+            Compression::<'a, Decompressing, _>::new(args, Decompressing { max_len })
                 .changes()?
                 .ops()?
                 .write_data()
@@ -156,7 +159,9 @@ impl Direction for Compressing {
 }
 
 #[derive(Debug)]
-struct Decompressing;
+struct Decompressing {
+    max_len: usize,
+}
 
 impl Direction for Decompressing {
     type Error = raw_column::ParseError;
@@ -172,9 +177,9 @@ impl Direction for Decompressing {
         meta_out: &mut Vec<u8>,
     ) -> Result<Cols<Self::Out>, raw_column::ParseError> {
         let start = out.len();
-        let raw_columns = cols
-            .raw_columns
-            .uncompress(&input[cols.data.clone()], out)?;
+        let raw_columns =
+            cols.raw_columns
+                .uncompress(&input[cols.data.clone()], out, self.max_len)?;
         raw_columns.write(meta_out);
         Ok(Cols {
             data: start..out.len(),
@@ -2,14 +2,20 @@ use tracing::instrument;
 
 use crate::{
     change::Change,
-    storage::{self, parse},
+    error::LimitExceeded,
+    storage::{self, parse, ReadChangeOpError},
 };
 
 mod change_collector;
+mod limits;
 mod reconstruct_document;
+mod stream;
+pub(crate) use limits::Budget;
+pub use limits::LoadLimits;
 pub(crate) use reconstruct_document::{
     reconstruct_document, DocObserver, LoadedObject, Reconstructed, VerificationMode,
 };
+pub(crate) use stream::read_chunk;
 
 #[derive(Debug, thiserror::Error)]
 #[allow(unreachable_pub)]
@@ -26,6 +32,30 @@ pub enum Error {
     InflateDocument(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("bad checksum")]
     BadChecksum,
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+    #[error("failed to read from stream: {0}")]
+    Read(#[source] std::io::Error),
+    #[error("stream ended part way through a chunk, expected {expected} bytes but found {found}")]
+    Truncated { expected: usize, found: usize },
+}
+
+impl From<reconstruct_document::Error> for Error {
+    fn from(e: reconstruct_document::Error) -> Self {
+        match e {
+            reconstruct_document::Error::LimitExceeded(l) => Error::LimitExceeded(l),
+            e => Error::InflateDocument(Box::new(e)),
+        }
+    }
+}
+
+impl From<ReadChangeOpError> for Error {
+    fn from(e: ReadChangeOpError) -> Self {
+        match e {
+            ReadChangeOpError::LimitExceeded(l) => Error::LimitExceeded(l),
+            e => Error::InvalidChangeColumns(Box::new(e)),
+        }
+    }
 }
 
 pub(crate) enum LoadedChanges<'a> {
@@ -51,11 +81,16 @@ pub(crate) enum LoadedChanges<'a> {
 /// or more changes. This means it is possible to partially load corrupted data if the first `n`
 /// chunks are valid. This function returns a `LoadedChanges` which you can examine to determine if
 /// this is the case.
-#[instrument(skip(data))]
-pub(crate) fn load_changes<'a>(mut data: parse::Input<'a>) -> LoadedChanges<'a> {
+///
+/// Loading stops with [`Error::LimitExceeded`] if `budget` is exhausted.
+#[instrument(skip(data, budget))]
+pub(crate) fn load_changes<'a>(
+    mut data: parse::Input<'a>,
+    budget: &mut Budget,
+) -> LoadedChanges<'a> {
     let mut changes = Vec::new();
     while !data.is_empty() {
-        let remaining = match load_next_change(data, &mut changes) {
+        let remaining = match load_next_change(data, &mut changes, budget) {
             Ok(d) => d,
             Err(e) => {
                 return LoadedChanges::Partial {
@@ -70,11 +105,35 @@ pub(crate) fn load_changes<'a>(mut data: parse::Input<'a>) -> LoadedChanges<'a>
     LoadedChanges::Complete(changes)
 }
 
+/// Parse the next chunk in `data`, spending the decompressed size of the chunk from `budget`
+pub(crate) fn parse_chunk<'a>(
+    data: parse::Input<'a>,
+    budget: &mut Budget,
+) -> Result<(parse::Input<'a>, storage::Chunk<'a>), Error> {
+    let (remaining, chunk) =
+        storage::Chunk::parse_with_inflate_limit(data, budget.remaining_bytes()).map_err(|e| {
+            match e {
+                parse::ParseError::Error(e) if e.is_inflate_limit() => {
+                    Error::LimitExceeded(budget.bytes_exceeded())
+                }
+                e => Error::Parse(Box::new(e)),
+            }
+        })?;
+    let len = match &chunk {
+        storage::Chunk::Document(d) => d.uncompressed_len(),
+        storage::Chunk::Change(c) => c.bytes().len(),
+        storage::Chunk::CompressedChange(c, _) => c.bytes().len(),
+    };
+    budget.spend_bytes(len)?;
+    Ok((remaining, chunk))
+}
+
 fn load_next_change<'a>(
     data: parse::Input<'a>,
     changes: &mut Vec<Change>,
+    budget: &mut Budget,
 ) -> Result<parse::Input<'a>, Error> {
-    let (remaining, chunk) = storage::Chunk::parse(data).map_err(|e| Error::Parse(Box::new(e)))?;
+    let (remaining, chunk) = parse_chunk(data, budget)?;
     if !chunk.checksum_valid() {
         return Err(Error::BadChecksum);
     }
@@ -84,14 +143,13 @@ fn load_next_change<'a>(
             let Reconstructed {
                 changes: new_changes,
                 ..
-            } = reconstruct_document(&d, VerificationMode::DontCheck, NullObserver)
-                .map_err(|e| Error::InflateDocument(Box::new(e)))?;
+            } = reconstruct_document(&d, VerificationMode::DontCheck, NullObserver, budget)?;
             changes.extend(new_changes);
         }
         storage::Chunk::Change(change) => {
             tracing::trace!("loading change chunk");
-            let change = Change::new_from_unverified(change.into_owned(), None)
-                .map_err(|e| Error::InvalidChangeColumns(Box::new(e)))?;
+            let change =
+                Change::new_from_unverified_with_budget(change.into_owned(), None, budget)?;
             #[cfg(debug_assertions)]
             {
                 let loaded_ops = change.iter_ops().collect::<Vec<_>>();
@@ -103,9 +161,11 @@ fn load_next_change<'a>(
         }
         storage::Chunk::CompressedChange(change, compressed) => {
             tracing::trace!("loading compressed change chunk");
-            let change =
-                Change::new_from_unverified(change.into_owned(), Some(compressed.into_owned()))
-                    .map_err(|e| Error::InvalidChangeColumns(Box::new(e)))?;
+            let change = Change::new_from_unverified_with_budget(
+                change.into_owned(),
+                Some(compressed.into_owned()),
+                budget,
+            )?;
             changes.push(change);
         }
     };
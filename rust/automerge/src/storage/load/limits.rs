@@ -0,0 +1,75 @@
+use crate::error::LimitExceeded;
+
+/// Limits on the resources which may be used when loading a document with
+/// [`crate::Automerge::load_with_limits`]
+///
+/// Loading stops with [`LimitExceeded`] as soon as any limit is crossed, before the data which
+/// would cross it is allocated. Every limit is unlimited by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadLimits {
+    /// The maximum number of ops encoded in the data
+    pub max_ops: Option<usize>,
+    /// The maximum number of objects created by ops encoded in the data
+    pub max_objects: Option<usize>,
+    /// The maximum total size of the data once any compressed chunks or columns are decompressed
+    pub max_bytes: Option<usize>,
+}
+
+/// Tracks how much of each limit in a [`LoadLimits`] has been used during a load
+#[derive(Debug, Default)]
+pub(crate) struct Budget {
+    limits: LoadLimits,
+    ops: usize,
+    objects: usize,
+    bytes: usize,
+}
+
+impl Budget {
+    pub(crate) fn new(limits: LoadLimits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn spend_op(&mut self) -> Result<(), LimitExceeded> {
+        self.ops += 1;
+        match self.limits.max_ops {
+            Some(max) if self.ops > max => Err(LimitExceeded::Ops(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn spend_object(&mut self) -> Result<(), LimitExceeded> {
+        self.objects += 1;
+        match self.limits.max_objects {
+            Some(max) if self.objects > max => Err(LimitExceeded::Objects(max)),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn spend_bytes(&mut self, len: usize) -> Result<(), LimitExceeded> {
+        self.bytes = self.bytes.saturating_add(len);
+        match self.limits.max_bytes {
+            Some(max) if self.bytes > max => Err(LimitExceeded::Bytes(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The number of bytes which may still be decompressed
+    pub(crate) fn remaining_bytes(&self) -> usize {
+        match self.limits.max_bytes {
+            Some(max) => max.saturating_sub(self.bytes),
+            None => usize::MAX,
+        }
+    }
+
+    /// The error to return when decompression was stopped by [`Self::remaining_bytes`]
+    pub(crate) fn bytes_exceeded(&self) -> LimitExceeded {
+        LimitExceeded::Bytes(self.limits.max_bytes.unwrap_or(usize::MAX))
+    }
+}
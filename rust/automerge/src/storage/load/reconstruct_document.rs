@@ -1,4 +1,5 @@
 use super::change_collector::ChangeCollector;
+use super::Budget;
 use std::collections::{BTreeSet, HashMap};
 use tracing::instrument;
 
@@ -29,6 +30,8 @@ pub(crate) enum Error {
     SuccOutOfOrder,
     #[error(transparent)]
     InvalidOp(#[from] crate::error::InvalidOpType),
+    #[error(transparent)]
+    LimitExceeded(#[from] crate::error::LimitExceeded),
 }
 
 pub(crate) struct MismatchedHeads {
@@ -89,11 +92,12 @@ pub enum VerificationMode {
     DontCheck,
 }
 
-#[instrument(skip(doc, observer))]
+#[instrument(skip(doc, observer, budget))]
 pub(crate) fn reconstruct_document<'a, O: DocObserver>(
     doc: &'a Document<'a>,
     mode: VerificationMode,
     mut observer: O,
+    budget: &mut Budget,
 ) -> Result<Reconstructed<O::Output>, Error> {
     // The document format does not contain the bytes of the changes which are encoded in it
     // directly. Instead the metadata about the changes (the actor, the start op, etc.) are all
@@ -133,6 +137,7 @@ pub(crate) fn reconstruct_document<'a, O: DocObserver>(
 
     for op_res in doc.iter_ops() {
         let doc_op = op_res.map_err(|e| Error::ReadOp(Box::new(e)))?;
+        budget.spend_op()?;
         max_op = std::cmp::max(max_op, doc_op.id.counter());
 
         // Delete ops only appear as succ values in the document operations, so if a delete
@@ -148,6 +153,7 @@ pub(crate) fn reconstruct_document<'a, O: DocObserver>(
         tracing::trace!(?op, ?obj, "loading document op");
 
         if let OpType::Make(obj_type) = op.action {
+            budget.spend_object()?;
             create_ops.insert(
                 ObjId::from(op.id),
                 CreateOp {
@@ -267,7 +273,7 @@ impl LoadingObject {
     fn append_op(&mut self, op: Op) -> Result<(), Error> {
         // Collect set and make operations so we can find the keys which delete operations refer to
         // in `finish`
-        if matches!(op.action, OpType::Put(_) | OpType::Make(_)) {
+        if op.action.is_put() || op.action.is_make() {
             match op.key {
                 Key::Map(_) => {
                     self.set_ops.insert(op.id, op.key);
@@ -0,0 +1,78 @@
+use std::io::Read;
+
+use super::Error;
+use crate::storage::{ChunkType, MAGIC_BYTES};
+
+/// The magic bytes, checksum and chunk type which start every chunk
+const FIXED_HEADER_LEN: usize = 9;
+
+/// The maximum number of bytes in a LEB128 encoded u64
+const MAX_LEB128_LEN: usize = 10;
+
+/// Read the next chunk, header included, from `reader`
+///
+/// Returns `Ok(None)` if the stream ended cleanly between two chunks. The data of the chunk is
+/// read in pieces as it arrives rather than allocated up front, so a corrupt length in the header
+/// fails with [`Error::Truncated`] instead of allocating a huge buffer.
+pub(crate) fn read_chunk<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, Error> {
+    let mut chunk = Vec::with_capacity(FIXED_HEADER_LEN + MAX_LEB128_LEN);
+    let read = read_up_to(reader, &mut chunk, FIXED_HEADER_LEN)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read < FIXED_HEADER_LEN {
+        return Err(Error::Truncated {
+            expected: FIXED_HEADER_LEN,
+            found: read,
+        });
+    }
+    if chunk[..4] != MAGIC_BYTES {
+        return Err(Error::Parse("invalid magic bytes".into()));
+    }
+    if ChunkType::try_from(chunk[8]).is_err() {
+        return Err(Error::Parse(
+            format!("unknown chunk type: {}", chunk[8]).into(),
+        ));
+    }
+
+    let mut data_len: u64 = 0;
+    for i in 0..MAX_LEB128_LEN {
+        if read_up_to(reader, &mut chunk, 1)? == 0 {
+            return Err(Error::Truncated {
+                expected: FIXED_HEADER_LEN + i + 1,
+                found: FIXED_HEADER_LEN + i,
+            });
+        }
+        let byte = chunk[FIXED_HEADER_LEN + i];
+        data_len |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            break;
+        }
+        if i == MAX_LEB128_LEN - 1 {
+            return Err(Error::Parse("chunk length overflows a u64".into()));
+        }
+    }
+
+    let header_len = chunk.len();
+    let data_len = usize::try_from(data_len)
+        .map_err(|_| Error::Parse("chunk length overflows a usize".into()))?;
+    let read = read_up_to(reader, &mut chunk, data_len)?;
+    if read < data_len {
+        return Err(Error::Truncated {
+            expected: header_len + data_len,
+            found: header_len + read,
+        });
+    }
+    Ok(Some(chunk))
+}
+
+/// Append up to `len` bytes from `reader` to `buf`, stopping early only at the end of the stream
+///
+/// Returns the number of bytes which were appended
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut Vec<u8>, len: usize) -> Result<usize, Error> {
+    reader
+        .by_ref()
+        .take(len as u64)
+        .read_to_end(buf)
+        .map_err(Error::Read)
+}
@@ -1,2 +1,2 @@
 mod document;
-pub(crate) use document::save_document;
+pub(crate) use document::{save_document, write_document};
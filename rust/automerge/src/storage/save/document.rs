@@ -51,6 +51,47 @@ where
     doc.into_bytes()
 }
 
+/// Like [`save_document`] but writing the document chunk to `out`, see [`Document::write`]
+///
+/// # Panics
+///
+/// As for [`save_document`]
+#[tracing::instrument(skip(changes, ops, actors, props, config, out))]
+pub(crate) fn write_document<'a, I, O, W>(
+    changes: I,
+    ops: O,
+    actors: &'a IndexedCache<ActorId>,
+    props: &IndexedCache<String>,
+    heads: &[ChangeHash],
+    config: Option<CompressConfig>,
+    out: W,
+) -> std::io::Result<()>
+where
+    I: Iterator<Item = &'a Change> + Clone + 'a,
+    O: Iterator<Item = (&'a ObjId, &'a Op)> + Clone + ExactSizeIterator,
+    W: std::io::Write,
+{
+    let actor_lookup = actors.encode_index();
+    let doc_ops = ops.map(|(obj, op)| op_as_docop(&actor_lookup, props, obj, op));
+
+    let hash_graph = HashGraph::new(changes.clone());
+    let changes = changes.map(|c| ChangeWithGraph {
+        actors,
+        actor_lookup: &actor_lookup,
+        change: c,
+        graph: &hash_graph,
+    });
+
+    Document::write(
+        actors.sorted().cache,
+        hash_graph.heads_with_indices(heads.to_vec()),
+        doc_ops,
+        changes,
+        config.unwrap_or(CompressConfig::Threshold(DEFLATE_MIN_SIZE)),
+        out,
+    )
+}
+
 struct HashGraph {
     index_by_hash: HashMap<ChangeHash, usize, FxBuildHasher>,
 }
@@ -72,15 +72,18 @@ use serde::ser::SerializeMap;
 use std::collections::{HashMap, HashSet};
 
 use crate::{
+    columnar::encoding::leb128::ulebsize,
     patches::{PatchLog, TextRepresentation},
     storage::{parse, Change as StoredChange, ReadChangeOpError},
-    Automerge, AutomergeError, Change, ChangeHash, ReadDoc,
+    Automerge, AutomergeError, Change, ChangeHash, Patch, ReadDoc,
 };
 
 mod bloom;
+mod peers;
 mod state;
 
 pub use bloom::{BloomFilter, DecodeError as DecodeBloomError};
+pub(crate) use peers::Peers;
 pub use state::DecodeError as DecodeStateError;
 pub use state::{Have, State};
 
@@ -126,6 +129,108 @@ pub trait SyncDoc {
         message: Message,
         patch_log: &mut PatchLog,
     ) -> Result<(), AutomergeError>;
+
+    /// Like [`Self::generate_sync_message`] but splitting the changes to send across several
+    /// messages, each of which encodes to at most `max_bytes`
+    ///
+    /// The messages must be passed to [`Self::receive_sync_message`] on the remote peer in the
+    /// order they are returned. Only the last message carries the need and have, bloom filters
+    /// included, of the unsplit message. The remote peer replaces its record of these with each
+    /// message it receives, so they have to come last for its [`State`] to end up the same as if
+    /// it had received a single message. Every message carries our heads, as a message without
+    /// heads tells the remote peer that we have lost our data.
+    ///
+    /// A change is never split, so a message containing a change which is larger than
+    /// `max_bytes` on its own will exceed the limit. If the heads, need and have don't fit in
+    /// `max_bytes` the last message, which carries them, will exceed it too.
+    fn generate_sync_message_bounded(
+        &self,
+        sync_state: &mut State,
+        max_bytes: usize,
+    ) -> Option<Vec<Message>> {
+        self.generate_sync_message(sync_state)
+            .map(|message| split_message(message, max_bytes))
+    }
+}
+
+/// Exchange messages between `a` and `b` until neither has anything to send, see
+/// [`Automerge::sync_round_trip`]
+pub(crate) fn sync_round_trip<A: SyncDoc, B: SyncDoc>(
+    a: &mut A,
+    b: &mut B,
+) -> Result<(usize, usize), AutomergeError> {
+    let mut a_state = State::new();
+    let mut b_state = State::new();
+    let (mut sent_by_a, mut sent_by_b) = (0, 0);
+    loop {
+        let a_to_b = a.generate_sync_message(&mut a_state);
+        let b_to_a = b.generate_sync_message(&mut b_state);
+        if a_to_b.is_none() && b_to_a.is_none() {
+            return Ok((sent_by_a, sent_by_b));
+        }
+        if let Some(message) = a_to_b {
+            sent_by_a += 1;
+            b.receive_sync_message(&mut b_state, message)?;
+        }
+        if let Some(message) = b_to_a {
+            sent_by_b += 1;
+            a.receive_sync_message(&mut a_state, message)?;
+        }
+    }
+}
+
+/// Split `message` into messages which each encode to at most `max_bytes`, see
+/// [`SyncDoc::generate_sync_message_bounded`]
+fn split_message(message: Message, max_bytes: usize) -> Vec<Message> {
+    let Message {
+        heads,
+        need,
+        have,
+        changes,
+    } = message;
+    let fragment = |changes| Message {
+        heads: heads.clone(),
+        need: Vec::new(),
+        have: Vec::new(),
+        changes,
+    };
+    // The length of a fragment without any changes, less the single byte for a change count of 0
+    let header_len = fragment(Vec::new()).size_hint() - 1;
+
+    let mut messages = Vec::new();
+    let mut current = Vec::new();
+    let mut current_len = 0;
+    for change in changes {
+        let change_len = change.raw_bytes().len();
+        let change_len = ulebsize(change_len as u64) as usize + change_len;
+        let len_with_change =
+            header_len + ulebsize(current.len() as u64 + 1) as usize + current_len + change_len;
+        if !current.is_empty() && len_with_change > max_bytes {
+            messages.push(fragment(std::mem::take(&mut current)));
+            current_len = 0;
+        }
+        current.push(change);
+        current_len += change_len;
+    }
+
+    // The need and have go in the last message, or in a message of their own if they don't fit
+    // alongside the last changes
+    let last = Message {
+        heads: heads.clone(),
+        need,
+        have,
+        changes: current,
+    };
+    if !last.changes.is_empty() && last.size_hint() > max_bytes {
+        messages.push(fragment(last.changes));
+        messages.push(Message {
+            changes: Vec::new(),
+            ..last
+        });
+    } else {
+        messages.push(last);
+    }
+    messages
 }
 
 const MESSAGE_TYPE_SYNC: u8 = 0x42; // first byte of a sync message, for identification
@@ -240,6 +345,101 @@ impl SyncDoc for Automerge {
 }
 
 impl Automerge {
+    /// Limit the number of peers whose sync state is tracked by this document
+    ///
+    /// Sync states are tracked for peers which are synchronized using
+    /// [`Self::generate_sync_message_for_peer`] and [`Self::receive_sync_message_for_peer`]. When
+    /// more than `n` peers are being tracked the state of the least recently active peer is
+    /// discarded. If that peer reconnects it will start again from a fresh [`State`].
+    pub fn set_sync_peer_limit(&mut self, n: usize) {
+        self.sync_peers_mut().set_limit(n);
+    }
+
+    /// The [`State`] this document tracks for `peer_id`
+    ///
+    /// Returns `None` if this document hasn't synchronized with `peer_id` using
+    /// [`Self::generate_sync_message_for_peer`] or [`Self::receive_sync_message_for_peer`], or
+    /// if the state of `peer_id` was discarded because of [`Self::set_sync_peer_limit`].
+    pub fn get_sync_state_for(&self, peer_id: &str) -> Option<&State> {
+        self.sync_peers().get(peer_id)
+    }
+
+    /// Like [`SyncDoc::generate_sync_message`] but using the [`State`] this document tracks for
+    /// `peer_id`
+    pub fn generate_sync_message_for_peer(&mut self, peer_id: &str) -> Option<Message> {
+        let mut sync_state = self.sync_peers_mut().take(peer_id);
+        let message = self.generate_sync_message(&mut sync_state);
+        self.sync_peers_mut().put(peer_id, sync_state);
+        message
+    }
+
+    /// Like [`SyncDoc::receive_sync_message`] but using the [`State`] this document tracks for
+    /// `peer_id`
+    pub fn receive_sync_message_for_peer(
+        &mut self,
+        peer_id: &str,
+        message: Message,
+    ) -> Result<(), AutomergeError> {
+        let mut sync_state = self.sync_peers_mut().take(peer_id);
+        let result = self.receive_sync_message(&mut sync_state, message);
+        self.sync_peers_mut().put(peer_id, sync_state);
+        result
+    }
+
+    /// Apply the changes in `message` without tracking any [`State`] for the sender
+    ///
+    /// The heads, needs and bloom filters in the message are ignored, which suits a sender which
+    /// broadcasts changes it knows the recipient is missing, for example every change to a
+    /// recipient it knows is empty. Changes whose dependencies are missing are kept until the
+    /// dependencies arrive, as with [`Self::apply_changes`]. Returns the hashes of the changes in
+    /// the message which were applied by this call, in the order they appear in the message.
+    pub fn receive_sync_message_without_state(
+        &mut self,
+        message: Message,
+    ) -> Result<Vec<ChangeHash>, AutomergeError> {
+        let new = message
+            .changes
+            .iter()
+            .map(|c| c.hash())
+            .filter(|hash| self.get_change_by_hash(hash).is_none())
+            .collect::<Vec<_>>();
+        self.apply_changes(message.changes)?;
+        Ok(new
+            .into_iter()
+            .filter(|hash| self.get_change_by_hash(hash).is_some())
+            .collect())
+    }
+
+    /// Like [`SyncDoc::receive_sync_message`] but returning the [`Patch`]es which describe how
+    /// the received changes altered the current state of the document
+    ///
+    /// This is equivalent to calling [`SyncDoc::receive_sync_message_log_patches`] with an active
+    /// [`PatchLog`] and then [`Self::make_patches`], so there is no need to record the heads
+    /// before and after receiving the message and diff between them.
+    pub fn receive_sync_message_with_patches(
+        &mut self,
+        sync_state: &mut State,
+        message: Message,
+    ) -> Result<Vec<Patch>, AutomergeError> {
+        let mut patch_log = PatchLog::active(TextRepresentation::default());
+        self.receive_sync_message_inner(sync_state, message, &mut patch_log)?;
+        Ok(self.make_patches(&mut patch_log))
+    }
+
+    /// Sync this document with `other` until they both have the same changes
+    ///
+    /// Each document generates a message for the other in turn, starting from a new [`State`]
+    /// on both sides, until neither has anything more to send. This is useful in tests and when
+    /// both documents are in the same process.
+    ///
+    /// Returns the number of messages sent by this document and by `other`.
+    pub fn sync_round_trip(
+        &mut self,
+        other: &mut Automerge,
+    ) -> Result<(usize, usize), AutomergeError> {
+        sync_round_trip(self, other)
+    }
+
     fn make_bloom_filter(&self, last_sync: Vec<ChangeHash>) -> Have {
         let new_changes = self.get_changes(&last_sync);
         let hashes = new_changes.iter().map(|change| change.hash());
@@ -527,6 +727,41 @@ impl Message {
         ))
     }
 
+    /// The number of bytes [`Self::encode`] will produce
+    ///
+    /// This is calculated without encoding the message, so it can be used to decide how to
+    /// allocate, fragment or compress the message before encoding it.
+    pub fn size_hint(&self) -> usize {
+        fn len_prefix(len: usize) -> usize {
+            ulebsize(len as u64) as usize
+        }
+        fn hashes_len(hashes: &[ChangeHash]) -> usize {
+            len_prefix(hashes.len()) + std::mem::size_of_val(hashes)
+        }
+        let have_len: usize = self
+            .have
+            .iter()
+            .map(|h| {
+                let bloom_len = h.bloom.encoded_len();
+                hashes_len(&h.last_sync) + len_prefix(bloom_len) + bloom_len
+            })
+            .sum();
+        let changes_len: usize = self
+            .changes
+            .iter()
+            .map(|c| {
+                let change_len = c.raw_bytes().len();
+                len_prefix(change_len) + change_len
+            })
+            .sum();
+        1 + hashes_len(&self.heads)
+            + hashes_len(&self.need)
+            + len_prefix(self.have.len())
+            + have_len
+            + len_prefix(self.changes.len())
+            + changes_len
+    }
+
     pub fn encode(mut self) -> Vec<u8> {
         let mut buf = vec![MESSAGE_TYPE_SYNC];
 
@@ -661,6 +896,11 @@ mod tests {
             assert!(i.is_empty());
             assert_eq!(msg, decoded);
         }
+
+        #[test]
+        fn size_hint_is_exact(msg in gen_sync_message()) {
+            assert_eq!(msg.size_hint(), msg.clone().encode().len());
+        }
     }
 
     #[test]
@@ -673,6 +913,77 @@ mod tests {
         assert!(doc.sync().generate_sync_message(&mut sync_state).is_none());
     }
 
+    #[test]
+    fn sync_with_tracked_peer_states() {
+        let mut doc1 = Automerge::new();
+        let mut tx = doc1.transaction();
+        tx.put(crate::ROOT, "key", "value").unwrap();
+        tx.commit();
+        let mut doc2 = Automerge::new();
+
+        loop {
+            let one_to_two = doc1.generate_sync_message_for_peer("doc2");
+            if let Some(msg) = one_to_two.clone() {
+                doc2.receive_sync_message_for_peer("doc1", msg).unwrap();
+            }
+            let two_to_one = doc2.generate_sync_message_for_peer("doc1");
+            if let Some(msg) = two_to_one.clone() {
+                doc1.receive_sync_message_for_peer("doc2", msg).unwrap();
+            }
+            if one_to_two.is_none() && two_to_one.is_none() {
+                break;
+            }
+        }
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+        assert_eq!(
+            doc1.get_sync_state_for("doc2").unwrap().shared_heads,
+            doc1.get_heads()
+        );
+        assert!(doc1.get_sync_state_for("doc3").is_none());
+
+        // once the state for doc2 is evicted we start from scratch and send a message again
+        assert!(doc1.generate_sync_message_for_peer("doc2").is_none());
+        doc1.generate_sync_message_for_peer("doc3");
+        doc1.set_sync_peer_limit(1);
+        assert!(doc1.get_sync_state_for("doc2").is_none());
+        assert!(doc1.generate_sync_message_for_peer("doc2").is_some());
+    }
+
+    #[test]
+    fn receive_sync_message_without_state_applies_missing_changes() {
+        let mut doc1 = crate::AutoCommit::new();
+        doc1.put(crate::ROOT, "key", "value").unwrap();
+        doc1.commit();
+        let first = doc1.get_heads();
+        doc1.put(crate::ROOT, "key", "other").unwrap();
+        doc1.commit();
+        let changes = doc1
+            .get_changes(&[])
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let message = |changes: &[Change]| Message {
+            heads: Vec::new(),
+            need: Vec::new(),
+            have: Vec::new(),
+            changes: changes.to_vec(),
+        };
+
+        let mut doc2 = Automerge::new();
+        // the second change can't be applied until the first arrives
+        assert_eq!(
+            doc2.receive_sync_message_without_state(message(&changes[1..]))
+                .unwrap(),
+            Vec::new()
+        );
+        assert_eq!(
+            doc2.receive_sync_message_without_state(message(&changes))
+                .unwrap(),
+            vec![first[0], doc1.get_heads()[0]]
+        );
+        assert_eq!(doc2.get_heads(), doc1.get_heads());
+    }
+
     #[test]
     fn should_not_reply_if_we_have_no_data() {
         let mut doc1 = crate::AutoCommit::new();
@@ -948,6 +1259,289 @@ mod tests {
         assert_eq!(doc1.get_heads(), doc2.get_heads());
     }
 
+    #[test]
+    fn receive_sync_message_with_patches_returns_patches() {
+        let mut doc1 = crate::AutoCommit::new();
+        doc1.put(crate::ROOT, "key", "value").unwrap();
+        let list = doc1
+            .put_object(crate::ROOT, "list", crate::ObjType::List)
+            .unwrap();
+        doc1.insert(&list, 0, 1).unwrap();
+
+        let mut doc2 = crate::AutoCommit::new();
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        let mut patches = Vec::new();
+        loop {
+            let a_to_b = doc1.sync().generate_sync_message(&mut s1);
+            let b_to_a = doc2.sync().generate_sync_message(&mut s2);
+            if a_to_b.is_none() && b_to_a.is_none() {
+                break;
+            }
+            if let Some(msg) = a_to_b {
+                patches.extend(
+                    doc2.receive_sync_message_with_patches(&mut s2, msg)
+                        .unwrap(),
+                );
+            }
+            if let Some(msg) = b_to_a {
+                doc1.sync().receive_sync_message(&mut s1, msg).unwrap();
+            }
+        }
+
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+        assert_eq!(patches.len(), 3);
+        assert!(patches.iter().any(|p| matches!(
+            &p.action,
+            crate::PatchAction::PutMap { key, .. } if key == "key"
+        )));
+        assert!(patches.iter().any(|p| p.obj == list
+            && matches!(
+                &p.action,
+                crate::PatchAction::Insert { index: 0, values, .. } if values.len() == 1
+            )));
+
+        let heads = doc1.get_heads();
+        let empty = Message {
+            heads,
+            need: vec![],
+            have: vec![],
+            changes: vec![],
+        };
+        assert_eq!(
+            doc2.receive_sync_message_with_patches(&mut s2, empty)
+                .unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn receive_sync_message_with_patches_uses_the_text_representation() {
+        // Deleting the winning value of "x" reveals a text object, which is observed in full
+        let mut doc1 = crate::AutoCommit::new()
+            .with_actor(crate::ActorId::from(&[2][..]))
+            .with_text_rep(TextRepresentation::String);
+        doc1.put(crate::ROOT, "x", 1).unwrap();
+        let mut doc2 = doc1.fork().with_actor(crate::ActorId::from(&[3][..]));
+        doc2.delete(crate::ROOT, "x").unwrap();
+        let mut other = crate::AutoCommit::new().with_actor(crate::ActorId::from(&[1][..]));
+        let text = other
+            .put_object(crate::ROOT, "x", crate::ObjType::Text)
+            .unwrap();
+        other.splice_text(&text, 0, 0, "hi").unwrap();
+        doc1.merge(&mut other).unwrap();
+
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        let mut patches = Vec::new();
+        loop {
+            let a_to_b = doc2.sync().generate_sync_message(&mut s2);
+            let b_to_a = doc1.sync().generate_sync_message(&mut s1);
+            if a_to_b.is_none() && b_to_a.is_none() {
+                break;
+            }
+            if let Some(msg) = a_to_b {
+                patches.extend(
+                    doc1.receive_sync_message_with_patches(&mut s1, msg)
+                        .unwrap(),
+                );
+            }
+            if let Some(msg) = b_to_a {
+                doc2.sync().receive_sync_message(&mut s2, msg).unwrap();
+            }
+        }
+
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+        assert!(patches.iter().any(|p| p.obj == text
+            && matches!(
+                &p.action,
+                crate::PatchAction::SpliceText { index: 0, value, .. } if value.make_string() == "hi"
+            )));
+    }
+
+    #[test]
+    fn bounded_sync_messages_fit_and_reassemble() {
+        let mut doc1 = crate::AutoCommit::new();
+        let text = doc1
+            .put_object(crate::ROOT, "text", crate::ObjType::Text)
+            .unwrap();
+        for i in 0..20 {
+            doc1.splice_text(&text, 0, 0, &format!("paragraph {} ", i).repeat(20))
+                .unwrap();
+            doc1.commit();
+        }
+
+        let mut doc2 = crate::AutoCommit::new();
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        let max_bytes = 1024;
+        let mut fragmented = false;
+        loop {
+            let a_to_b = doc1
+                .sync()
+                .generate_sync_message_bounded(&mut s1, max_bytes);
+            let b_to_a = doc2.sync().generate_sync_message(&mut s2);
+            if a_to_b.is_none() && b_to_a.is_none() {
+                break;
+            }
+            if let Some(msgs) = a_to_b {
+                fragmented |= msgs.len() >= 3;
+                let (last, rest) = msgs.split_last().unwrap();
+                assert!(rest.iter().all(|m| m.need.is_empty() && m.have.is_empty()));
+                assert!(msgs.iter().all(|m| m.heads == last.heads));
+                for msg in msgs {
+                    assert!(msg.clone().encode().len() <= max_bytes);
+                    doc2.sync().receive_sync_message(&mut s2, msg).unwrap();
+                }
+            }
+            if let Some(msg) = b_to_a {
+                doc1.sync().receive_sync_message(&mut s1, msg).unwrap();
+            }
+        }
+
+        assert!(fragmented);
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+        assert_eq!(doc1.text(&text).unwrap(), doc2.text(&text).unwrap());
+    }
+
+    #[test]
+    fn split_messages_send_the_need_and_have_last() {
+        let mut doc = crate::AutoCommit::new();
+        for i in 0..3 {
+            doc.put(crate::ROOT, "key", i).unwrap();
+            doc.commit();
+        }
+        let message = Message {
+            heads: doc.get_heads(),
+            need: vec![doc.get_heads()[0]],
+            have: vec![Have::default()],
+            changes: doc.get_changes(&[]).into_iter().cloned().collect(),
+        };
+
+        // Too small for anything, so each change and the need and have get a message of their own
+        let messages = split_message(message.clone(), 1);
+        assert_eq!(messages.len(), 4);
+        for (fragment, change) in messages.iter().zip(&message.changes) {
+            assert_eq!(fragment.heads, message.heads);
+            assert!(fragment.need.is_empty() && fragment.have.is_empty());
+            assert_eq!(fragment.changes, vec![change.clone()]);
+        }
+        let last = &messages[3];
+        assert_eq!((&last.need, &last.have), (&message.need, &message.have));
+        assert!(last.changes.is_empty());
+
+        // Large enough for everything, so the message is unchanged
+        assert_eq!(split_message(message.clone(), usize::MAX), vec![message]);
+    }
+
+    #[test]
+    fn reset_state_matches_a_decoded_state() {
+        let mut doc1 = crate::AutoCommit::new();
+        doc1.put(crate::ROOT, "key", "value").unwrap();
+        let mut doc2 = crate::AutoCommit::new();
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        sync(&mut doc1, &mut doc2, &mut s1, &mut s2);
+        assert!(s1.their_heads.is_some());
+
+        let decoded = State::decode(&s1.encode()).unwrap();
+        s1.reset();
+        assert_eq!(s1, decoded);
+        s2.reset();
+
+        doc2.put(crate::ROOT, "other", "value").unwrap();
+        sync(&mut doc1, &mut doc2, &mut s1, &mut s2);
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+    }
+
+    #[test]
+    fn versioned_state_round_trips() {
+        let mut doc1 = crate::AutoCommit::new();
+        doc1.put(crate::ROOT, "key", "value").unwrap();
+        let mut doc2 = crate::AutoCommit::new();
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        sync(&mut doc1, &mut doc2, &mut s1, &mut s2);
+
+        let expected = State::decode(&s1.encode()).unwrap();
+        assert!(!expected.shared_heads.is_empty());
+        assert_eq!(
+            State::decode_versioned(&s1.encode_versioned()).unwrap(),
+            expected
+        );
+        assert_eq!(State::decode_versioned(&s1.encode()).unwrap(), expected);
+    }
+
+    #[test]
+    fn versioned_state_rejects_unknown_versions() {
+        let mut encoded = State::new().encode_versioned();
+        encoded[0] = 7;
+        assert!(matches!(
+            State::decode_versioned(&encoded),
+            Err(DecodeStateError::UnsupportedVersion(7))
+        ));
+        assert!(matches!(
+            State::decode_versioned(&[]),
+            Err(DecodeStateError::NotEnoughInput)
+        ));
+    }
+
+    #[test]
+    fn catch_up_sends_everything_in_the_first_message() {
+        let mut doc1 = crate::AutoCommit::new();
+        for i in 0..10 {
+            doc1.put(crate::ROOT, "key", i).unwrap();
+            doc1.commit();
+        }
+
+        // Without catch up the changes are only sent in the third message
+        let mut doc2 = crate::AutoCommit::new();
+        let mut s1 = State::new();
+        let mut s2 = State::new();
+        let msg = doc1.sync().generate_sync_message(&mut s1).unwrap();
+        assert!(msg.changes.is_empty());
+        doc2.sync().receive_sync_message(&mut s2, msg).unwrap();
+        let msg = doc2.sync().generate_sync_message(&mut s2).unwrap();
+        doc1.sync().receive_sync_message(&mut s1, msg).unwrap();
+        let msg = doc1.sync().generate_sync_message(&mut s1).unwrap();
+        doc2.sync().receive_sync_message(&mut s2, msg).unwrap();
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+
+        let mut doc3 = crate::AutoCommit::new();
+        let mut s1 = State::new_empty_peer();
+        let mut s3 = State::new();
+        let msg = doc1.sync().generate_sync_message(&mut s1).unwrap();
+        assert_eq!(msg.changes.len(), 10);
+        doc3.sync().receive_sync_message(&mut s3, msg).unwrap();
+        assert_eq!(doc1.get_heads(), doc3.get_heads());
+
+        // Later changes go through the normal protocol
+        sync(&mut doc1, &mut doc3, &mut s1, &mut s3);
+        doc3.put(crate::ROOT, "other", "value").unwrap();
+        doc1.put(crate::ROOT, "key", "new").unwrap();
+        sync(&mut doc1, &mut doc3, &mut s1, &mut s3);
+        assert_eq!(doc1.get_heads(), doc3.get_heads());
+    }
+
+    #[test]
+    fn sync_round_trip_syncs_both_ways() {
+        let mut doc1 = crate::AutoCommit::new();
+        doc1.put(crate::ROOT, "one", 1).unwrap();
+        let mut doc2 = crate::AutoCommit::new();
+        doc2.put(crate::ROOT, "two", 2).unwrap();
+
+        let (sent1, sent2) = doc1.sync_round_trip(&mut doc2).unwrap();
+        assert!(sent1 > 0 && sent2 > 0);
+        assert_eq!(doc1.get_heads(), doc2.get_heads());
+        assert!(doc1.get(crate::ROOT, "two").unwrap().is_some());
+        assert!(doc2.get(crate::ROOT, "one").unwrap().is_some());
+
+        let mut doc1 = doc1.document().clone();
+        let mut doc2 = doc2.document().clone();
+        let (sent1, sent2) = doc1.sync_round_trip(&mut doc2).unwrap();
+        assert_eq!((sent1, sent2), (1, 1));
+    }
+
     fn sync(
         a: &mut crate::AutoCommit,
         b: &mut crate::AutoCommit,
@@ -1,5 +1,6 @@
 use std::borrow::Borrow;
 
+use crate::columnar::encoding::leb128::ulebsize;
 use crate::storage::parse;
 use crate::ChangeHash;
 
@@ -46,6 +47,18 @@ impl BloomFilter {
         buf
     }
 
+    /// The length of [`Self::to_bytes`], without allocating it
+    pub(crate) fn encoded_len(&self) -> usize {
+        if self.num_entries == 0 {
+            0
+        } else {
+            ulebsize(self.num_entries as u64) as usize
+                + ulebsize(self.num_bits_per_entry as u64) as usize
+                + ulebsize(self.num_probes as u64) as usize
+                + self.bits.len()
+        }
+    }
+
     pub(crate) fn parse(input: parse::Input<'_>) -> parse::ParseResult<'_, Self, ParseError> {
         if input.is_empty() {
             Ok((input, Self::default()))
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use super::State;
+
+/// The sync states of the peers a document is synchronizing with, keyed by peer ID.
+///
+/// Each state is stamped with a logical timestamp every time it is used. If a limit has been set
+/// with [`Self::set_limit`] then the least recently used states are evicted whenever the number of
+/// tracked peers exceeds the limit.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Peers {
+    states: HashMap<String, Peer>,
+    limit: Option<usize>,
+    tick: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Peer {
+    state: State,
+    last_active: u64,
+}
+
+impl Peers {
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = Some(limit);
+        self.evict();
+    }
+
+    /// Remove the state for `peer_id`, creating a new state if we weren't tracking this peer
+    ///
+    /// The state should be handed back with [`Self::put`] once it has been used.
+    pub(crate) fn take(&mut self, peer_id: &str) -> State {
+        self.states
+            .remove(peer_id)
+            .map(|p| p.state)
+            .unwrap_or_default()
+    }
+
+    /// The state for `peer_id`, if we are tracking this peer
+    pub(crate) fn get(&self, peer_id: &str) -> Option<&State> {
+        self.states.get(peer_id).map(|p| &p.state)
+    }
+
+    /// Store the state for `peer_id`, marking it as the most recently active peer
+    pub(crate) fn put(&mut self, peer_id: &str, state: State) {
+        self.tick += 1;
+        self.states.insert(
+            peer_id.to_string(),
+            Peer {
+                state,
+                last_active: self.tick,
+            },
+        );
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        while self.states.len() > limit {
+            let oldest = self
+                .states
+                .iter()
+                .min_by_key(|(_, p)| p.last_active)
+                .map(|(id, _)| id.clone());
+            if let Some(id) = oldest {
+                tracing::trace!(peer_id=%id, "evicting sync state");
+                self.states.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_active_peer() {
+        let mut peers = Peers::default();
+        peers.put("a", State::new());
+        peers.put("b", State::new());
+        peers.put("c", State::new());
+
+        // touch "a" so that "b" becomes the oldest
+        let a = peers.take("a");
+        peers.put("a", a);
+
+        peers.set_limit(2);
+        assert!(peers.states.contains_key("a"));
+        assert!(!peers.states.contains_key("b"));
+        assert!(peers.states.contains_key("c"));
+
+        peers.put("d", State::new());
+        assert!(!peers.states.contains_key("c"));
+        assert!(peers.states.contains_key("a"));
+        assert!(peers.states.contains_key("d"));
+    }
+}
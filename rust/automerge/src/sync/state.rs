@@ -6,6 +6,10 @@ use crate::ChangeHash;
 
 const SYNC_STATE_TYPE: u8 = 0x43; // first byte of an encoded sync state, for identification
 
+/// The format version written by [`State::encode_versioned`]. Versions must never be equal to
+/// `SYNC_STATE_TYPE` so that unversioned encodings can be told apart from versioned ones.
+const SYNC_STATE_VERSION: u8 = 0;
+
 #[derive(Debug, thiserror::Error)]
 pub enum DecodeError {
     #[error("{0:?}")]
@@ -14,6 +18,8 @@ pub enum DecodeError {
     WrongType { expected_one_of: Vec<u8>, found: u8 },
     #[error("not enough input")]
     NotEnoughInput,
+    #[error("unsupported sync state version: {0}")]
+    UnsupportedVersion(u8),
 }
 
 impl From<parse::leb128::Error> for DecodeError {
@@ -69,6 +75,41 @@ impl State {
         Default::default()
     }
 
+    /// A state for syncing with a peer which is known to have an empty document
+    ///
+    /// This is the state we would be in had the peer already told us that it has no changes, so
+    /// the first message generated sends every change rather than first exchanging bloom filters
+    /// to find out which changes the peer is missing, saving two round trips. Once we hear from
+    /// the peer the normal protocol carries on. If the peer does have changes the ones it already
+    /// has are sent anyway, which is wasteful but harmless.
+    pub fn new_empty_peer() -> Self {
+        Self {
+            their_heads: Some(Vec::new()),
+            their_need: Some(Vec::new()),
+            their_have: Some(vec![Have::default()]),
+            ..Default::default()
+        }
+    }
+
+    /// Forget everything about the current session with the peer, ready for it to reconnect
+    ///
+    /// This leaves the state as it would be after a round trip through [`Self::encode`] and
+    /// [`Self::decode`], keeping [`Self::shared_heads`] but discarding what we know about the
+    /// peer's heads and bloom filters and what we have sent to it, including the assumption made by
+    /// [`Self::new_empty_peer`]. Buffers are cleared rather than freed, so they can be reused by
+    /// the next session.
+    pub fn reset(&mut self) {
+        self.last_sent_heads.clear();
+        self.their_heads = None;
+        self.their_need = None;
+        match &mut self.their_have {
+            Some(have) => have.clear(),
+            None => self.their_have = Some(Vec::new()),
+        }
+        self.sent_hashes.clear();
+        self.in_flight = false;
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut buf = vec![SYNC_STATE_TYPE];
         encode_hashes(&mut buf, &self.shared_heads);
@@ -84,6 +125,33 @@ impl State {
         }
     }
 
+    /// Like [`Self::encode`] but prefixed with a format version
+    ///
+    /// Use [`Self::decode_versioned`] to decode the result. Later versions of this library will
+    /// refuse to decode a version they don't understand rather than misinterpreting it.
+    pub fn encode_versioned(&self) -> Vec<u8> {
+        let mut buf = vec![SYNC_STATE_VERSION];
+        buf.extend(self.encode());
+        buf
+    }
+
+    /// Decode the output of [`Self::encode_versioned`]
+    ///
+    /// The output of [`Self::encode`] is also accepted and treated as version 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecodeError::UnsupportedVersion`] if the input was encoded with a version this
+    /// library doesn't know about.
+    pub fn decode_versioned(input: &[u8]) -> Result<Self, DecodeError> {
+        match input.first() {
+            None => Err(DecodeError::NotEnoughInput),
+            Some(&SYNC_STATE_TYPE) => Self::decode(input),
+            Some(&SYNC_STATE_VERSION) => Self::decode(&input[1..]),
+            Some(&version) => Err(DecodeError::UnsupportedVersion(version)),
+        }
+    }
+
     pub(crate) fn parse(input: parse::Input<'_>) -> parse::ParseResult<'_, Self, DecodeError> {
         let (i, record_type) = parse::take1(input)?;
         if record_type != SYNC_STATE_TYPE {
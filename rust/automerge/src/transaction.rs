@@ -1,13 +1,20 @@
+mod clone;
 mod commit;
 mod inner;
 mod manual_transaction;
+mod observer;
 mod result;
+pub(crate) mod text_diff;
 mod transactable;
 
+pub(crate) use self::clone::Snapshot;
 pub use self::commit::CommitOptions;
-pub use self::transactable::Transactable;
+pub use self::text_diff::TextOp;
+pub use self::transactable::{Alignment, Transactable};
 pub(crate) use inner::{TransactionArgs, TransactionInner};
 pub use manual_transaction::Transaction;
+pub use observer::OpObserver;
+pub(crate) use observer::TxObserver;
 pub use result::Failure;
 pub use result::Success;
 
@@ -0,0 +1,157 @@
+use crate::exid::ExId;
+use crate::marks::{ExpandMark, Mark};
+use crate::{AutomergeError, ObjType, Prop, ReadDoc, ScalarValue, Value};
+
+use super::Transactable;
+
+/// The contents of an object read out of a document so that they can be written somewhere else.
+///
+/// We read the whole tree before writing anything so that copying an object into one of its own
+/// descendants doesn't end up copying the copy.
+#[derive(Debug)]
+pub(crate) enum Snapshot {
+    Scalar(ScalarValue),
+    Map(ObjType, Vec<(String, Snapshot)>),
+    List(Vec<Snapshot>),
+    Text(String, Vec<Mark<'static>>),
+}
+
+impl Snapshot {
+    pub(crate) fn read<R: ReadDoc + ?Sized>(doc: &R, obj: &ExId) -> Result<Self, AutomergeError> {
+        let typ = doc.object_type(obj)?;
+        Ok(match typ {
+            ObjType::Map | ObjType::Table => Snapshot::Map(
+                typ,
+                doc.map_range(obj, ..)
+                    .map(|item| {
+                        Ok((
+                            item.key.to_string(),
+                            Self::read_item(doc, item.value, &item.id)?,
+                        ))
+                    })
+                    .collect::<Result<_, AutomergeError>>()?,
+            ),
+            ObjType::List => Snapshot::List(
+                doc.list_range(obj, ..)
+                    .map(|item| Self::read_item(doc, item.value, &item.id))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ObjType::Text => Snapshot::Text(
+                doc.text(obj)?,
+                doc.marks(obj)?.into_iter().map(Mark::into_owned).collect(),
+            ),
+        })
+    }
+
+    /// A snapshot of `value`, which has the id `id`
+    pub(crate) fn read_item<R: ReadDoc + ?Sized>(
+        doc: &R,
+        value: Value<'_>,
+        id: &ExId,
+    ) -> Result<Self, AutomergeError> {
+        match value {
+            Value::Object(_) => Self::read(doc, id),
+            Value::Scalar(s) => Ok(Snapshot::Scalar(s.into_owned().detached())),
+        }
+    }
+
+    fn obj_type(&self) -> Option<ObjType> {
+        match self {
+            Snapshot::Scalar(_) => None,
+            Snapshot::Map(typ, _) => Some(*typ),
+            Snapshot::List(_) => Some(ObjType::List),
+            Snapshot::Text(..) => Some(ObjType::Text),
+        }
+    }
+
+    /// Write this snapshot to `prop` in `obj`, returning the ID of the new object if this is a
+    /// snapshot of an object
+    pub(crate) fn put<T: Transactable + ?Sized>(
+        self,
+        tx: &mut T,
+        obj: &ExId,
+        prop: Prop,
+    ) -> Result<Option<ExId>, AutomergeError> {
+        match self.obj_type() {
+            Some(typ) => {
+                let new_obj = tx.put_object(obj, prop, typ)?;
+                self.write_contents(tx, &new_obj)?;
+                Ok(Some(new_obj))
+            }
+            None => {
+                if let Snapshot::Scalar(s) = self {
+                    tx.put(obj, prop, s)?;
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Write this snapshot to `prop` in `obj`, returning the ID of the new value whether or not
+    /// it is an object
+    pub(crate) fn put_value<T: Transactable + ?Sized>(
+        self,
+        tx: &mut T,
+        obj: &ExId,
+        prop: Prop,
+    ) -> Result<ExId, AutomergeError> {
+        match self.put(tx, obj, prop.clone())? {
+            Some(id) => Ok(id),
+            None => Ok(tx
+                .get(obj, prop)?
+                .map(|(_, id)| id)
+                .expect("a value which was just put exists")),
+        }
+    }
+
+    /// Insert this snapshot into the list `obj` at `index`
+    pub(crate) fn insert<T: Transactable + ?Sized>(
+        self,
+        tx: &mut T,
+        obj: &ExId,
+        index: usize,
+    ) -> Result<(), AutomergeError> {
+        match self.obj_type() {
+            Some(typ) => {
+                let new_obj = tx.insert_object(obj, index, typ)?;
+                self.write_contents(tx, &new_obj)
+            }
+            None => {
+                if let Snapshot::Scalar(s) = self {
+                    tx.insert(obj, index, s)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the contents of this snapshot into the (empty) object `obj`
+    fn write_contents<T: Transactable + ?Sized>(
+        self,
+        tx: &mut T,
+        obj: &ExId,
+    ) -> Result<(), AutomergeError> {
+        match self {
+            Snapshot::Scalar(_) => Ok(()),
+            Snapshot::Map(_, entries) => {
+                for (key, value) in entries {
+                    value.put(tx, obj, key.into())?;
+                }
+                Ok(())
+            }
+            Snapshot::List(items) => {
+                for (index, value) in items.into_iter().enumerate() {
+                    value.insert(tx, obj, index)?;
+                }
+                Ok(())
+            }
+            Snapshot::Text(text, marks) => {
+                tx.splice_text(obj, 0, 0, &text)?;
+                for mark in marks {
+                    tx.mark(obj, mark, ExpandMark::default())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
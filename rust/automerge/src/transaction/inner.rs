@@ -108,8 +108,10 @@ impl TransactionInner {
             let ops = change.iter_ops().collect::<Vec<_>>();
             tracing::trace!(commit=?hash, ?ops, deps=?change.deps(), "committing transaction");
         }
+        let before = doc.observed_heads();
         doc.update_history(change, num_ops);
         debug_assert_eq!(doc.get_heads(), vec![hash]);
+        doc.notify_observers(before);
         hash
     }
 
@@ -149,11 +151,34 @@ impl TransactionInner {
 
     /// Undo the operations added in this transaction, returning the number of cancelled
     /// operations.
-    pub(crate) fn rollback(self, doc: &mut Automerge) -> usize {
+    ///
+    /// If `observer` is given it is called with the object, property and action of each operation
+    /// just before it is removed, most recent operation first. An operation on a sequence element
+    /// which can't be found in the document is removed without calling `observer`.
+    pub(crate) fn rollback(
+        self,
+        doc: &mut Automerge,
+        mut observer: Option<&mut dyn FnMut(ExId, Prop, OpType)>,
+    ) -> usize {
         let num = self.pending_ops();
         // remove in reverse order so sets are removed before makes etc...
         let encoding = ListEncoding::List; // encoding doesnt matter here - we dont care what the index is
         for (obj, op) in self.operations.into_iter().rev() {
+            if let Some(observer) = observer.as_mut() {
+                // the document is in the state just after `op` was applied, so this is the
+                // index the op was made at
+                let prop = match op.key {
+                    Key::Map(key) => Some(Prop::Map(doc.ops().m.props.get(key).clone())),
+                    Key::Seq(_) => op
+                        .elemid()
+                        .and_then(|e| doc.ops().seek_opid(&obj, e.0, None))
+                        .map(|found| Prop::Seq(found.index)),
+                };
+                // an element which can't be found has no index to report, so it isn't observed
+                if let Some(prop) = prop {
+                    observer(doc.id_to_exid(obj.0), prop, op.action.clone());
+                }
+            }
             for pred_id in &op.pred {
                 if let Some(p) = doc
                     .ops()
@@ -211,6 +236,30 @@ impl TransactionInner {
         Ok(())
     }
 
+    /// Set each key of the map `obj` to the corresponding value, see
+    /// [`crate::transaction::Transactable::put_all`]
+    pub(crate) fn put_all<I: IntoIterator<Item = (String, ScalarValue)>>(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        entries: I,
+    ) -> Result<(), AutomergeError> {
+        let obj = doc.exid_to_obj(ex_obj)?;
+        if obj.typ != ObjType::Map {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        // The ops of a map are sorted by key so visiting the keys in the same order means
+        // consecutive searches walk the same nodes of the op tree. The sort is stable so repeated
+        // keys keep their order and the last value wins, as it would with separate puts.
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (key, value) in entries {
+            self.local_map_op(doc, patch_log, obj.id, key, value.into())?;
+        }
+        Ok(())
+    }
+
     /// Set the value of property `P` to value `V` in object `obj`.
     ///
     /// # Returns
@@ -406,7 +455,7 @@ impl TransactionInner {
 
         // increment operations are only valid against counter values.
         // if there are multiple values (from conflicts) then we just need one of them to be a counter.
-        if matches!(action, OpType::Increment(_)) && ops.iter().all(|op| !op.is_counter()) {
+        if action.is_increment() && ops.iter().all(|op| !op.is_counter()) {
             return Err(AutomergeError::MissingCounter);
         }
 
@@ -449,7 +498,7 @@ impl TransactionInner {
 
         // increment operations are only valid against counter values.
         // if there are multiple values (from conflicts) then we just need one of them to be a counter.
-        if matches!(action, OpType::Increment(_)) && query.ops.iter().all(|op| !op.is_counter()) {
+        if action.is_increment() && query.ops.iter().all(|op| !op.is_counter()) {
             return Err(AutomergeError::MissingCounter);
         }
 
@@ -545,6 +594,39 @@ impl TransactionInner {
         )
     }
 
+    /// Insert several values into a sequence, returning the ID of each inserted element
+    pub(crate) fn list_insert_many(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        index: usize,
+        vals: impl IntoIterator<Item = ScalarValue>,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        let obj = doc.exid_to_obj(ex_obj)?;
+        if !matches!(obj.typ, ObjType::List | ObjType::Text) {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        let values: Vec<_> = vals.into_iter().collect();
+        let count = values.len() as u64;
+        // inner_splice inserts each value with the next op ID in turn
+        let first = self.next_id();
+        self.inner_splice(
+            doc,
+            patch_log,
+            SpliceArgs {
+                obj: obj.id,
+                index,
+                del: 0,
+                values,
+                splice_type: SpliceType::List,
+            },
+        )?;
+        Ok((0..count)
+            .map(|i| doc.id_to_exid(OpId::new(first.counter() + i, first.actor())))
+            .collect())
+    }
+
     /// Splice string into a text object
     pub(crate) fn splice_text(
         &mut self,
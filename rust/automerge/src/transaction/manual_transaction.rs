@@ -5,9 +5,11 @@ use crate::iter::{Keys, ListRange, MapRange, Values};
 use crate::marks::{ExpandMark, Mark};
 use crate::patches::PatchLog;
 use crate::AutomergeError;
-use crate::{Automerge, ChangeHash, Cursor, ObjType, Prop, ReadDoc, ScalarValue, Value};
+use crate::{Automerge, ChangeHash, Cursor, ObjType, OpType, Prop, ReadDoc, ScalarValue, Value};
 
-use super::{CommitOptions, Transactable, TransactionArgs, TransactionInner};
+use super::{
+    CommitOptions, OpObserver, Transactable, TransactionArgs, TransactionInner, TxObserver,
+};
 
 /// A transaction on a document.
 /// Transactions group operations into a single change so that no other operations can happen
@@ -28,6 +30,7 @@ pub struct Transaction<'a> {
     inner: Option<TransactionInner>,
     patch_log: PatchLog,
     doc: &'a mut Automerge,
+    observer: Option<TxObserver<'a>>,
 }
 
 impl<'a> Transaction<'a> {
@@ -36,6 +39,7 @@ impl<'a> Transaction<'a> {
             inner: Some(TransactionInner::new(args)),
             doc,
             patch_log,
+            observer: None,
         }
     }
 }
@@ -51,6 +55,18 @@ impl<'a> Transaction<'a> {
 }
 
 impl<'a> Transaction<'a> {
+    /// Call `observer` for each operation as it is applied by this transaction
+    ///
+    /// See [`OpObserver`]. This replaces any observer set before. The observer is dropped when
+    /// the transaction is committed or rolled back, and isn't told about a rollback. If this
+    /// transaction was created with [`Automerge::transaction_log_patches`] and an active
+    /// [`PatchLog`] then text is reported in the representation of that log.
+    pub fn with_observer<Obs: OpObserver + 'a>(mut self, observer: Obs) -> Self {
+        self.stop_observing();
+        self.observer = Some(TxObserver::new(observer, &mut self.patch_log));
+        self
+    }
+
     /// Get the heads of the document before this transaction was started.
     pub fn get_heads(&self) -> Vec<ChangeHash> {
         self.doc.get_heads()
@@ -59,6 +75,7 @@ impl<'a> Transaction<'a> {
     /// Commit the operations performed in this transaction, returning the hashes corresponding to
     /// the new heads.
     pub fn commit(mut self) -> (Option<ChangeHash>, PatchLog) {
+        self.stop_observing();
         let tx = self.inner.take().unwrap();
         let hash = tx.commit(self.doc, None, None);
         // TODO - remove this clone
@@ -82,6 +99,7 @@ impl<'a> Transaction<'a> {
     /// tx.commit_with(CommitOptions::default().with_message("Create todos list").with_time(now));
     /// ```
     pub fn commit_with(mut self, options: CommitOptions) -> (Option<ChangeHash>, PatchLog) {
+        self.stop_observing();
         let tx = self.inner.take().unwrap();
         let hash = tx.commit(self.doc, options.message, options.time);
         // TODO - remove this clone
@@ -91,7 +109,28 @@ impl<'a> Transaction<'a> {
     /// Undo the operations added in this transaction, returning the number of cancelled
     /// operations.
     pub fn rollback(mut self) -> usize {
-        self.inner.take().unwrap().rollback(self.doc)
+        self.inner.take().unwrap().rollback(self.doc, None)
+    }
+
+    /// Like [`Self::rollback`] but call `observer` for each discarded operation
+    ///
+    /// `observer` receives the object, property and action of each operation, starting with the
+    /// most recent. For sequences the property is the index the operation was made at, and an
+    /// operation whose element can't be found, so has no index, is discarded without calling
+    /// `observer`. This allows optimistic updates which were shown for the discarded operations
+    /// to be reverted.
+    pub fn rollback_with<F: FnMut(ExId, Prop, OpType)>(mut self, mut observer: F) -> usize {
+        self.inner
+            .take()
+            .unwrap()
+            .rollback(self.doc, Some(&mut observer))
+    }
+
+    /// Drop the observer, leaving the patch log as active as it was before the observer was set
+    fn stop_observing(&mut self) {
+        if let Some(observer) = self.observer.take() {
+            observer.finish(&mut self.patch_log);
+        }
     }
 
     fn do_tx<F, O>(&mut self, f: F) -> O
@@ -99,7 +138,12 @@ impl<'a> Transaction<'a> {
         F: FnOnce(&mut TransactionInner, &mut Automerge, &mut PatchLog) -> O,
     {
         let tx = self.inner.as_mut().unwrap();
-        f(tx, self.doc, &mut self.patch_log)
+        let start = self.patch_log.num_events();
+        let result = f(tx, self.doc, &mut self.patch_log);
+        if let Some(observer) = &mut self.observer {
+            observer.observe(self.doc, &mut self.patch_log, start);
+        }
+        result
     }
 }
 
@@ -246,6 +290,10 @@ impl<'a> ReadDoc for Transaction<'a> {
         self.doc.parents(obj)
     }
 
+    fn count_conflicts(&self) -> usize {
+        self.doc.count_conflicts()
+    }
+
     fn parents_at<O: AsRef<ExId>>(
         &self,
         obj: O,
@@ -286,6 +334,14 @@ impl<'a> Transactable for Transaction<'a> {
         self.do_tx(|tx, doc, hist| tx.put(doc, hist, obj.as_ref(), prop, value))
     }
 
+    fn put_all<O: AsRef<ExId>, I: IntoIterator<Item = (String, ScalarValue)>>(
+        &mut self,
+        obj: O,
+        entries: I,
+    ) -> Result<(), AutomergeError> {
+        self.do_tx(|tx, doc, hist| tx.put_all(doc, hist, obj.as_ref(), entries))
+    }
+
     fn put_object<O: AsRef<ExId>, P: Into<Prop>>(
         &mut self,
         obj: O,
@@ -342,6 +398,15 @@ impl<'a> Transactable for Transaction<'a> {
         self.do_tx(|tx, doc, hist| tx.splice(doc, hist, obj.as_ref(), pos, del, vals))
     }
 
+    fn list_insert_many<O: AsRef<ExId>, V: IntoIterator<Item = ScalarValue>>(
+        &mut self,
+        obj: O,
+        index: usize,
+        vals: V,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        self.do_tx(|tx, doc, hist| tx.list_insert_many(doc, hist, obj.as_ref(), index, vals))
+    }
+
     fn splice_text<O: AsRef<ExId>>(
         &mut self,
         obj: O,
@@ -381,10 +446,17 @@ impl<'a> Transactable for Transaction<'a> {
 // intermediate state.
 // This defaults to rolling back the transaction to be compatible with `?` error returning before
 // reaching a call to `commit`.
+#[cfg(feature = "json")]
+impl<'a> crate::automerge::quill::QuillTarget for Transaction<'a> {
+    fn doc(&self) -> &Automerge {
+        self.doc
+    }
+}
+
 impl<'a> Drop for Transaction<'a> {
     fn drop(&mut self) {
         if let Some(txn) = self.inner.take() {
-            txn.rollback(self.doc);
+            txn.rollback(self.doc, None);
         }
     }
 }
@@ -0,0 +1,126 @@
+use std::fmt;
+
+use crate::exid::ExId;
+use crate::marks::Mark;
+use crate::patches::{Event, PatchLog, TextRepresentation};
+use crate::types::ObjId;
+use crate::{Automerge, Prop, Value};
+
+/// Receives each operation as it is applied by a [`crate::transaction::Transaction`]
+///
+/// Set with [`crate::transaction::Transaction::with_observer`]. The methods are called
+/// synchronously, in the order the operations are applied, so a secondary index can be kept up to
+/// date as the transaction is built rather than by diffing the document afterwards. Indices are
+/// those at the time of the operation. Operations which don't change the document, such as
+/// putting the value a property already has, are not reported.
+pub trait OpObserver {
+    /// `prop` of `obj` was set to `value`
+    fn put(&mut self, obj: ExId, prop: Prop, value: Value<'_>);
+
+    /// `value` was inserted into the sequence `obj` at `index`
+    fn insert(&mut self, obj: ExId, index: usize, value: Value<'_>);
+
+    /// The value of `prop` was deleted from `obj`
+    ///
+    /// A deletion of several elements of a sequence is reported once for each element.
+    fn delete(&mut self, obj: ExId, prop: Prop);
+
+    /// The counter at `prop` of `obj` was incremented by `by`
+    fn increment(&mut self, obj: ExId, prop: Prop, by: i64);
+
+    /// `mark` was applied to the sequence `obj`
+    fn mark(&mut self, obj: ExId, mark: &Mark<'_>);
+
+    /// `text` was inserted into the text object `obj` at `index`
+    fn splice_text(&mut self, obj: ExId, index: usize, text: &str);
+}
+
+/// The observer of a transaction
+///
+/// Operations are observed by reading the events they add to the transaction's [`PatchLog`]. If
+/// the transaction wasn't already recording patches the log is activated for the observer, with
+/// text recorded as splices, and the events are discarded once they have been delivered.
+pub(crate) struct TxObserver<'a> {
+    observer: Box<dyn OpObserver + 'a>,
+    /// How the patch log was set up before the observer was added, or `None` if it was already
+    /// recording patches
+    previous: Option<TextRepresentation>,
+}
+
+impl<'a> TxObserver<'a> {
+    pub(crate) fn new<Obs: OpObserver + 'a>(observer: Obs, patch_log: &mut PatchLog) -> Self {
+        let previous = if patch_log.is_active() {
+            None
+        } else {
+            let text_rep = patch_log.text_rep();
+            patch_log.set_active(true);
+            patch_log.set_text_rep(TextRepresentation::String);
+            Some(text_rep)
+        };
+        TxObserver {
+            observer: Box::new(observer),
+            previous,
+        }
+    }
+
+    /// Deliver the events recorded in `patch_log` since the first `start` and discard them if the
+    /// log was only recording them for the observer
+    pub(crate) fn observe(&mut self, doc: &Automerge, patch_log: &mut PatchLog, start: usize) {
+        self.notify(doc, patch_log.events_since(start));
+        if self.previous.is_some() {
+            patch_log.truncate_events(start);
+        }
+    }
+
+    /// Put `patch_log` back the way it was before the observer was added
+    pub(crate) fn finish(self, patch_log: &mut PatchLog) {
+        if let Some(text_rep) = self.previous {
+            patch_log.set_active(false);
+            patch_log.set_text_rep(text_rep);
+        }
+    }
+
+    /// Deliver the patch log `events` of operations on `doc`
+    fn notify(&mut self, doc: &Automerge, events: &[(ObjId, Event)]) {
+        for (obj, event) in events {
+            let obj = doc.id_to_exid(obj.0);
+            let observer = self.observer.as_mut();
+            match event {
+                Event::PutMap { key, value, .. } => {
+                    observer.put(obj, Prop::Map(key.clone()), value.into())
+                }
+                Event::PutSeq { index, value, .. } => {
+                    observer.put(obj, Prop::Seq(*index), value.into())
+                }
+                Event::Insert { index, value, .. } => observer.insert(obj, *index, value.into()),
+                Event::DeleteMap { key } => observer.delete(obj, Prop::Map(key.clone())),
+                Event::DeleteSeq { index, num } => {
+                    for _ in 0..*num {
+                        observer.delete(obj.clone(), Prop::Seq(*index));
+                    }
+                }
+                Event::IncrementMap { key, n, .. } => {
+                    observer.increment(obj, Prop::Map(key.clone()), *n)
+                }
+                Event::IncrementSeq { index, n, .. } => {
+                    observer.increment(obj, Prop::Seq(*index), *n)
+                }
+                Event::Splice { index, text } => observer.splice_text(obj, *index, text),
+                Event::Mark { mark } => {
+                    for mark in mark {
+                        observer.mark(obj.clone(), mark);
+                    }
+                }
+                Event::FlagConflictMap { .. } | Event::FlagConflictSeq { .. } => {}
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Debug for TxObserver<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TxObserver")
+            .field("previous", &self.previous)
+            .finish()
+    }
+}
@@ -0,0 +1,343 @@
+/// A run of the old text which should be replaced with `insert`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Hunk {
+    /// The index in the old text of the first character to delete
+    pub(crate) start: usize,
+    /// The number of characters to delete
+    pub(crate) delete: usize,
+    /// The characters to insert in place of the deleted ones
+    pub(crate) insert: String,
+}
+
+/// An edit to a piece of text, see [`crate::Automerge::text_diff_myers`]
+///
+/// Lengths are in characters, that is Unicode scalar values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextOp {
+    /// Keep the next `n` characters
+    Retain(usize),
+    /// Insert a string at the current position
+    Insert(String),
+    /// Delete the next `n` characters
+    Delete(usize),
+}
+
+/// The [`TextOp`]s which turn `old` into `new`
+///
+/// Consecutive ops are never of the same kind and there is no trailing [`TextOp::Retain`].
+pub(crate) fn text_ops(old: &str, new: &str) -> Vec<TextOp> {
+    let old = old.chars().collect::<Vec<_>>();
+    let new = new.chars().collect::<Vec<_>>();
+    let mut ops = Vec::new();
+    let mut pos = 0;
+    for hunk in diff(&old, &new) {
+        if hunk.start > pos {
+            ops.push(TextOp::Retain(hunk.start - pos));
+        }
+        if hunk.delete > 0 {
+            ops.push(TextOp::Delete(hunk.delete));
+        }
+        if !hunk.insert.is_empty() {
+            ops.push(TextOp::Insert(hunk.insert));
+        }
+        pos = hunk.start + hunk.delete;
+    }
+    ops
+}
+
+/// Past this many edits the diff gives up and replaces everything between the common prefix and
+/// suffix in one hunk. Finding the edits takes time proportional to their number multiplied by
+/// the length of the texts, which would otherwise be prohibitive for two large, unrelated texts.
+const MAX_EDIT_DISTANCE: usize = 1024;
+
+/// Compute a minimal set of hunks which turn `old` into `new` using Myers' diff algorithm
+///
+/// The hunks are in ascending order of `start` and do not overlap, so applying them in reverse
+/// order leaves the indices of the hunks still to be applied unchanged.
+pub(crate) fn diff(old: &[char], new: &[char]) -> Vec<Hunk> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    if a.is_empty() && b.is_empty() {
+        return Vec::new();
+    }
+    let edits = match shortest_edit(a, b) {
+        Some(edits) => edits,
+        None => {
+            return vec![Hunk {
+                start: prefix,
+                delete: a.len(),
+                insert: b.iter().collect(),
+            }]
+        }
+    };
+
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut old_index = prefix;
+    let mut in_hunk = false;
+    for edit in edits {
+        match edit {
+            Edit::Equal => {
+                old_index += 1;
+                in_hunk = false;
+            }
+            Edit::Delete => {
+                if !in_hunk {
+                    hunks.push(Hunk {
+                        start: old_index,
+                        delete: 0,
+                        insert: String::new(),
+                    });
+                    in_hunk = true;
+                }
+                // SAFETY: we just pushed a hunk if there wasn't one in progress
+                hunks.last_mut().unwrap().delete += 1;
+                old_index += 1;
+            }
+            Edit::Insert(c) => {
+                if !in_hunk {
+                    hunks.push(Hunk {
+                        start: old_index,
+                        delete: 0,
+                        insert: String::new(),
+                    });
+                    in_hunk = true;
+                }
+                hunks.last_mut().unwrap().insert.push(c);
+            }
+        }
+    }
+    hunks
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert(char),
+}
+
+/// The edit script for `a` to `b`, in order, or `None` if it is longer than
+/// [`MAX_EDIT_DISTANCE`]
+///
+/// This is the linear space variant of Myers' algorithm: the middle snake of an optimal path is
+/// found by searching from both ends at once, then the parts of the texts before and after it
+/// are diffed in turn. Only two vectors of furthest reaching points are kept, so the memory used
+/// is proportional to the length of the texts rather than the square of the edit distance.
+fn shortest_edit(a: &[char], b: &[char]) -> Option<Vec<Edit>> {
+    let (x, y, u, v) = middle_snake(a, b, (MAX_EDIT_DISTANCE + 1) / 2)?;
+    let mut edits = Vec::with_capacity(a.len().max(b.len()));
+    edit_script(&a[..x], &b[..y], &mut edits);
+    edits.extend(std::iter::repeat(Edit::Equal).take(u - x));
+    edit_script(&a[u..], &b[v..], &mut edits);
+    Some(edits)
+}
+
+/// Append the edit script for `a` to `b` to `edits`
+fn edit_script(a: &[char], b: &[char], edits: &mut Vec<Edit>) {
+    let prefix = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    edits.extend(std::iter::repeat(Edit::Equal).take(prefix));
+    let a = &a[prefix..a.len() - suffix];
+    let b = &b[prefix..b.len() - suffix];
+    if a.is_empty() {
+        edits.extend(b.iter().map(|c| Edit::Insert(*c)));
+    } else if b.is_empty() {
+        edits.extend(std::iter::repeat(Edit::Delete).take(a.len()));
+    } else {
+        // With no common prefix or suffix and neither side empty at least two edits are needed,
+        // so both halves either side of the middle snake need fewer edits than the whole
+        let (x, y, u, v) =
+            middle_snake(a, b, usize::MAX).expect("the middle snake is found without a limit");
+        edit_script(&a[..x], &b[..y], edits);
+        edits.extend(std::iter::repeat(Edit::Equal).take(u - x));
+        edit_script(&a[u..], &b[v..], edits);
+    }
+    edits.extend(std::iter::repeat(Edit::Equal).take(suffix));
+}
+
+/// The middle snake of an optimal path from the start of `a` and `b` to their ends, as the
+/// points `(x, y)` where it starts and `(u, v)` where it ends, or `None` if the path has more
+/// than `2 * limit` edits
+fn middle_snake(a: &[char], b: &[char], limit: usize) -> Option<(usize, usize, usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = ((a.len() + b.len() + 1) / 2).min(limit) as isize;
+    let offset = max + 1;
+    // The furthest x reached on each diagonal k, searching forwards from the start and, in
+    // `backward`, searching backwards from the end with x and y counted from the end
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let start_x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let start_y = start_x - k;
+            let (mut x, mut y) = (start_x, start_y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            // The backward search has done `d - 1` rounds, reaching diagonals up to `d - 1` away
+            let reverse_k = delta - k;
+            if odd && reverse_k.abs() < d && x + backward[at(reverse_k)] >= n {
+                let point = |x: isize, y: isize| (x as usize, y as usize);
+                let (start_x, start_y) = point(start_x, start_y);
+                let (x, y) = point(x, y);
+                return Some((start_x, start_y, x, y));
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let start_x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let start_y = start_x - k;
+            let (mut x, mut y) = (start_x, start_y);
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && forward[at(forward_k)] + x >= n {
+                let point = |x: isize, y: isize| ((n - x) as usize, (m - y) as usize);
+                let (x, y) = point(x, y);
+                let (end_x, end_y) = point(start_x, start_y);
+                return Some((x, y, end_x, end_y));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, text_ops, Hunk, TextOp, MAX_EDIT_DISTANCE};
+
+    fn apply(old: &str, hunks: &[Hunk]) -> String {
+        let mut chars: Vec<char> = old.chars().collect();
+        for hunk in hunks.iter().rev() {
+            chars.splice(hunk.start..hunk.start + hunk.delete, hunk.insert.chars());
+        }
+        chars.into_iter().collect()
+    }
+
+    fn check(old: &str, new: &str) -> Vec<Hunk> {
+        let old_chars: Vec<char> = old.chars().collect();
+        let new_chars: Vec<char> = new.chars().collect();
+        let hunks = diff(&old_chars, &new_chars);
+        assert_eq!(apply(old, &hunks), new, "diffing {:?} to {:?}", old, new);
+        hunks
+    }
+
+    #[test]
+    fn identical_text_has_no_hunks() {
+        assert_eq!(check("hello", "hello"), vec![]);
+        assert_eq!(check("", ""), vec![]);
+    }
+
+    #[test]
+    fn hunks_are_minimal() {
+        assert_eq!(
+            check("the quick fox", "the quick brown fox"),
+            vec![Hunk {
+                start: 10,
+                delete: 0,
+                insert: "brown ".to_string()
+            }]
+        );
+        let hunks = check("abcabba", "cbabac");
+        let edits: usize = hunks.iter().map(|h| h.delete + h.insert.len()).sum();
+        assert_eq!(edits, 5);
+    }
+
+    #[test]
+    fn diffs_apply() {
+        check("", "new");
+        check("old", "");
+        check("kitten", "sitting");
+        check("hello world", "world hello");
+        check("añb😀c", "a😀bñc");
+        check(&"ab".repeat(100), &"ba".repeat(100));
+    }
+
+    /// The number of characters inserted and deleted by the shortest edit from `a` to `b`
+    fn edit_distance(a: &[char], b: &[char]) -> usize {
+        let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                lcs[i + 1][j + 1] = if a[i] == b[j] {
+                    lcs[i][j] + 1
+                } else {
+                    lcs[i][j + 1].max(lcs[i + 1][j])
+                };
+            }
+        }
+        a.len() + b.len() - 2 * lcs[a.len()][b.len()]
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn diffs_are_minimal(old in "[abc]{0,40}", new in "[abc]{0,40}") {
+            let hunks = check(&old, &new);
+            let edits: usize = hunks.iter().map(|h| h.delete + h.insert.chars().count()).sum();
+            let old: Vec<char> = old.chars().collect();
+            let new: Vec<char> = new.chars().collect();
+            proptest::prop_assert_eq!(edits, edit_distance(&old, &new));
+        }
+    }
+
+    #[test]
+    fn long_edits_replace_the_changed_middle() {
+        let old = format!("<{}>", "a".repeat(MAX_EDIT_DISTANCE));
+        let new = format!("<{}>", "b".repeat(MAX_EDIT_DISTANCE));
+        assert_eq!(
+            check(&old, &new),
+            vec![Hunk {
+                start: 1,
+                delete: MAX_EDIT_DISTANCE,
+                insert: "b".repeat(MAX_EDIT_DISTANCE),
+            }]
+        );
+        // Just under the limit the edits are still minimal
+        let old = format!("{}x", "a".repeat(MAX_EDIT_DISTANCE / 2));
+        let new = format!("x{}", "a".repeat(MAX_EDIT_DISTANCE / 2));
+        assert_eq!(check(&old, &new).len(), 2);
+    }
+
+    #[test]
+    fn text_ops_describe_hunks() {
+        assert_eq!(text_ops("same", "same"), vec![]);
+        assert_eq!(
+            text_ops("the quick fox", "the slow fox!"),
+            vec![
+                TextOp::Retain(4),
+                TextOp::Delete(5),
+                TextOp::Insert("slow".to_string()),
+                TextOp::Retain(4),
+                TextOp::Insert("!".to_string()),
+            ]
+        );
+        assert_eq!(text_ops("añb", "b"), vec![TextOp::Delete(2)]);
+    }
+}
@@ -1,6 +1,19 @@
 use crate::exid::ExId;
 use crate::marks::{ExpandMark, Mark};
-use crate::{AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue};
+use crate::text_value::TextValue;
+use crate::transaction::{text_diff, Snapshot};
+use crate::{AutomergeError, ChangeHash, Cursor, ObjType, Prop, ReadDoc, ScalarValue};
+
+/// Where to place existing text when padding it with [`Transactable::text_pad`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Keep the text at the start and pad after it
+    Left,
+    /// Keep the text at the end and pad before it
+    Right,
+    /// Pad on both sides of the text
+    Center,
+}
 
 /// A way of mutating a document within a single change.
 pub trait Transactable: ReadDoc {
@@ -22,6 +35,28 @@ pub trait Transactable: ReadDoc {
         value: V,
     ) -> Result<(), AutomergeError>;
 
+    /// Set each key of the map `obj` to the corresponding value
+    ///
+    /// This has the same effect as calling [`Self::put`] for each entry in turn, including
+    /// skipping puts which wouldn't change the value, which is what the default implementation
+    /// does. The transactions in this crate instead visit the keys in the order the map stores
+    /// them, which is faster when setting many keys at once.
+    ///
+    /// # Errors
+    ///
+    /// - The object does not exist or is not a map
+    /// - One of the keys is empty
+    fn put_all<O: AsRef<ExId>, I: IntoIterator<Item = (String, ScalarValue)>>(
+        &mut self,
+        obj: O,
+        entries: I,
+    ) -> Result<(), AutomergeError> {
+        for (key, value) in entries {
+            self.put(obj.as_ref(), key, value)?;
+        }
+        Ok(())
+    }
+
     /// Set the value of property `P` to the new object `V` in object `obj`.
     ///
     /// # Returns
@@ -80,6 +115,64 @@ pub trait Transactable: ReadDoc {
         vals: V,
     ) -> Result<(), AutomergeError>;
 
+    /// Delete `del` elements of the list or text `obj`, starting at `pos`
+    ///
+    /// For text `pos` and `del` are in the same units as [`Self::splice_text`]. Unlike
+    /// [`Self::splice`] this doesn't stop at the end of the sequence.
+    ///
+    /// # Errors
+    ///
+    /// - [`AutomergeError::InvalidOp`] if `obj` is not a list or text
+    /// - [`AutomergeError::InvalidIndex`] with the end of the range if the range extends past
+    ///   the end of the sequence, in which case nothing is deleted
+    fn delete_range<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        pos: usize,
+        del: usize,
+    ) -> Result<(), AutomergeError> {
+        let obj = obj.as_ref();
+        let typ = self.object_type(obj)?;
+        match typ {
+            ObjType::List | ObjType::Text => {}
+            typ => return Err(AutomergeError::InvalidOp(typ)),
+        }
+        let end = pos.saturating_add(del);
+        if end > self.length(obj) {
+            return Err(AutomergeError::InvalidIndex(end));
+        }
+        if typ == ObjType::List {
+            self.splice(obj, pos, del, std::iter::empty())
+        } else {
+            self.splice_text(obj, pos, del, "")
+        }
+    }
+
+    /// Insert each of `vals` into the list `obj`, starting at `index`
+    ///
+    /// Returns the ID of each inserted element, in order. This is equivalent to calling
+    /// [`Self::insert`] for each value, which is what the default implementation does. The
+    /// transactions in this crate instead only look up the insertion point once.
+    fn list_insert_many<O: AsRef<ExId>, V: IntoIterator<Item = ScalarValue>>(
+        &mut self,
+        obj: O,
+        index: usize,
+        vals: V,
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        let obj = obj.as_ref();
+        let mut ids = Vec::new();
+        for (offset, value) in vals.into_iter().enumerate() {
+            let index = index + offset;
+            self.insert(obj, index, value)?;
+            // The value was just inserted so it is there to read back
+            let (_, id) = self
+                .get(obj, index)?
+                .ok_or(AutomergeError::InvalidIndex(index))?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
     /// Like [`Self::splice`] but for text.
     fn splice_text<O: AsRef<ExId>>(
         &mut self,
@@ -89,6 +182,189 @@ pub trait Transactable: ReadDoc {
         text: &str,
     ) -> Result<(), AutomergeError>;
 
+    /// Like [`Self::splice_text`] but starting at the element identified by `cursor`
+    ///
+    /// The cursor is resolved to an index when the splice is made, so edits made elsewhere in the
+    /// text since the cursor was created with [`ReadDoc::get_cursor`] don't shift the splice. If
+    /// the element at the cursor has been deleted the splice starts where it used to be.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidCursor`] if `cursor` is not an element of `obj`.
+    fn splice_text_at_cursor<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        cursor: &Cursor,
+        del: usize,
+        text: &str,
+    ) -> Result<(), AutomergeError> {
+        let pos = self.get_cursor_position(obj.as_ref(), cursor, None)?;
+        self.splice_text(obj, pos, del, text)
+    }
+
+    /// Delete the characters after `max_chars` in the text object `obj`
+    ///
+    /// Returns the number of characters which were removed, this will be `0` if the text is
+    /// already at most `max_chars` long.
+    fn text_truncate<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        max_chars: usize,
+    ) -> Result<usize, AutomergeError> {
+        let obj = obj.as_ref();
+        let typ = self.object_type(obj)?;
+        if typ != ObjType::Text {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+        let len = self.length(obj);
+        if len <= max_chars {
+            return Ok(0);
+        }
+        self.splice_text(obj, max_chars, len - max_chars, "")?;
+        Ok(len - self.length(obj))
+    }
+
+    /// Copy the object `src` to `dst_prop` in `dst_obj`
+    ///
+    /// This is a deep copy, every value in the new object is created with a new op by the
+    /// current actor, as if the values had been inserted fresh. Counters are copied as their
+    /// current value and marks on text objects are copied with the default [`ExpandMark`].
+    ///
+    /// # Returns
+    ///
+    /// The id of the new object.
+    fn clone_object<S: AsRef<ExId>, O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        src: S,
+        dst_obj: O,
+        dst_prop: P,
+    ) -> Result<ExId, AutomergeError> {
+        let snapshot = Snapshot::read(self, src.as_ref())?;
+        // SAFETY: `Snapshot::read` always returns an object snapshot
+        Ok(snapshot
+            .put(self, dst_obj.as_ref(), dst_prop.into())?
+            .unwrap())
+    }
+
+    /// Copy the object `src_obj` from the document `src` to `dest_prop` in `dest_obj`
+    ///
+    /// This is [`Self::clone_object`] across documents, the structure and object types of the
+    /// source object are recreated with new ops by the current actor so nothing from the history
+    /// of `src` is carried over.
+    ///
+    /// # Returns
+    ///
+    /// The id of the new object.
+    fn graft<O: AsRef<ExId>, P: Into<Prop>, R: ReadDoc, S: AsRef<ExId>>(
+        &mut self,
+        dest_obj: O,
+        dest_prop: P,
+        src: &R,
+        src_obj: S,
+    ) -> Result<ExId, AutomergeError> {
+        let snapshot = Snapshot::read(src, src_obj.as_ref())?;
+        // SAFETY: `Snapshot::read` always returns an object snapshot
+        Ok(snapshot
+            .put(self, dest_obj.as_ref(), dest_prop.into())?
+            .unwrap())
+    }
+
+    /// Put each of `entries` into the map `obj`
+    ///
+    /// The object type and every key are checked before anything is written, so either all of
+    /// the entries are put or, if this returns an error, none of them are.
+    fn map_set_many<O, E, K, V>(&mut self, obj: O, entries: E) -> Result<(), AutomergeError>
+    where
+        O: AsRef<ExId>,
+        E: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<ScalarValue>,
+    {
+        let obj = obj.as_ref();
+        let typ = self.object_type(obj)?;
+        if typ != ObjType::Map {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        if entries.iter().any(|(key, _)| key.as_ref().is_empty()) {
+            return Err(AutomergeError::EmptyStringKey);
+        }
+        for (key, value) in entries {
+            self.put(obj, key.as_ref(), value)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the content of the text object `obj` with `new_content`
+    ///
+    /// Rather than deleting the old text and inserting the new text this diffs the two and only
+    /// splices the characters which changed, so unchanged characters keep their identity along
+    /// with any marks and cursors attached to them.
+    fn text_set<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        new_content: &str,
+    ) -> Result<(), AutomergeError> {
+        let obj = obj.as_ref();
+        let typ = self.object_type(obj)?;
+        if typ != ObjType::Text {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+        let old = self.text(obj)?.chars().collect::<Vec<_>>();
+        let new = new_content.chars().collect::<Vec<_>>();
+        // Positions in text objects are measured in `TextValue::width`, which is not always one
+        // per char, so convert char indices to positions using the width of everything before
+        let mut positions = Vec::with_capacity(old.len() + 1);
+        let mut position = 0;
+        let mut buf = [0; 4];
+        for c in &old {
+            positions.push(position);
+            position += TextValue::width(c.encode_utf8(&mut buf));
+        }
+        positions.push(position);
+        for hunk in text_diff::diff(&old, &new).into_iter().rev() {
+            let start = positions[hunk.start];
+            let delete = positions[hunk.start + hunk.delete] - start;
+            self.splice_text(obj, start, delete, &hunk.insert)?;
+        }
+        Ok(())
+    }
+
+    /// Pad the text object `obj` with `pad_char` until it is `target_len` characters long
+    ///
+    /// `align` determines where the existing text ends up. The padding is added after the text
+    /// for left alignment and before it for right alignment. Centering pads both sides, with any
+    /// odd character going on the right. The existing characters are never touched, so they keep
+    /// their marks and cursors. Text which is already at least `target_len` characters long is
+    /// left untouched.
+    fn text_pad<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        target_len: usize,
+        pad_char: char,
+        align: Alignment,
+    ) -> Result<(), AutomergeError> {
+        let obj = obj.as_ref();
+        let typ = self.object_type(obj)?;
+        if typ != ObjType::Text {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+        let len = self.length(obj);
+        if len >= target_len {
+            return Ok(());
+        }
+        let padding = target_len - len;
+        let pad = |n| std::iter::repeat(pad_char).take(n).collect::<String>();
+        match align {
+            Alignment::Left => self.splice_text(obj, len, 0, &pad(padding)),
+            Alignment::Right => self.splice_text(obj, 0, 0, &pad(padding)),
+            Alignment::Center => {
+                self.splice_text(obj, len, 0, &pad(padding - padding / 2))?;
+                self.splice_text(obj, 0, 0, &pad(padding / 2))
+            }
+        }
+    }
+
     /// Mark a sequence
     fn mark<O: AsRef<ExId>>(
         &mut self,
@@ -97,6 +373,29 @@ pub trait Transactable: ReadDoc {
         expand: ExpandMark,
     ) -> Result<(), AutomergeError>;
 
+    /// Mark the whole of the sequence `obj`
+    ///
+    /// This is [`Self::mark`] from `0` to the current length of `obj`, it does nothing if the
+    /// sequence is empty.
+    fn mark_all<O: AsRef<ExId>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        name: &str,
+        value: V,
+        expand: ExpandMark,
+    ) -> Result<(), AutomergeError> {
+        let obj = obj.as_ref();
+        let typ = self.object_type(obj)?;
+        if !typ.is_sequence() {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+        let length = self.length(obj);
+        if length == 0 {
+            return Ok(());
+        }
+        self.mark(obj, Mark::new(name.to_string(), value, 0, length), expand)
+    }
+
     /// Remove a Mark from a sequence
     fn unmark<O: AsRef<ExId>>(
         &mut self,
@@ -107,6 +406,36 @@ pub trait Transactable: ReadDoc {
         expand: ExpandMark,
     ) -> Result<(), AutomergeError>;
 
+    /// Remove every mark called `name` from the sequence `obj`
+    ///
+    /// Overlapping and adjacent marks are removed with a single [`Self::unmark`], so this creates
+    /// one operation for each separate run of marked elements.
+    fn remove_all_marks<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        name: &str,
+    ) -> Result<(), AutomergeError> {
+        let obj = obj.as_ref();
+        let mut ranges = self
+            .marks(obj)?
+            .into_iter()
+            .filter(|m| m.name() == name)
+            .map(|m| (m.start, m.end))
+            .collect::<Vec<_>>();
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        for (start, end) in merged {
+            self.unmark(obj, name, start, end, ExpandMark::None)?;
+        }
+        Ok(())
+    }
+
     /// The heads this transaction will be based on
     fn base_heads(&self) -> Vec<ChangeHash>;
 }
@@ -2,6 +2,7 @@ use crate::error;
 use crate::legacy as amp;
 use crate::text_value::TextValue;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::borrow::Cow;
 use std::cmp::Eq;
 use std::cmp::Ordering;
@@ -50,6 +51,16 @@ impl ActorId {
         ActorId(TinyVec::from(*uuid::Uuid::new_v4().as_bytes()))
     }
 
+    /// Derive a 16 byte actor ID from `seed`
+    ///
+    /// The same seed always produces the same actor ID, which is useful for tests which need
+    /// stable output across runs. The ID is the first 16 bytes of the SHA-256 hash of the seed, it
+    /// is stable but NOT cryptographically unique, use [`Self::random`] for real actors.
+    pub fn from_seed(seed: u64) -> ActorId {
+        let hash = Sha256::digest(seed.to_be_bytes());
+        ActorId::from(&hash[..16])
+    }
+
     pub fn to_bytes(&self) -> &[u8] {
         &self.0
     }
@@ -273,9 +284,35 @@ impl OpType {
         }
     }
 
-    pub(crate) fn is_mark(&self) -> bool {
+    /// Whether this is the beginning or end of a mark
+    #[inline]
+    pub fn is_mark(&self) -> bool {
         matches!(&self, OpType::MarkBegin(_, _) | OpType::MarkEnd(_))
     }
+
+    /// Whether this puts a scalar value
+    #[inline]
+    pub fn is_put(&self) -> bool {
+        matches!(&self, OpType::Put(_))
+    }
+
+    /// Whether this creates an object
+    #[inline]
+    pub fn is_make(&self) -> bool {
+        matches!(&self, OpType::Make(_))
+    }
+
+    /// Whether this deletes a value
+    #[inline]
+    pub fn is_delete(&self) -> bool {
+        matches!(&self, OpType::Delete)
+    }
+
+    /// Whether this increments a counter
+    #[inline]
+    pub fn is_increment(&self) -> bool {
+        matches!(&self, OpType::Increment(_))
+    }
 }
 
 impl From<ObjType> for OpType {
@@ -679,6 +716,16 @@ impl Op {
         }
     }
 
+    /// The Lamport timestamp of this op as its counter and the actor which created it
+    ///
+    /// `actors` are the actors of the document this op belongs to, indexed by actor index.
+    /// Timestamps compare in the same order as [`OpId::lamport_cmp`], so they can be compared
+    /// across documents whose actor indices differ.
+    #[allow(dead_code, unreachable_pub)]
+    pub fn lamport_timestamp<'a>(&self, actors: &'a [ActorId]) -> (u64, &'a ActorId) {
+        (self.id.counter(), &actors[self.id.actor()])
+    }
+
     pub(crate) fn width(&self, encoding: ListEncoding) -> usize {
         match encoding {
             ListEncoding::List => 1,
@@ -733,11 +780,11 @@ impl Op {
     }
 
     pub(crate) fn is_delete(&self) -> bool {
-        matches!(&self.action, OpType::Delete)
+        self.action.is_delete()
     }
 
     pub(crate) fn is_inc(&self) -> bool {
-        matches!(&self.action, OpType::Increment(_))
+        self.action.is_increment()
     }
 
     pub(crate) fn is_counter(&self) -> bool {
@@ -934,6 +981,75 @@ impl From<Prop> for wasm_bindgen::JsValue {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::{ActorId, Key, MarkData, ObjType, Op, OpId, OpIds, OpType, ScalarValue};
+
+    #[test]
+    fn actor_id_from_seed_is_stable() {
+        assert_eq!(ActorId::from_seed(42), ActorId::from_seed(42));
+        assert_ne!(ActorId::from_seed(42), ActorId::from_seed(43));
+        assert_eq!(ActorId::from_seed(42).to_bytes().len(), 16);
+        assert_eq!(
+            ActorId::from_seed(0).to_hex_string(),
+            "af5570f5a1810b7af78caf4bc70a660f"
+        );
+    }
+
+    #[test]
+    fn lamport_timestamps_order_like_lamport_cmp() {
+        // Actor indices are deliberately not in actor order
+        let actors = [ActorId::from(&[2][..]), ActorId::from(&[1][..])];
+        let op = |counter, actor| Op {
+            id: OpId::new(counter, actor),
+            action: OpType::Delete,
+            key: Key::Map(0),
+            succ: OpIds::empty(),
+            pred: OpIds::empty(),
+            insert: false,
+        };
+        assert_eq!(op(3, 1).lamport_timestamp(&actors), (3, &actors[1]));
+        for (a, b) in [
+            (op(1, 0), op(1, 1)),
+            (op(1, 0), op(2, 0)),
+            (op(2, 1), op(1, 0)),
+        ] {
+            assert_eq!(
+                a.lamport_timestamp(&actors)
+                    .cmp(&b.lamport_timestamp(&actors)),
+                a.id.lamport_cmp(&b.id, &actors)
+            );
+        }
+    }
+
+    #[test]
+    fn op_type_predicates_match_exactly_one_kind() {
+        let mark = MarkData {
+            name: "bold".into(),
+            value: ScalarValue::Boolean(true),
+        };
+        let kinds = [
+            OpType::Put(ScalarValue::Int(1)),
+            OpType::Make(ObjType::List),
+            OpType::Delete,
+            OpType::Increment(1),
+            OpType::MarkBegin(true, mark),
+            OpType::MarkEnd(true),
+        ];
+        for (i, kind) in kinds.iter().enumerate() {
+            let predicates = [
+                kind.is_put(),
+                kind.is_make(),
+                kind.is_delete(),
+                kind.is_increment(),
+                kind.is_mark(),
+            ];
+            assert_eq!(predicates.iter().filter(|p| **p).count(), 1, "{:?}", kind);
+            assert!(predicates[i.min(4)], "{:?}", kind);
+        }
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod gen {
     use super::{
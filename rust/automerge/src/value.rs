@@ -373,6 +373,18 @@ pub struct Counter {
 }
 
 impl Counter {
+    /// The value the counter was created with
+    pub fn initial_value(&self) -> i64 {
+        self.start
+    }
+
+    /// The sum of every increment applied to the counter
+    ///
+    /// The current value, which `i64::from` returns, is [`Self::initial_value`] plus this.
+    pub fn increment_value(&self) -> i64 {
+        self.current - self.start
+    }
+
     pub(crate) fn increment(&mut self, inc: i64, id: OpId) {
         self.current += inc;
         self.increments.push((id, inc));
@@ -478,6 +490,17 @@ impl PartialEq for Counter {
 }
 
 impl ScalarValue {
+    /// This value as it should be written to a new location
+    ///
+    /// Counters read from a document carry the history of their increments, only the current
+    /// value is meaningful elsewhere.
+    pub(crate) fn detached(self) -> ScalarValue {
+        match self {
+            ScalarValue::Counter(c) => ScalarValue::counter(c.current),
+            v => v,
+        }
+    }
+
     pub(crate) fn as_datatype(
         &self,
         datatype: DataType,
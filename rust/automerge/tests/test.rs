@@ -1,10 +1,11 @@
 use automerge::marks::{ExpandMark, Mark};
 use automerge::op_tree::B;
 use automerge::patches::TextRepresentation;
-use automerge::transaction::Transactable;
+use automerge::transaction::{CommitOptions, Transactable};
 use automerge::{
-    ActorId, AutoCommit, Automerge, AutomergeError, Change, ExpandedChange, ObjId, ObjType, Patch,
-    PatchAction, PatchLog, Prop, ReadDoc, ScalarValue, SequenceTree, Value, ROOT,
+    ActorId, ApplyReport, AutoCommit, Automerge, AutomergeError, Change, ChangeHash, ChangeOpId,
+    ExpandedChange, ObjId, ObjType, Patch, PatchAction, PatchLog, Prop, ReadDoc, SaveOptions,
+    ScalarValue, SequenceTree, Value, ROOT,
 };
 use std::fs;
 
@@ -1723,6 +1724,894 @@ fn marks() {
     assert_eq!(marks[1].value(), &ScalarValue::from(true));
 }
 
+#[test]
+fn change_op_ids_order_by_counter_then_actor() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(&[0xff][..]));
+    doc1.put(&ROOT, "key", "one").unwrap();
+    let mut doc2 = AutoCommit::new().with_actor(ActorId::from(&[0x00][..]));
+    doc2.put(&ROOT, "other", "value").unwrap();
+    doc2.put(&ROOT, "key", "two").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let ids = doc1
+        .get_all(&ROOT, "key")
+        .unwrap()
+        .into_iter()
+        .map(|(_, id)| id.op_id().unwrap())
+        .collect::<Vec<_>>();
+    // the later op was made by the lexically smaller actor but it has a higher counter
+    assert_eq!(
+        ids,
+        vec![
+            ChangeOpId {
+                counter: 1,
+                actor: ActorId::from(&[0xff][..])
+            },
+            ChangeOpId {
+                counter: 2,
+                actor: ActorId::from(&[0x00][..])
+            },
+        ]
+    );
+    assert!(ids[0] < ids[1]);
+    let (_, winner) = doc1.get(&ROOT, "key").unwrap().unwrap();
+    assert_eq!(winner.op_id().as_ref(), ids.last());
+    assert_eq!(ROOT.op_id(), None);
+}
+
+#[test]
+fn verify_change_hash_rejects_tampered_changes() {
+    let mut doc = AutoCommit::new();
+    doc.put(&ROOT, "key", "value").unwrap();
+    let change = doc.get_last_local_change().unwrap().clone();
+    let mut bytes = change.raw_bytes().to_vec();
+    assert_eq!(
+        automerge::verify_change_hash(&bytes).unwrap(),
+        change.hash()
+    );
+
+    // flip a bit in the value of the op
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    assert!(matches!(
+        automerge::verify_change_hash(&bytes),
+        Err(automerge::InvalidChange::BadChecksum)
+    ));
+}
+
+#[test]
+fn load_with_limits_stops_at_the_cap() {
+    use automerge::{LimitExceeded, LoadLimits};
+
+    // A change with a huge number of identical ops run length encodes to a handful of bytes
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    for i in 0..10_000 {
+        doc.insert(&list, i, ScalarValue::Null).unwrap();
+    }
+    let change = doc.get_last_local_change().unwrap().clone();
+    assert!(change.raw_bytes().len() < 200);
+
+    let limits = LoadLimits {
+        max_ops: Some(100),
+        ..Default::default()
+    };
+    let err = Automerge::load_with_limits(change.raw_bytes(), limits).unwrap_err();
+    assert!(matches!(
+        err,
+        AutomergeError::LimitExceeded(LimitExceeded::Ops(100))
+    ));
+    let err = Automerge::load_with_limits(&doc.save(), limits).unwrap_err();
+    assert!(matches!(
+        err,
+        AutomergeError::LimitExceeded(LimitExceeded::Ops(100))
+    ));
+
+    let limits = LoadLimits {
+        max_objects: Some(0),
+        ..Default::default()
+    };
+    let err = Automerge::load_with_limits(&doc.save(), limits).unwrap_err();
+    assert!(matches!(
+        err,
+        AutomergeError::LimitExceeded(LimitExceeded::Objects(0))
+    ));
+
+    // The text is stored in a compressed column so this trips whilst inflating it
+    let mut text_doc = AutoCommit::new();
+    let text = text_doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    text_doc
+        .splice_text(&text, 0, 0, &"a".repeat(100_000))
+        .unwrap();
+    let saved_text = text_doc.save();
+    assert!(saved_text.len() < 10_000);
+    let limits = LoadLimits {
+        max_bytes: Some(10_000),
+        ..Default::default()
+    };
+    let err = Automerge::load_with_limits(&saved_text, limits).unwrap_err();
+    assert!(matches!(
+        err,
+        AutomergeError::LimitExceeded(LimitExceeded::Bytes(10_000))
+    ));
+
+    let saved = doc.save();
+    let loaded = Automerge::load_with_limits(&saved, LoadLimits::default()).unwrap();
+    assert_eq!(loaded.length(&list), 10_000);
+}
+
+#[test]
+fn graft_copies_nested_objects_between_documents() {
+    let mut src = AutoCommit::new();
+    let fragment = src.put_object(&ROOT, "fragment", ObjType::Map).unwrap();
+    let todos = src.put_object(&fragment, "todos", ObjType::List).unwrap();
+    for title in ["one", "two"] {
+        let todo = src.insert_object(&todos, 0, ObjType::Map).unwrap();
+        src.put(&todo, "title", title).unwrap();
+        let tags = src.put_object(&todo, "tags", ObjType::List).unwrap();
+        src.insert(&tags, 0, "tag").unwrap();
+    }
+    src.put(&fragment, "count", ScalarValue::counter(1))
+        .unwrap();
+    src.increment(&fragment, "count", 2).unwrap();
+    let notes = src.put_object(&fragment, "notes", ObjType::Text).unwrap();
+    src.splice_text(&notes, 0, 0, "hello world").unwrap();
+    src.mark(
+        &notes,
+        Mark::new("bold".to_string(), true, 0, 5),
+        ExpandMark::After,
+    )
+    .unwrap();
+
+    let mut dest = AutoCommit::new();
+    dest.put(&ROOT, "other", "value").unwrap();
+    let grafted = dest
+        .graft(&ROOT, "fragment", src.document(), &fragment)
+        .unwrap();
+
+    let src_json = serde_json::to_value(automerge::AutoSerde::from(&src)).unwrap();
+    let dest_json = serde_json::to_value(automerge::AutoSerde::from(&dest)).unwrap();
+    assert_eq!(src_json["fragment"], dest_json["fragment"]);
+    assert_eq!(dest_json["other"], "value");
+
+    // everything in the graft was written by the destination actor
+    let dest_actor = dest.get_actor().clone();
+    let (_, todos_id) = dest.get(&grafted, "todos").unwrap().unwrap();
+    assert_eq!(todos_id.op_id().unwrap().actor, dest_actor);
+
+    let (_, grafted_notes) = dest.get(&grafted, "notes").unwrap().unwrap();
+    assert_eq!(
+        dest.marks(&grafted_notes).unwrap(),
+        src.marks(&notes).unwrap()
+    );
+}
+
+#[test]
+fn save_to_writes_the_same_bytes_as_save() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, &"hello ".repeat(200)).unwrap();
+    doc.put(ROOT, "count", 1).unwrap();
+
+    // an orphaned change, which is retained by default
+    let mut other = doc.fork();
+    other.put(ROOT, "a", 1).unwrap();
+    other.commit();
+    other.put(ROOT, "b", 2).unwrap();
+    let orphan = other.get_last_local_change().unwrap().clone();
+    doc.apply_changes(vec![orphan]).unwrap();
+    assert_eq!(doc.get_missing_deps(&[]).len(), 1);
+
+    let mut written = Vec::new();
+    doc.save_to(&mut written).unwrap();
+    assert_eq!(written, doc.save());
+    let mut written_nocompress = Vec::new();
+    doc.document()
+        .save_to_with_options(
+            &mut written_nocompress,
+            SaveOptions {
+                deflate: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(written_nocompress, doc.save_nocompress());
+    assert!(written_nocompress.len() > written.len());
+    assert_eq!(
+        AutoCommit::load(&written).unwrap().get_heads(),
+        doc.get_heads()
+    );
+
+    struct Broken;
+    impl std::io::Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "broken"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    assert!(matches!(doc.save_to(Broken), Err(AutomergeError::Write(_))));
+}
+
+#[test]
+fn load_from_reads_chunks_from_a_stream() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, &"hello ".repeat(200)).unwrap();
+    let mut saved = doc.save();
+    doc.put(ROOT, "count", 1).unwrap();
+    saved.extend(doc.save_incremental());
+    doc.splice_text(&text, 0, 5, "howdy").unwrap();
+    saved.extend(doc.save_incremental());
+
+    // A reader which hands out a few bytes at a time
+    struct Trickle<'a>(&'a [u8]);
+    impl<'a> std::io::Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    let mut loaded = AutoCommit::load_from(Trickle(&saved)).unwrap();
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+    assert_eq!(loaded.text(&text).unwrap(), doc.text(&text).unwrap());
+
+    assert_eq!(
+        Automerge::load_from(std::io::empty()).unwrap().get_heads(),
+        vec![]
+    );
+
+    for cut in [3, 12, saved.len() - 1] {
+        let result = Automerge::load_from(&saved[..cut]);
+        assert!(
+            matches!(&result, Err(AutomergeError::Load(e)) if e.to_string().contains("stream ended")),
+            "unexpected result for a stream cut at {}: {:?}",
+            cut,
+            result.map(|d| d.get_heads())
+        );
+    }
+
+    let mut bad_magic = saved.clone();
+    bad_magic[0] = 0;
+    assert!(Automerge::load_from(bad_magic.as_slice()).is_err());
+}
+
+#[test]
+fn change_message_and_timestamp_survive_save_and_load() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "a", 1).unwrap();
+    let hash = doc
+        .commit_with(
+            CommitOptions::default()
+                .with_message("first commit")
+                .with_time(1_700_000_000_000),
+        )
+        .unwrap();
+    doc.put(ROOT, "b", 2).unwrap();
+    let unnamed = doc.commit().unwrap();
+
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    assert_eq!(loaded.change_message(&hash), Some("first commit"));
+    assert_eq!(loaded.change_timestamp(&hash), Some(1_700_000_000_000));
+    assert_eq!(loaded.change_message(&unnamed), None);
+    assert_eq!(loaded.change_timestamp(&unnamed), Some(0));
+
+    let missing = ChangeHash([0; 32]);
+    assert_eq!(loaded.change_message(&missing), None);
+    assert_eq!(loaded.change_timestamp(&missing), None);
+}
+
+#[test]
+fn heads_are_sorted_regardless_of_application_order() {
+    let mut base = AutoCommit::new();
+    base.put(ROOT, "base", 0).unwrap();
+    base.commit();
+    let mut changes = Vec::new();
+    for i in 0..5 {
+        let mut fork = base.fork();
+        fork.put(ROOT, "key", i).unwrap();
+        changes.push(fork.get_last_local_change().unwrap().clone());
+    }
+
+    let mut forwards = base.fork();
+    let mut backwards = base.fork();
+    for change in &changes {
+        forwards.apply_changes([change.clone()]).unwrap();
+    }
+    for change in changes.iter().rev() {
+        backwards.apply_changes([change.clone()]).unwrap();
+    }
+
+    let heads = forwards.get_heads();
+    assert_eq!(heads.len(), 5);
+    assert_eq!(heads, backwards.get_heads());
+    let mut sorted = heads.clone();
+    sorted.sort();
+    assert_eq!(heads, sorted);
+    for change in &changes {
+        assert!(forwards.has_head(&change.hash()));
+    }
+    assert!(!forwards.has_head(&base.get_heads()[0]));
+}
+
+#[test]
+fn apply_changes_and_diff_returns_remote_insert() {
+    let mut doc1 = AutoCommit::new();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc1.insert(&list, 0, "a").unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork();
+    doc2.insert(&list, 1, "b").unwrap();
+    doc2.commit();
+    let change = doc2.get_last_local_change().unwrap().clone();
+
+    let patches = doc1.apply_changes_and_diff([change.clone()]).unwrap();
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].obj, list);
+    match &patches[0].action {
+        PatchAction::Insert { index, values, .. } => {
+            assert_eq!(*index, 1);
+            assert_eq!(values.len(), 1);
+            assert_eq!(values.get(0).unwrap().0, Value::from("b"));
+        }
+        other => panic!("expected an insert, got {:?}", other),
+    }
+
+    assert!(doc1.apply_changes_and_diff([change]).unwrap().is_empty());
+}
+
+#[test]
+fn apply_changes_report_counts_deferred_and_duplicate_changes() {
+    let mut source = AutoCommit::new();
+    source.put(ROOT, "a", 1).unwrap();
+    source.commit();
+    source.put(ROOT, "b", 2).unwrap();
+    source.commit();
+    source.put(ROOT, "c", 3).unwrap();
+    source.commit();
+    let changes = source
+        .get_changes(&[])
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut doc = AutoCommit::new();
+    let report = doc
+        .apply_changes_report([changes[0].clone(), changes[2].clone()])
+        .unwrap();
+    assert_eq!(
+        report,
+        ApplyReport {
+            applied: 1,
+            duplicate: 0,
+            deferred: 1
+        }
+    );
+
+    let report = doc.apply_changes_report(changes.clone()).unwrap();
+    assert_eq!(
+        report,
+        ApplyReport {
+            applied: 2,
+            duplicate: 2,
+            deferred: 0
+        }
+    );
+    assert_eq!(doc.get_heads(), source.get_heads());
+}
+
+#[test]
+fn missing_deps_for_follows_buffered_changes() {
+    let mut source = AutoCommit::new();
+    for i in 0..3 {
+        source.put(ROOT, "key", i).unwrap();
+        source.commit();
+    }
+    let changes = source
+        .get_changes(&[])
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let (first, second, third) = (&changes[0], &changes[1], &changes[2]);
+
+    let mut doc = AutoCommit::new();
+    assert_eq!(
+        doc.missing_deps_for(std::slice::from_ref(third), &[]),
+        vec![second.hash()]
+    );
+
+    doc.apply_changes([second.clone()]).unwrap();
+    let heads = doc.get_heads();
+    assert_eq!(
+        doc.missing_deps_for(std::slice::from_ref(third), &heads),
+        vec![first.hash()]
+    );
+    assert_eq!(
+        doc.missing_deps_for(&[first.clone(), third.clone()], &heads),
+        vec![]
+    );
+
+    doc.apply_changes([first.clone()]).unwrap();
+    let heads = doc.get_heads();
+    assert_eq!(
+        doc.missing_deps_for(std::slice::from_ref(third), &heads),
+        vec![]
+    );
+    let unknown = ChangeHash([7; 32]);
+    assert_eq!(
+        doc.missing_deps_for(std::slice::from_ref(third), &[unknown]),
+        vec![second.hash()]
+    );
+}
+
+#[test]
+fn missing_deps_for_counts_the_history_of_heads_as_present() {
+    let mut source = AutoCommit::new();
+    for i in 0..3 {
+        source.put(ROOT, "key", i).unwrap();
+        source.commit();
+    }
+    let changes = source
+        .get_changes(&[])
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let (first, second, third) = (&changes[0], &changes[1], &changes[2]);
+    let mut doc = AutoCommit::new();
+    doc.apply_changes([first.clone(), second.clone()]).unwrap();
+
+    // The second change is a dependency which only counts as present if the heads cover it
+    assert_eq!(
+        doc.missing_deps_for(std::slice::from_ref(third), &[second.hash()]),
+        vec![]
+    );
+    assert_eq!(
+        doc.missing_deps_for(std::slice::from_ref(third), &[first.hash()]),
+        vec![second.hash()]
+    );
+    assert_eq!(
+        doc.missing_deps_for(std::slice::from_ref(third), &[]),
+        vec![second.hash()]
+    );
+}
+
+#[test]
+fn into_arc_shares_a_readable_document() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "a").unwrap();
+    let shared = doc.document().clone().into_arc();
+    let reader = std::sync::Arc::clone(&shared);
+    let length = std::thread::spawn(move || reader.length(&list))
+        .join()
+        .unwrap();
+    assert_eq!(length, 1);
+    assert_eq!(shared.keys(ROOT).collect::<Vec<_>>(), vec!["list"]);
+}
+
+#[test]
+fn transaction_actor_authors_the_next_change() {
+    let actor = ActorId::from(&[1, 2, 3][..]);
+    let mut doc = AutoCommit::new().with_actor(actor.clone());
+    assert_eq!(doc.transaction_actor(), &actor);
+    doc.put(ROOT, "a", 1).unwrap();
+    assert_eq!(doc.transaction_actor(), &actor);
+    doc.commit();
+    let change = doc.get_last_local_change().unwrap();
+    assert_eq!(change.actor_id(), &actor);
+    assert_eq!(doc.document().transaction_actor(), &actor);
+}
+
+#[test]
+fn read_doc_is_generic_over_documents_and_transactions() {
+    fn count_keys<R: ReadDoc>(r: &R, obj: &automerge::ObjId) -> usize {
+        r.keys(obj).count()
+    }
+
+    let mut doc = Automerge::new();
+    let mut tx = doc.transaction();
+    let map = tx.put_object(ROOT, "map", ObjType::Map).unwrap();
+    tx.put(&map, "a", 1).unwrap();
+    assert_eq!(count_keys(&tx, &map), 1);
+    tx.put(&map, "b", 2).unwrap();
+    assert_eq!(count_keys(&tx, &ROOT), 1);
+    assert_eq!(count_keys(&tx, &map), 2);
+    tx.commit();
+    assert_eq!(count_keys(&doc, &map), 2);
+}
+
+#[test]
+fn length_at_uses_the_historical_clock() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.insert(&list, 0, "a").unwrap();
+    doc.insert(&list, 1, "b").unwrap();
+    doc.splice_text(&text, 0, 0, "hé").unwrap();
+    let intermediate = doc.get_heads();
+    doc.insert(&list, 2, "c").unwrap();
+    doc.splice_text(&text, 2, 0, "llo").unwrap();
+    let full = doc.get_heads();
+    doc.delete(&list, 0).unwrap();
+    doc.splice_text(&text, 0, 5, "").unwrap();
+
+    assert_eq!(doc.length_at(&list, &intermediate), 2);
+    assert_eq!(doc.length_at(&list, &full), 3);
+    assert_eq!(doc.length(&list), 2);
+    assert_eq!(doc.length_at(&text, &intermediate), 2);
+    assert_eq!(doc.length_at(&text, &full), 5);
+    assert_eq!(doc.length(&text), 0);
+}
+
+#[test]
+fn text_into_matches_text_and_reuses_the_buffer() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello wörld").unwrap();
+    doc.insert_object(&text, 5, ObjType::Map).unwrap();
+
+    let mut buf = String::with_capacity(64);
+    let capacity = buf.capacity();
+    let ptr = buf.as_ptr();
+    doc.text_into(&text, &mut buf).unwrap();
+    assert_eq!(buf.as_bytes(), doc.text(&text).unwrap().as_bytes());
+    assert!(buf.contains('\u{fffc}'));
+
+    doc.splice_text(&text, 0, 5, "bye").unwrap();
+    doc.text_into(&text, &mut buf).unwrap();
+    assert_eq!(buf, doc.text(&text).unwrap());
+    assert_eq!((buf.capacity(), buf.as_ptr()), (capacity, ptr));
+
+    let missing = ObjId::Id(100, ActorId::random(), 0);
+    assert!(doc.text_into(&missing, &mut buf).is_err());
+}
+
+#[test]
+fn counters_expose_initial_and_increment_values() {
+    let mut doc1 = AutoCommit::new();
+    doc1.put(ROOT, "count", ScalarValue::counter(10)).unwrap();
+    let mut doc2 = doc1.fork();
+    doc1.increment(ROOT, "count", 5).unwrap();
+    doc2.increment(ROOT, "count", -2).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let (value, _) = doc1.get(ROOT, "count").unwrap().unwrap();
+    match value.to_scalar() {
+        Some(ScalarValue::Counter(counter)) => {
+            assert_eq!(counter.initial_value(), 10);
+            assert_eq!(counter.increment_value(), 3);
+            assert_eq!(i64::from(counter), 13);
+        }
+        other => panic!("expected a counter, got {:?}", other),
+    }
+}
+
+#[test]
+fn increment_by_distinguishes_missing_props_from_non_counters() {
+    let mut doc = Automerge::new();
+    let list = doc
+        .transact::<_, _, AutomergeError>(|tx| {
+            tx.put(ROOT, "counter", ScalarValue::counter(1))?;
+            tx.put(ROOT, "string", "mystring")?;
+            let list = tx.put_object(ROOT, "list", ObjType::List)?;
+            tx.insert(&list, 0, ScalarValue::counter(10))?;
+            Ok(list)
+        })
+        .unwrap()
+        .result;
+
+    doc.increment_by(ROOT, "counter", 2).unwrap();
+    doc.increment_by(&list, 0, -3).unwrap();
+    assert_eq!(
+        doc.get(ROOT, "counter").unwrap().unwrap().0,
+        Value::counter(3)
+    );
+    assert_eq!(doc.get(&list, 0).unwrap().unwrap().0, Value::counter(7));
+
+    let heads = doc.get_heads();
+    assert!(matches!(
+        doc.increment_by(ROOT, "nothing", 1),
+        Err(AutomergeError::MissingProp(Prop::Map(k))) if k == "nothing"
+    ));
+    assert!(matches!(
+        doc.increment_by(&list, 1, 1),
+        Err(AutomergeError::MissingProp(Prop::Seq(1)))
+    ));
+    assert!(matches!(
+        doc.increment_by(ROOT, "string", 1),
+        Err(AutomergeError::MissingCounter)
+    ));
+    assert_eq!(doc.get_heads(), heads);
+
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "counter", ScalarValue::counter(1)).unwrap();
+    doc.increment_by(ROOT, "counter", 4).unwrap();
+    assert_eq!(
+        doc.get(ROOT, "counter").unwrap().unwrap().0,
+        Value::counter(5)
+    );
+    assert!(matches!(
+        doc.increment_by(ROOT, "nothing", 1),
+        Err(AutomergeError::MissingProp(_))
+    ));
+}
+
+#[test]
+fn nested_objects_can_be_populated_from_the_returned_ids() {
+    let mut doc = new_doc();
+    let people = doc.put_object(ROOT, "people", ObjType::List).unwrap();
+    for (index, (name, age)) in [("alice", 30), ("bob", 25)].into_iter().enumerate() {
+        let person = doc.insert_object(&people, index, ObjType::Map).unwrap();
+        doc.put(&person, "name", name).unwrap();
+        doc.put(&person, "age", age).unwrap();
+    }
+
+    assert_doc!(
+        &doc,
+        map! {
+            "people" => {
+                list![
+                    { map! { "name" => { "alice" }, "age" => { 30 } } },
+                    { map! { "name" => { "bob" }, "age" => { 25 } } },
+                ]
+            }
+        }
+    );
+}
+
+#[test]
+fn put_all_matches_repeated_puts_and_skips_noops() {
+    let entries = vec![
+        ("b".to_string(), ScalarValue::from(2)),
+        ("a".to_string(), ScalarValue::from("one")),
+        ("c".to_string(), ScalarValue::from(true)),
+        ("a".to_string(), ScalarValue::from(1)),
+    ];
+    let mut all = AutoCommit::new();
+    all.put_all(ROOT, entries.clone()).unwrap();
+    let mut looped = AutoCommit::new();
+    for (key, value) in entries.clone() {
+        looped.put(ROOT, key, value).unwrap();
+    }
+    assert_eq!(all.hydrate(None), looped.hydrate(None));
+    assert_eq!(all.get(ROOT, "a").unwrap().unwrap().0, Value::from(1));
+    all.commit();
+
+    // only the changed key creates an op
+    let mut changed = entries[2..].to_vec();
+    changed.push(("b".to_string(), ScalarValue::from(3)));
+    all.put_all(ROOT, changed).unwrap();
+    assert_eq!(all.pending_ops(), 1);
+    assert_eq!(all.get(ROOT, "b").unwrap().unwrap().0, Value::from(3));
+
+    let list = all.put_object(ROOT, "list", ObjType::List).unwrap();
+    assert!(matches!(
+        all.put_all(&list, entries),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    ));
+}
+
+#[test]
+fn delete_range_deletes_whole_sequences_and_rejects_ranges_past_the_end() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.splice(&list, 0, 0, (1..=5).map(ScalarValue::from))
+        .unwrap();
+    doc.delete_range(&list, 1, 2).unwrap();
+    assert_eq!(
+        doc.values(&list).map(|(v, _)| v).collect::<Vec<_>>(),
+        vec![Value::from(1), Value::from(4), Value::from(5)]
+    );
+
+    assert!(matches!(
+        doc.delete_range(&list, 2, 2),
+        Err(AutomergeError::InvalidIndex(4))
+    ));
+    assert_eq!(doc.length(&list), 3);
+    doc.delete_range(&list, 0, 3).unwrap();
+    assert_eq!(doc.length(&list), 0);
+
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc.delete_range(&text, 5, 6).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "hello");
+    assert!(matches!(
+        doc.delete_range(&text, 0, 6),
+        Err(AutomergeError::InvalidIndex(6))
+    ));
+    assert!(matches!(
+        doc.delete_range(ROOT, 0, 0),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    ));
+    doc.put(ROOT, "key", 1).unwrap();
+    assert!(matches!(
+        doc.delete_range(ROOT, 0, 5),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    ));
+}
+
+#[test]
+fn text_words_yields_words_with_their_character_index() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "naïve words, don't split")
+        .unwrap();
+    let mut buf = String::new();
+    let words = doc.text_words(&text, &mut buf).unwrap().collect::<Vec<_>>();
+    assert_eq!(
+        words,
+        vec![(0, "naïve"), (6, "words"), (13, "don't"), (19, "split")]
+    );
+    assert_eq!(buf, doc.text(&text).unwrap());
+}
+
+#[test]
+fn op_observer_is_called_in_op_order() {
+    use automerge::transaction::OpObserver;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl Recorder {
+        fn log(&self, event: String) {
+            self.0.borrow_mut().push(event);
+        }
+    }
+
+    impl OpObserver for Recorder {
+        fn put(&mut self, _obj: ObjId, prop: Prop, value: Value<'_>) {
+            self.log(format!("put {} {}", prop, value));
+        }
+        fn insert(&mut self, _obj: ObjId, index: usize, value: Value<'_>) {
+            self.log(format!("insert {} {}", index, value));
+        }
+        fn delete(&mut self, _obj: ObjId, prop: Prop) {
+            self.log(format!("delete {}", prop));
+        }
+        fn increment(&mut self, _obj: ObjId, prop: Prop, by: i64) {
+            self.log(format!("increment {} {}", prop, by));
+        }
+        fn mark(&mut self, _obj: ObjId, mark: &Mark<'_>) {
+            self.log(format!("mark {} {}..{}", mark.name(), mark.start, mark.end));
+        }
+        fn splice_text(&mut self, _obj: ObjId, index: usize, text: &str) {
+            self.log(format!("splice_text {} {}", index, text));
+        }
+    }
+
+    let mut doc = Automerge::new();
+    let recorder = Recorder::default();
+    let mut tx = doc.transaction().with_observer(recorder.clone());
+    tx.put(ROOT, "counter", ScalarValue::counter(10)).unwrap();
+    tx.increment(ROOT, "counter", 5).unwrap();
+    // putting the same value again doesn't create an op
+    tx.put(ROOT, "key", 1).unwrap();
+    tx.put(ROOT, "key", 1).unwrap();
+    let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+    tx.insert(&list, 0, "a").unwrap();
+    tx.splice(&list, 0, 1, vec!["b".into(), "c".into()])
+        .unwrap();
+    let text = tx.put_object(ROOT, "text", ObjType::Text).unwrap();
+    tx.splice_text(&text, 0, 0, "hello").unwrap();
+    tx.mark(
+        &text,
+        Mark::new("bold".into(), true, 0, 2),
+        ExpandMark::None,
+    )
+    .unwrap();
+    tx.delete(ROOT, "key").unwrap();
+    let (_, patch_log) = tx.commit();
+
+    assert_eq!(
+        *recorder.0.borrow(),
+        vec![
+            "put counter Counter: 10",
+            "increment counter 5",
+            "put key 1",
+            "put list list",
+            "insert 0 \"a\"",
+            "delete 0",
+            "insert 0 \"b\"",
+            "insert 1 \"c\"",
+            "put text text",
+            "splice_text 0 hello",
+            "mark bold 0..2",
+            "delete key",
+        ]
+    );
+    // the events recorded for the observer don't leak into the inactive patch log
+    assert_eq!(doc.make_patches(&mut patch_log.clone()), Vec::new());
+}
+
+#[test]
+fn path_to_object_follows_current_indices() {
+    let mut doc = AutoCommit::new();
+    let config = doc.put_object(ROOT, "config", ObjType::Map).unwrap();
+    let items = doc.put_object(&config, "items", ObjType::List).unwrap();
+    for i in 0..4 {
+        doc.insert(&items, i, i as i64).unwrap();
+    }
+    let item = doc.insert_object(&items, 3, ObjType::Map).unwrap();
+    let title = doc.put_object(&item, "title", ObjType::Text).unwrap();
+
+    let expected = |index: usize| {
+        vec![
+            (ROOT, Prop::from("config")),
+            (config.clone(), Prop::from("items")),
+            (items.clone(), Prop::from(index)),
+            (item.clone(), Prop::from("title")),
+        ]
+    };
+    assert_eq!(doc.path_to_object(&title).unwrap(), expected(3));
+
+    doc.insert(&items, 0, "first").unwrap();
+    assert_eq!(doc.path_to_object(&title).unwrap(), expected(4));
+    assert_eq!(doc.path_to_object(&ROOT).unwrap(), vec![]);
+}
+
+#[test]
+fn get_all_returns_conflicting_values_with_the_winner_last() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(&[1][..]));
+    doc1.put(ROOT, "title", "draft").unwrap();
+    assert_eq!(
+        doc1.get_all(ROOT, "title")
+            .unwrap()
+            .into_iter()
+            .map(|(value, _)| value)
+            .collect::<Vec<_>>(),
+        vec![Value::from("draft")]
+    );
+
+    let mut doc2 = doc1.fork().with_actor(ActorId::from(&[2][..]));
+    doc1.put(ROOT, "title", "mine").unwrap();
+    doc2.put(ROOT, "title", "theirs").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    let values = doc1.get_all(ROOT, "title").unwrap();
+    assert_eq!(
+        values
+            .iter()
+            .map(|(value, _)| value.clone())
+            .collect::<Vec<_>>(),
+        vec![Value::from("mine"), Value::from("theirs")]
+    );
+    assert_eq!(doc1.get(ROOT, "title").unwrap().as_ref(), values.last());
+    assert_eq!(doc1.get_all(ROOT, "missing").unwrap(), vec![]);
+}
+
+#[test]
+fn get_all_at_reports_conflicts_resolved_later() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(&[1][..]));
+    let mut doc2 = AutoCommit::new().with_actor(ActorId::from(&[2][..]));
+    doc1.put(ROOT, "colour", "red").unwrap();
+    doc2.put(ROOT, "colour", "blue").unwrap();
+    doc1.merge(&mut doc2).unwrap();
+    let conflicted = doc1.get_heads();
+
+    doc1.put(ROOT, "colour", "purple").unwrap();
+
+    let values = |values: Vec<(Value<'_>, ObjId)>| {
+        values
+            .into_iter()
+            .map(|(value, _)| value.into_string().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        values(doc1.get_all_at(ROOT, "colour", &conflicted).unwrap()),
+        vec!["red", "blue"]
+    );
+    assert_eq!(
+        values(doc1.get_all(ROOT, "colour").unwrap()),
+        vec!["purple"]
+    );
+}
+
 /*
 #[test]
 fn conflicting_unicode_text_with_different_widths() -> Result<(), AutomergeError> {
@@ -1753,3 +2642,56 @@ fn conflicting_unicode_text_with_different_widths() -> Result<(), AutomergeError
     Ok(())
 }
 */
+
+#[test]
+fn autocommit_reads_see_pending_ops_without_committing() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    let text = doc.insert_object(&list, 0, ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    doc.mark(
+        &text,
+        Mark::new("bold".into(), true, 0, 2),
+        ExpandMark::None,
+    )
+    .unwrap();
+    let pending = doc.pending_ops();
+    assert!(pending > 0);
+
+    assert_eq!(doc.text_spans(&text).unwrap().len(), 2);
+    assert_eq!(doc.get_marks_at_index(&text, 1).unwrap().len(), 1);
+    let cursor = doc.text_to_cursor(&text, 3).unwrap();
+    assert_eq!(doc.get_cursor_position(&text, &cursor, None).unwrap(), 3);
+    assert_eq!(
+        doc.objects_of_type(ObjType::Text).collect::<Vec<_>>(),
+        vec![text.clone()]
+    );
+    assert_eq!(
+        doc.path_to_object(&text).unwrap(),
+        vec![(ROOT, Prop::from("list")), (list.clone(), Prop::Seq(0))]
+    );
+    #[cfg(feature = "json")]
+    assert!(doc.text_to_quill_delta(&text).unwrap().contains("llo"));
+    let mut texts = 0;
+    struct CountText<'a>(&'a mut usize);
+    impl automerge::DocumentVisitor for CountText<'_> {
+        fn text(&mut self, _prop: &Prop, _text: &str, _obj: &ObjId) {
+            *self.0 += 1;
+        }
+    }
+    doc.walk(CountText(&mut texts));
+    assert_eq!(texts, 1);
+    assert_eq!(doc.op_count(), pending);
+    #[cfg(feature = "msgpack")]
+    assert!(!doc.to_msgpack().unwrap().is_empty());
+    #[cfg(feature = "cbor")]
+    assert!(!doc.export_cbor().is_empty());
+    #[cfg(feature = "json")]
+    assert_eq!(doc.list_to_json(&list).unwrap()[0], "hello");
+    #[cfg(feature = "json")]
+    assert_eq!(doc.to_json_typed()["list"][0]["__am_type"], "text");
+    #[cfg(feature = "json")]
+    assert_eq!(doc.export_json()["list"][0], "hello");
+
+    assert_eq!(doc.pending_ops(), pending);
+}